@@ -0,0 +1,482 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use ethers::{
+    signers::Signer,
+    types::{
+        transaction::{eip2718::TypedTransaction, eip712::Eip712},
+        Address, Bytes, Signature, TransactionReceipt, H256, U256, U64,
+    },
+    utils::keccak256,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+pub const DEFAULT_AUDIT_PATH: &str = "pow-audit.log";
+
+// 已签名交易的内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SignedTx {
+    tx_hash: H256,
+    from: Address,
+    nonce: Option<U256>,
+    to: Option<Address>,
+    selector: Option<Bytes>,
+    calldata: Bytes,
+    value: U256,
+    gas: Option<U256>,
+    gas_price: Option<U256>,
+    max_priority_fee_per_gas: Option<U256>,
+    chain_id: Option<U64>,
+}
+
+// 审计事件：签名时记录交易内容，收到收据时追加执行结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum AuditEvent {
+    Signed(Box<SignedTx>),
+    Receipt {
+        tx_hash: H256,
+        status: Option<U64>,
+        block_number: Option<U64>,
+        gas_used: Option<U256>,
+        effective_gas_price: Option<U256>,
+    },
+}
+
+// 参与哈希计算的字段
+#[derive(Serialize)]
+struct EntryBody<'a> {
+    seq: u64,
+    timestamp: i64,
+    prev: H256,
+    event: &'a AuditEvent,
+}
+
+// 日志中的一行；hash = keccak256(prev || body)
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AuditEntry {
+    seq: u64,
+    timestamp: i64,
+    prev: H256,
+    event: AuditEvent,
+    hash: H256,
+}
+
+impl AuditEntry {
+    fn compute_hash(&self) -> Result<H256> {
+        let body = serde_json::to_vec(&EntryBody {
+            seq: self.seq,
+            timestamp: self.timestamp,
+            prev: self.prev,
+            event: &self.event,
+        })?;
+        Ok(H256::from(keccak256(
+            [self.prev.as_bytes(), &body].concat(),
+        )))
+    }
+}
+
+struct Tail {
+    file: File,
+    next_seq: u64,
+    prev: H256,
+}
+
+// 只追加的哈希链审计日志，每条记录包含上一条的哈希，篡改任一行都会被 `audit verify` 发现
+pub struct AuditLog {
+    path: PathBuf,
+    tail: Mutex<Tail>,
+}
+
+impl AuditLog {
+    pub fn open(path: &Path) -> Result<Self> {
+        let open_error = |e: std::io::Error| {
+            anyhow!(
+                "无法打开审计日志 / Cannot open audit log {}: {}",
+                path.display(),
+                e
+            )
+        };
+
+        // 从最后一条记录接续哈希链
+        let mut next_seq = 0;
+        let mut prev = H256::zero();
+        if path.exists() {
+            let reader = BufReader::new(File::open(path).map_err(open_error)?);
+            if let Some(line) = reader.lines().map_while(|line| line.ok()).last() {
+                let entry: AuditEntry = serde_json::from_str(&line).map_err(|e| {
+                    anyhow!(
+                        "审计日志最后一行损坏 / Last audit log entry is corrupt {}: {}",
+                        path.display(),
+                        e
+                    )
+                })?;
+                next_seq = entry.seq + 1;
+                prev = entry.hash;
+            }
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(open_error)?;
+        Ok(AuditLog {
+            path: path.to_path_buf(),
+            tail: Mutex::new(Tail {
+                file,
+                next_seq,
+                prev,
+            }),
+        })
+    }
+
+    // 追加一条记录并同步到磁盘
+    fn append(&self, event: AuditEvent) -> Result<()> {
+        let mut tail = self.tail.lock().unwrap();
+        let mut entry = AuditEntry {
+            seq: tail.next_seq,
+            timestamp: chrono::Utc::now().timestamp(),
+            prev: tail.prev,
+            event,
+            hash: H256::zero(),
+        };
+        entry.hash = entry.compute_hash()?;
+
+        let mut line = serde_json::to_vec(&entry)?;
+        line.push(b'\n');
+        tail.file.write_all(&line)?;
+        tail.file.sync_data()?;
+
+        tail.next_seq += 1;
+        tail.prev = entry.hash;
+        Ok(())
+    }
+
+    pub fn record_signed(
+        &self,
+        from: Address,
+        tx: &TypedTransaction,
+        signature: &Signature,
+    ) -> Result<()> {
+        let calldata = tx.data().cloned().unwrap_or_default();
+        let (gas_price, max_priority_fee_per_gas) = match tx {
+            TypedTransaction::Eip1559(tx) => (tx.max_fee_per_gas, tx.max_priority_fee_per_gas),
+            _ => (tx.gas_price(), None),
+        };
+        self.append(AuditEvent::Signed(Box::new(SignedTx {
+            tx_hash: H256::from(keccak256(tx.rlp_signed(signature))),
+            from,
+            nonce: tx.nonce().copied(),
+            to: tx.to_addr().copied(),
+            selector: calldata.get(..4).map(|s| Bytes::from(s.to_vec())),
+            calldata,
+            value: tx.value().copied().unwrap_or_default(),
+            gas: tx.gas().copied(),
+            gas_price,
+            max_priority_fee_per_gas,
+            chain_id: tx.chain_id(),
+        })))
+    }
+
+    pub fn record_receipt(&self, receipt: &TransactionReceipt) -> Result<()> {
+        self.append(AuditEvent::Receipt {
+            tx_hash: receipt.transaction_hash,
+            status: receipt.status,
+            block_number: receipt.block_number,
+            gas_used: receipt.gas_used,
+            effective_gas_price: receipt.effective_gas_price,
+        })
+    }
+}
+
+// 校验整个哈希链，返回记录条数
+pub fn verify(path: &Path) -> Result<u64> {
+    let file = File::open(path).map_err(|e| {
+        anyhow!(
+            "无法打开审计日志 / Cannot open audit log {}: {}",
+            path.display(),
+            e
+        )
+    })?;
+
+    let mut expected_seq = 0;
+    let mut prev = H256::zero();
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line_no = index + 1;
+        let line = line?;
+        let entry: AuditEntry = serde_json::from_str(&line).map_err(|e| {
+            anyhow!(
+                "第 {} 行格式错误 / Line {} is malformed: {}",
+                line_no,
+                line_no,
+                e
+            )
+        })?;
+
+        if entry.seq != expected_seq {
+            return Err(anyhow!(
+                "第 {} 行序号为 {}，应为 {}，记录被删除或重排 / Line {} has sequence {}, expected {}; entries were removed or reordered",
+                line_no, entry.seq, expected_seq, line_no, entry.seq, expected_seq
+            ));
+        }
+        if entry.prev != prev {
+            return Err(anyhow!(
+                "第 {} 行与上一条记录的哈希不连续 / Line {} does not chain to the previous entry",
+                line_no,
+                line_no
+            ));
+        }
+        if entry.compute_hash()? != entry.hash {
+            return Err(anyhow!(
+                "第 {} 行内容与哈希不符，记录已被修改 / Line {} does not match its hash; the entry was modified",
+                line_no,
+                line_no
+            ));
+        }
+
+        expected_seq += 1;
+        prev = entry.hash;
+    }
+    Ok(expected_seq)
+}
+
+// 签名成功后、交易发出前写入审计日志；写入失败时不返回签名
+#[derive(Clone)]
+pub struct AuditSigner<S> {
+    inner: S,
+    log: Arc<AuditLog>,
+}
+
+impl<S> AuditSigner<S> {
+    pub fn new(inner: S, log: Arc<AuditLog>) -> Self {
+        AuditSigner { inner, log }
+    }
+}
+
+impl<S: std::fmt::Debug> std::fmt::Debug for AuditSigner<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuditSigner")
+            .field("inner", &self.inner)
+            .field("log", &self.log.path)
+            .finish()
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum AuditSignerError<E: std::error::Error> {
+    #[error("无法写入审计日志 / Cannot write audit log: {0}")]
+    Log(String),
+    #[error(transparent)]
+    Signer(E),
+}
+
+#[async_trait]
+impl<S: Signer> Signer for AuditSigner<S> {
+    type Error = AuditSignerError<S::Error>;
+
+    async fn sign_message<T: Send + Sync + AsRef<[u8]>>(
+        &self,
+        message: T,
+    ) -> Result<Signature, Self::Error> {
+        self.inner
+            .sign_message(message)
+            .await
+            .map_err(AuditSignerError::Signer)
+    }
+
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature, Self::Error> {
+        let signature = self
+            .inner
+            .sign_transaction(tx)
+            .await
+            .map_err(AuditSignerError::Signer)?;
+
+        // 与 SignerMiddleware 一致，签名时补上链ID
+        let mut tx = tx.clone();
+        if tx.chain_id().is_none() {
+            tx.set_chain_id(self.inner.chain_id());
+        }
+        self.log
+            .record_signed(self.inner.address(), &tx, &signature)
+            .map_err(|e| AuditSignerError::Log(e.to_string()))?;
+        Ok(signature)
+    }
+
+    async fn sign_typed_data<T: Eip712 + Send + Sync>(
+        &self,
+        payload: &T,
+    ) -> Result<Signature, Self::Error> {
+        self.inner
+            .sign_typed_data(payload)
+            .await
+            .map_err(AuditSignerError::Signer)
+    }
+
+    fn address(&self) -> Address {
+        self.inner.address()
+    }
+
+    fn chain_id(&self) -> u64 {
+        self.inner.chain_id()
+    }
+
+    fn with_chain_id<T: Into<u64>>(self, chain_id: T) -> Self {
+        AuditSigner {
+            inner: self.inner.with_chain_id(chain_id),
+            log: self.log,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::{signers::LocalWallet, types::TransactionRequest};
+
+    // 测试结束时删除的临时日志
+    struct TempLog(PathBuf);
+
+    impl TempLog {
+        fn new() -> Self {
+            TempLog(std::env::temp_dir().join(format!(
+                "pow-client-audit-test-{}.log",
+                rand::random::<u64>()
+            )))
+        }
+
+        fn lines(&self) -> Vec<String> {
+            std::fs::read_to_string(&self.0)
+                .unwrap()
+                .lines()
+                .map(str::to_string)
+                .collect()
+        }
+
+        fn write(&self, lines: &[String]) {
+            std::fs::write(&self.0, lines.join("\n") + "\n").unwrap();
+        }
+
+        fn verify_error(&self) -> String {
+            verify(&self.0).unwrap_err().to_string()
+        }
+    }
+
+    impl Drop for TempLog {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    fn receipt(byte: u8) -> TransactionReceipt {
+        TransactionReceipt {
+            transaction_hash: H256::repeat_byte(byte),
+            status: Some(U64::one()),
+            block_number: Some(U64::from(byte)),
+            ..Default::default()
+        }
+    }
+
+    // 一笔签名记录与三条收据记录
+    fn sample_log() -> TempLog {
+        let log = TempLog::new();
+        let audit = AuditLog::open(&log.0).unwrap();
+        let wallet = LocalWallet::from_bytes(&[1; 32]).unwrap();
+        let tx: TypedTransaction = TransactionRequest::new()
+            .to(Address::repeat_byte(0x51))
+            .data(vec![1, 2, 3, 4])
+            .nonce(0)
+            .gas(21_000)
+            .gas_price(1)
+            .chain_id(1)
+            .into();
+        let signature = wallet.sign_transaction_sync(&tx).unwrap();
+        audit
+            .record_signed(wallet.address(), &tx, &signature)
+            .unwrap();
+        for byte in 1..=3 {
+            audit.record_receipt(&receipt(byte)).unwrap();
+        }
+        log
+    }
+
+    #[test]
+    fn intact_log_verifies() {
+        let log = sample_log();
+        assert_eq!(verify(&log.0).unwrap(), 4);
+    }
+
+    #[test]
+    fn reopened_log_continues_the_chain() {
+        let log = sample_log();
+        AuditLog::open(&log.0)
+            .unwrap()
+            .record_receipt(&receipt(4))
+            .unwrap();
+        assert_eq!(verify(&log.0).unwrap(), 5);
+    }
+
+    #[test]
+    fn modified_line_is_detected() {
+        let log = sample_log();
+        let mut lines = log.lines();
+        lines[2] = lines[2].replace("\"status\":\"0x1\"", "\"status\":\"0x0\"");
+        log.write(&lines);
+        assert!(log
+            .verify_error()
+            .contains("Line 3 does not match its hash"));
+    }
+
+    #[test]
+    fn rehashed_line_breaks_the_chain() {
+        let log = sample_log();
+        let mut lines = log.lines();
+        let mut entry: AuditEntry = serde_json::from_str(&lines[1]).unwrap();
+        entry.timestamp += 1;
+        entry.hash = entry.compute_hash().unwrap();
+        lines[1] = serde_json::to_string(&entry).unwrap();
+        log.write(&lines);
+        assert!(log
+            .verify_error()
+            .contains("Line 3 does not chain to the previous entry"));
+    }
+
+    #[test]
+    fn deleted_line_is_detected() {
+        let log = sample_log();
+        let mut lines = log.lines();
+        lines.remove(1);
+        log.write(&lines);
+        assert!(log
+            .verify_error()
+            .contains("Line 2 has sequence 2, expected 1"));
+    }
+
+    #[test]
+    fn reordered_lines_are_detected() {
+        let log = sample_log();
+        let mut lines = log.lines();
+        lines.swap(1, 2);
+        log.write(&lines);
+        assert!(log
+            .verify_error()
+            .contains("Line 2 has sequence 2, expected 1"));
+    }
+
+    #[test]
+    fn corrupt_last_line_prevents_appending() {
+        let log = sample_log();
+        let mut lines = log.lines();
+        lines.push("{".to_string());
+        log.write(&lines);
+        assert!(AuditLog::open(&log.0).is_err());
+        assert!(log.verify_error().contains("Line 5 is malformed"));
+    }
+}
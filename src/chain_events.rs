@@ -0,0 +1,284 @@
+use anyhow::{anyhow, Result};
+use colored::*;
+use ethers::{
+    contract::{parse_log, EthEvent},
+    prelude::*,
+};
+use futures::{Stream, StreamExt};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use tokio::sync::{broadcast, watch};
+
+use crate::contract::NewMiningTaskFilter;
+use crate::rpc_pool::{BroadcastResult, RpcPool, Transport};
+
+// 没有订阅时轮询最新区块的间隔
+const HEAD_POLL_INTERVAL: Duration = Duration::from_secs(3);
+// 订阅断开后，轮询多久再尝试重新订阅
+const RESUBSCRIBE_INTERVAL: Duration = Duration::from_secs(30);
+// 等待收据时即使没有新区块也至少隔这么久查询一次
+const RECEIPT_RECHECK_INTERVAL: Duration = Duration::from_secs(3);
+// 订阅保持连接但这么久（数个出块间隔）没有推送新区块时视为停滞，改为轮询
+const HEAD_STALL_TIMEOUT: Duration = Duration::from_secs(30);
+
+// 链上事件：最新区块高度与本钱包的 NewMiningTask 事件。
+// 优先通过 WebSocket/IPC 订阅获取，不可用时自动退回 HTTP 轮询区块高度
+pub struct ChainEvents {
+    head: watch::Sender<u64>,
+    tasks: broadcast::Sender<NewMiningTaskFilter>,
+    subscribed: AtomicBool,
+}
+
+impl ChainEvents {
+    pub fn spawn(pool: RpcPool, contract: Address, wallet: Address) -> Arc<Self> {
+        let (head, _) = watch::channel(0);
+        let (tasks, _) = broadcast::channel(16);
+        let events = Arc::new(ChainEvents {
+            head,
+            tasks,
+            subscribed: AtomicBool::new(false),
+        });
+
+        let filter = Filter::new()
+            .address(contract)
+            .topic0(NewMiningTaskFilter::signature())
+            .topic1(H256::from(wallet));
+        tokio::spawn(events.clone().run(pool, filter));
+
+        events
+    }
+
+    // 订阅是否正在工作；为 false 时 NewMiningTask 事件不会推送，需要自行轮询
+    pub fn is_subscribed(&self) -> bool {
+        self.subscribed.load(Ordering::SeqCst)
+    }
+
+    pub fn subscribe_tasks(&self) -> broadcast::Receiver<NewMiningTaskFilter> {
+        self.tasks.subscribe()
+    }
+
+    // 等待交易上链，每出现新区块检查一次收据；超时返回 Ok(None)
+    pub async fn wait_for_receipt<M: Middleware>(
+        &self,
+        client: &M,
+        tx_hash: H256,
+        timeout: Duration,
+    ) -> Result<Option<TransactionReceipt>> {
+        let receipt = self
+            .wait_until(timeout, || async {
+                // 查询失败视为暂时性错误，下一个区块再试
+                match client.get_transaction_receipt(tx_hash).await {
+                    Ok(Some(receipt)) if receipt.block_number.is_some() => Some(receipt),
+                    _ => None,
+                }
+            })
+            .await;
+        Ok(receipt)
+    }
+
+    // 等待广播的交易上链，并报告最先确认打包的节点
+    pub async fn wait_for_broadcast(
+        &self,
+        pool: &RpcPool,
+        broadcast: &BroadcastResult,
+        timeout: Duration,
+    ) -> Result<Option<TransactionReceipt>> {
+        let inclusion = self
+            .wait_until(timeout, || pool.first_inclusion(broadcast))
+            .await;
+
+        Ok(inclusion.map(|(url, receipt)| {
+            console_println!(
+                "{}",
+                format!(
+                    "节点 {} 最先报告交易 {:?} 已打包 / Endpoint {} first reported inclusion of {:?}",
+                    url, broadcast.tx_hash, url, broadcast.tx_hash
+                )
+                .cyan()
+            );
+            receipt
+        }))
+    }
+
+    // 每出现新区块（或最长间隔 RECEIPT_RECHECK_INTERVAL）执行一次检查，直到有结果或超时
+    async fn wait_until<T, F, Fut>(&self, timeout: Duration, mut check: F) -> Option<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = Option<T>>,
+    {
+        let mut head = self.head.subscribe();
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            if let Some(result) = check().await {
+                return Some(result);
+            }
+
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return None;
+            }
+            let _ =
+                tokio::time::timeout(remaining.min(RECEIPT_RECHECK_INTERVAL), head.changed()).await;
+        }
+    }
+
+    fn update_head(&self, block: u64) {
+        self.head.send_if_modified(|head| {
+            if block > *head {
+                *head = block;
+                true
+            } else {
+                false
+            }
+        });
+    }
+
+    async fn run(self: Arc<Self>, pool: RpcPool, filter: Filter) {
+        let provider = Provider::new(pool.clone());
+
+        loop {
+            if let Some((url, transport)) = pool.pubsub_client() {
+                let result = match transport {
+                    Transport::Ws(ws) => self.follow(Provider::new(ws), &filter).await,
+                    Transport::Ipc(ipc) => self.follow(Provider::new(ipc), &filter).await,
+                    Transport::Http(_) => Ok(()),
+                };
+                self.subscribed.store(false, Ordering::SeqCst);
+                if let Err(e) = result {
+                    console_eprintln!(
+                        warn: "{}",
+                        format!(
+                            "订阅中断，改为轮询 / Subscription to {} lost, falling back to polling: {}",
+                            url, e
+                        )
+                        .yellow()
+                    );
+                }
+            }
+
+            // 轮询最新区块，一段时间后再尝试订阅
+            let retry_at = Instant::now() + RESUBSCRIBE_INTERVAL;
+            while Instant::now() < retry_at {
+                if let Ok(block) = provider.get_block_number().await {
+                    self.update_head(block.as_u64());
+                }
+                tokio::time::sleep(HEAD_POLL_INTERVAL).await;
+            }
+        }
+    }
+
+    async fn follow<P: PubsubClient>(&self, provider: Provider<P>, filter: &Filter) -> Result<()> {
+        let blocks = provider.subscribe_blocks().await?;
+        let logs = provider.subscribe_logs(filter).await?;
+        if !self.subscribed.swap(true, Ordering::SeqCst) {
+            console_println!(
+                "{}",
+                "已订阅新区块与挖矿任务事件 / Subscribed to new heads and mining task events"
+                    .green()
+            );
+        }
+        self.follow_streams(blocks, logs, HEAD_STALL_TIMEOUT).await
+    }
+
+    // 处理订阅推送，订阅关闭或超过 stall 没有新区块时返回错误，由 run 退回轮询
+    async fn follow_streams<B, L>(&self, mut blocks: B, mut logs: L, stall: Duration) -> Result<()>
+    where
+        B: Stream<Item = Block<TxHash>> + Unpin,
+        L: Stream<Item = Log> + Unpin,
+    {
+        let stalled = tokio::time::sleep(stall);
+        tokio::pin!(stalled);
+
+        loop {
+            tokio::select! {
+                block = blocks.next() => {
+                    let block = block.ok_or_else(|| anyhow!("区块订阅已关闭 / Block subscription closed"))?;
+                    if let Some(number) = block.number {
+                        self.update_head(number.as_u64());
+                    }
+                    stalled.as_mut().reset(tokio::time::Instant::now() + stall);
+                }
+                _ = &mut stalled => {
+                    return Err(anyhow!(
+                        "{} 秒内没有收到新区块 / No new heads for {} seconds",
+                        stall.as_secs(),
+                        stall.as_secs()
+                    ));
+                }
+                log = logs.next() => {
+                    let log = log.ok_or_else(|| anyhow!("日志订阅已关闭 / Log subscription closed"))?;
+                    if let Ok(event) = parse_log::<NewMiningTaskFilter>(log) {
+                        // 没有接收者时发送失败，忽略即可
+                        let _ = self.tasks.send(event);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::stream;
+
+    fn events() -> ChainEvents {
+        ChainEvents {
+            head: watch::channel(0).0,
+            tasks: broadcast::channel(16).0,
+            subscribed: AtomicBool::new(true),
+        }
+    }
+
+    fn block(number: u64) -> Block<TxHash> {
+        Block {
+            number: Some(number.into()),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn silent_subscription_is_reported_as_stalled() {
+        let events = events();
+        // 推送两个区块后保持连接但不再推送
+        let blocks = stream::iter([block(5), block(6)]).chain(stream::pending());
+        let started = tokio::time::Instant::now();
+
+        let error = events
+            .follow_streams(blocks, stream::pending::<Log>(), HEAD_STALL_TIMEOUT)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("No new heads"));
+        assert!(started.elapsed() >= HEAD_STALL_TIMEOUT);
+        assert_eq!(*events.head.borrow(), 6);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn steady_heads_keep_the_subscription() {
+        let events = events();
+        // 每个区块间隔小于停滞时间，直到订阅关闭都不会判定停滞
+        let blocks = stream::iter(1..=10u64).then(|number| async move {
+            tokio::time::sleep(HEAD_STALL_TIMEOUT / 2).await;
+            block(number)
+        });
+        let started = tokio::time::Instant::now();
+
+        let error = events
+            .follow_streams(
+                Box::pin(blocks),
+                stream::pending::<Log>(),
+                HEAD_STALL_TIMEOUT,
+            )
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("closed"));
+        assert!(started.elapsed() >= HEAD_STALL_TIMEOUT * 5);
+        assert_eq!(*events.head.borrow(), 10);
+    }
+}
@@ -0,0 +1,473 @@
+use anyhow::{anyhow, Result};
+use colored::*;
+use ethers::{
+    providers::Provider,
+    types::{Address, Bytes, H256},
+    utils::keccak256,
+};
+use std::sync::Arc;
+
+use crate::{
+    code_pins::CodePins,
+    contract::MiningContract,
+    rpc_pool::{ChainIdentity, RpcPool},
+};
+
+// PUSH4 操作码，Solidity 的函数分发表用它压入选择器
+const PUSH4: u8 = 0x63;
+
+// 启动时的链与合约校验选项
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VerifyOptions {
+    // 链ID不符时仅警告
+    pub allow_chain_mismatch: bool,
+    // 合约代码校验失败时仅警告
+    pub allow_unverified_contract: bool,
+    // 指定期望的合约代码哈希，未指定时使用首次运行时记录的哈希
+    pub code_hash: Option<H256>,
+}
+
+// 在签名任何交易前确认每个节点连接的是正确的链和正确的挖矿合约，
+// 防止配置错误或恶意的 RPC 节点诱导我们向错误的合约签名；
+// 不符的节点被隔离，启动时未响应的节点在健康检查中核对通过后才使用。
+// 合约代码哈希采用首次信任 (trust on first use)：未用 --contract-code-hash 指定时，
+// 首次运行以各节点一致返回的代码为准记入代码哈希记录文件，之后代码与记录不符即拒绝；
+// 因此首次运行时节点本身必须可信
+pub async fn verify(
+    pool: &RpcPool,
+    pins: &CodePins,
+    chain_id: u64,
+    address: Address,
+    options: VerifyOptions,
+) -> Result<()> {
+    let mut answers = Vec::new();
+    for (url, result) in pool.identify(address).await {
+        match result {
+            Ok((endpoint_chain_id, code)) => answers.push((url, endpoint_chain_id, code)),
+            Err(e) => console_eprintln!(
+                warn: "{}",
+                format!(
+                    "节点 {} 暂时无法核对，恢复后再核对 / Endpoint {} could not be verified yet: {}",
+                    url, url, e
+                )
+                .yellow()
+            ),
+        }
+    }
+    if answers.is_empty() {
+        return Err(anyhow!(
+            "无法从任何节点获取链ID与合约代码，无法确认所连接的网络 / Could not get chain ID and contract code from any endpoint to verify the network"
+        ));
+    }
+
+    let mut chain_ids: Vec<u64> = answers.iter().map(|(_, id, _)| *id).collect();
+    chain_ids.sort();
+    chain_ids.dedup();
+    for endpoint_chain_id in chain_ids {
+        console_println!(
+            "{}",
+            format!(
+                "连接到链ID: {} / Connected to chain ID: {}",
+                endpoint_chain_id, endpoint_chain_id
+            )
+            .green()
+        );
+    }
+    if options.allow_chain_mismatch && answers.iter().any(|(_, id, _)| *id != chain_id) {
+        console_println!(
+            warn: "{}",
+            format!(
+                "警告：有节点链ID与设置的 {} 不符，已通过 --allow-chain-mismatch 忽略 / Warning: some endpoints do not match configured chain ID {}, ignored due to --allow-chain-mismatch",
+                chain_id, chain_id
+            )
+            .yellow()
+        );
+    }
+    let on_chain: Vec<&Bytes> = answers
+        .iter()
+        .filter(|(_, id, _)| options.allow_chain_mismatch || *id == chain_id)
+        .map(|(_, _, code)| code)
+        .collect();
+
+    // 期望的代码哈希：命令行指定 → 首次运行时记录的
+    let expected = options.code_hash.or_else(|| pins.get(chain_id, address));
+    let mut problems = Vec::new();
+    let mut hashes: Vec<H256> = on_chain
+        .iter()
+        .map(|code| H256::from(keccak256(code)))
+        .collect();
+    hashes.sort();
+    hashes.dedup();
+    for hash in &hashes {
+        console_println!(
+            "{}",
+            format!("合约代码哈希 / Contract code hash: {:?}", hash).cyan()
+        );
+    }
+    // 首次运行时各节点必须返回相同的代码，才以此为准
+    let reference = match (expected, hashes.as_slice()) {
+        (Some(hash), _) => Some(hash),
+        (None, [hash]) => Some(*hash),
+        (None, _) => {
+            problems.push(
+                "各节点返回的合约代码不一致，无法确定以哪个为准 / endpoints disagree on the contract code, cannot tell which one to trust"
+                    .to_string(),
+            );
+            None
+        }
+    };
+    if let Some(expected) = expected.filter(|expected| !hashes.contains(expected)) {
+        problems.push(format!(
+            "没有节点返回期望的代码哈希 {:?} / no endpoint returned the expected code hash {:?}",
+            expected, expected
+        ));
+    }
+
+    let identity = ChainIdentity {
+        chain_id: (!options.allow_chain_mismatch).then_some(chain_id),
+        contract: address,
+        code_hash: reference.filter(|_| !options.allow_unverified_contract),
+    };
+    let mut verified = None;
+    for (url, endpoint_chain_id, code) in &answers {
+        let mismatch = identity.mismatch(*endpoint_chain_id, code);
+        if mismatch.is_none() && verified.is_none() {
+            verified = Some(code.clone());
+        }
+        pool.set_verified(url, mismatch);
+    }
+    pool.set_identity(identity);
+
+    let Some(code) = verified else {
+        return Err(anyhow!(
+            "没有节点通过链ID与合约代码核对，拒绝继续 / No endpoint passed the chain ID and contract code checks, refusing to continue"
+        ));
+    };
+    // 之后的只读调用只会发往核对通过的节点
+    problems.extend(check_contract(pool, address, &code).await);
+
+    // 首次运行且其他检查均通过时记录代码哈希，之后代码变化即拒绝
+    if expected.is_none() && problems.is_empty() {
+        if let Some(hash) = reference {
+            pins.pin(chain_id, address, hash)?;
+            console_println!(
+                warn: "{}",
+                format!(
+                    "首次记录合约代码哈希，之后将以此为准；删除 {} 会重新记录 / Pinned contract code hash on first use; deleting {} resets the pin",
+                    pins.path().display(),
+                    pins.path().display()
+                )
+                .yellow()
+            );
+        }
+    }
+
+    if problems.is_empty() {
+        console_println!("{}", "合约校验通过 / Contract verification passed".green());
+        return Ok(());
+    }
+
+    for problem in &problems {
+        console_eprintln!(
+            error: "{}",
+            format!("合约校验失败 / Contract check failed: {}", problem).red()
+        );
+    }
+    if options.allow_unverified_contract {
+        console_eprintln!(
+            warn: "{}",
+            "已通过 --allow-unverified-contract 忽略合约校验失败 / Ignoring contract verification failures due to --allow-unverified-contract"
+                .yellow()
+        );
+        return Ok(());
+    }
+    Err(anyhow!(
+        "合约 {:?} 未通过校验，拒绝继续；确认无误后可使用 --allow-unverified-contract / Contract {:?} failed verification, refusing to continue; use --allow-unverified-contract if you are sure",
+        address,
+        address
+    ))
+}
+
+// 检查合约代码的函数分发表与只读调用，返回发现的问题
+async fn check_contract(pool: &RpcPool, address: Address, code: &Bytes) -> Vec<String> {
+    if code.is_empty() {
+        return vec![format!(
+            "合约地址上没有代码 / No code at contract address {:?}",
+            address
+        )];
+    }
+
+    let mut problems = Vec::new();
+    let contract = MiningContract::new(address, Arc::new(Provider::new(pool.clone())));
+    for (name, selector) in [
+        ("getMyTask", contract.get_my_task().tx.data().cloned()),
+        ("FREE_REWARD", contract.free_reward().tx.data().cloned()),
+    ] {
+        if !dispatches(code, selector.as_ref()) {
+            problems.push(format!(
+                "合约代码中没有 {} 的选择器 / selector for {} not found in contract code",
+                name, name
+            ));
+        }
+    }
+
+    // 用只读调用确认返回值能按 ABI 解码
+    if let Err(e) = contract.get_my_task().call().await {
+        problems.push(format!("getMyTask 调用失败 / getMyTask call failed: {}", e));
+    }
+    match contract.free_reward().call().await {
+        Ok(reward) if reward.is_zero() => {
+            problems.push("FREE_REWARD 为 0 / FREE_REWARD is zero".to_string())
+        }
+        Ok(_) => {}
+        Err(e) => problems.push(format!(
+            "FREE_REWARD 调用失败 / FREE_REWARD call failed: {}",
+            e
+        )),
+    }
+
+    problems
+}
+
+// 代码中是否存在 PUSH4 <selector>
+fn dispatches(code: &Bytes, calldata: Option<&Bytes>) -> bool {
+    let Some(selector) = calldata.and_then(|data| data.get(..4)) else {
+        return false;
+    };
+    code.windows(5)
+        .any(|window| window[0] == PUSH4 && &window[1..] == selector)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_rpc::{pool, MockRpc, Reply};
+    use ethers::{abi::Token, providers::Middleware, types::U256, utils::id};
+    use serde_json::{json, Value};
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    const CHAIN_ID: u64 = 114514;
+
+    fn address() -> Address {
+        Address::repeat_byte(0x51)
+    }
+
+    // 分发表中含 getMyTask 与 FREE_REWARD 选择器的合约代码，extra 用于制造不同的代码
+    fn code(extra: u8) -> Bytes {
+        let mut code = Vec::new();
+        for signature in ["getMyTask()", "FREE_REWARD()"] {
+            code.push(PUSH4);
+            code.extend_from_slice(&id(signature));
+        }
+        code.push(extra);
+        code.into()
+    }
+
+    fn hash(code: &Bytes) -> H256 {
+        H256::from(keccak256(code))
+    }
+
+    // 链ID为 chain_id、合约代码为 code 的节点；online 为 false 时不响应
+    async fn node(chain_id: u64, code: Bytes, online: Arc<AtomicBool>) -> MockRpc {
+        MockRpc::start(move |method, params: &Value| {
+            if !online.load(Ordering::SeqCst) {
+                return Reply::Error(-32000, "offline".to_string());
+            }
+            match method {
+                "eth_chainId" => Reply::Result(json!(format!("{:#x}", chain_id))),
+                "eth_blockNumber" => Reply::Result(json!("0x10")),
+                "eth_getCode" => Reply::Result(json!(code)),
+                "eth_call" => {
+                    let tx = &params[0];
+                    let data = tx["data"].as_str().or(tx["input"].as_str()).unwrap_or("");
+                    let output =
+                        if data.starts_with(&format!("0x{}", hex::encode(id("getMyTask()")))) {
+                            ethers::abi::encode(&[
+                                Token::Uint(U256::one()),
+                                Token::Uint(U256::one()),
+                                Token::Bool(true),
+                            ])
+                        } else {
+                            ethers::abi::encode(&[Token::Uint(U256::exp10(18))])
+                        };
+                    Reply::Result(json!(Bytes::from(output)))
+                }
+                _ => Reply::Error(-32601, "method not found".to_string()),
+            }
+        })
+        .await
+    }
+
+    async fn online(chain_id: u64, code: Bytes) -> MockRpc {
+        node(chain_id, code, Arc::new(AtomicBool::new(true))).await
+    }
+
+    // 临时文件中的代码哈希记录，测试结束后删除
+    struct TempPins(CodePins);
+
+    impl Drop for TempPins {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(self.0.path());
+        }
+    }
+
+    impl std::ops::Deref for TempPins {
+        type Target = CodePins;
+
+        fn deref(&self) -> &CodePins {
+            &self.0
+        }
+    }
+
+    fn pins() -> TempPins {
+        let path =
+            std::env::temp_dir().join(format!("pow-code-pins-{}.json", rand::random::<u64>()));
+        TempPins(CodePins::open(&path).unwrap())
+    }
+
+    fn status(pool: &RpcPool, node: &MockRpc) -> (bool, bool) {
+        let status = pool.status();
+        let ep = status.iter().find(|ep| ep.url == node.url).unwrap();
+        (ep.rejected, ep.unverified)
+    }
+
+    #[test]
+    fn dispatch_table_must_contain_selector() {
+        let selector = Bytes::from(id("getMyTask()").to_vec());
+        assert!(dispatches(&code(0), Some(&selector)));
+        assert!(!dispatches(&Bytes::from(vec![PUSH4]), Some(&selector)));
+        assert!(!dispatches(&code(0), None));
+    }
+
+    #[tokio::test]
+    async fn endpoint_on_another_chain_is_rejected() {
+        let good = online(CHAIN_ID, code(0)).await;
+        let other = online(1, code(0)).await;
+        let pool = pool(&[&good, &other]).await;
+        let pins = pins();
+
+        verify(&pool, &pins, CHAIN_ID, address(), VerifyOptions::default())
+            .await
+            .unwrap();
+
+        assert_eq!(status(&pool, &good), (false, false));
+        assert_eq!(status(&pool, &other), (true, false));
+        // 之后的请求不会发往被拒绝的节点
+        let calls = other.calls("eth_call").len();
+        Provider::new(pool.clone())
+            .get_block_number()
+            .await
+            .unwrap();
+        check_contract(&pool, address(), &code(0)).await;
+        assert_eq!(other.calls("eth_call").len(), calls);
+        assert!(other.calls("eth_blockNumber").is_empty());
+    }
+
+    #[tokio::test]
+    async fn endpoint_with_other_code_is_rejected() {
+        let good = online(CHAIN_ID, code(0)).await;
+        let forged = online(CHAIN_ID, code(1)).await;
+        let pool = pool(&[&forged, &good]).await;
+        let pins = pins();
+        let options = VerifyOptions {
+            code_hash: Some(hash(&code(0))),
+            ..Default::default()
+        };
+
+        verify(&pool, &pins, CHAIN_ID, address(), options)
+            .await
+            .unwrap();
+
+        assert_eq!(status(&pool, &good), (false, false));
+        assert_eq!(status(&pool, &forged), (true, false));
+    }
+
+    #[tokio::test]
+    async fn all_endpoints_wrong_fails() {
+        let forged = online(CHAIN_ID, code(1)).await;
+        let pool = pool(&[&forged]).await;
+        let pins = pins();
+        pins.pin(CHAIN_ID, address(), hash(&code(0))).unwrap();
+
+        assert!(
+            verify(&pool, &pins, CHAIN_ID, address(), VerifyOptions::default())
+                .await
+                .is_err()
+        );
+        assert_eq!(status(&pool, &forged), (true, false));
+    }
+
+    #[tokio::test]
+    async fn first_use_pins_only_when_endpoints_agree() {
+        let a = online(CHAIN_ID, code(0)).await;
+        let b = online(CHAIN_ID, code(1)).await;
+        let pins = pins();
+
+        let disagreeing = pool(&[&a, &b]).await;
+        assert!(verify(
+            &disagreeing,
+            &pins,
+            CHAIN_ID,
+            address(),
+            VerifyOptions::default()
+        )
+        .await
+        .is_err());
+        assert_eq!(pins.get(CHAIN_ID, address()), None);
+
+        let c = online(CHAIN_ID, code(0)).await;
+        let agreeing = pool(&[&a, &c]).await;
+        verify(
+            &agreeing,
+            &pins,
+            CHAIN_ID,
+            address(),
+            VerifyOptions::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(pins.get(CHAIN_ID, address()), Some(hash(&code(0))));
+    }
+
+    #[tokio::test]
+    async fn code_changed_since_pin_is_rejected() {
+        let pins = pins();
+        pins.pin(CHAIN_ID, address(), hash(&code(0))).unwrap();
+
+        // 所有节点一致返回新的代码也不能替换已记录的哈希
+        let a = online(CHAIN_ID, code(1)).await;
+        let b = online(CHAIN_ID, code(1)).await;
+        let pool = pool(&[&a, &b]).await;
+        assert!(
+            verify(&pool, &pins, CHAIN_ID, address(), VerifyOptions::default())
+                .await
+                .is_err()
+        );
+        assert_eq!(status(&pool, &a), (true, false));
+        assert_eq!(status(&pool, &b), (true, false));
+        assert_eq!(pins.get(CHAIN_ID, address()), Some(hash(&code(0))));
+    }
+
+    #[tokio::test]
+    async fn unreachable_endpoint_is_checked_before_use() {
+        let good = online(CHAIN_ID, code(0)).await;
+        let up = Arc::new(AtomicBool::new(false));
+        let late = node(CHAIN_ID, code(0), up.clone()).await;
+        let forged_up = Arc::new(AtomicBool::new(false));
+        let forged = node(CHAIN_ID, code(1), forged_up.clone()).await;
+        let pool = pool(&[&good, &late, &forged]).await;
+        let pins = pins();
+
+        verify(&pool, &pins, CHAIN_ID, address(), VerifyOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(status(&pool, &late), (false, true));
+        assert_eq!(status(&pool, &forged), (false, true));
+
+        up.store(true, Ordering::SeqCst);
+        forged_up.store(true, Ordering::SeqCst);
+        pool.check_health().await;
+        assert_eq!(status(&pool, &late), (false, false));
+        assert_eq!(status(&pool, &forged), (true, false));
+    }
+}
@@ -0,0 +1,129 @@
+use anyhow::{anyhow, Result};
+use ethers::types::{Address, H256};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+pub const DEFAULT_CODE_PINS_PATH: &str = "pow-code-pins.json";
+
+// 首次运行时记录的合约代码哈希
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CodePin {
+    chain_id: u64,
+    address: Address,
+    code_hash: H256,
+    pinned_at: i64,
+}
+
+// 合约代码哈希记录，与奖励账本分开保存：
+// 删除或更换账本不会丢失记录，否则下次启动会重新按首次信任记录
+pub struct CodePins {
+    path: PathBuf,
+    pins: Mutex<Vec<CodePin>>,
+}
+
+impl CodePins {
+    // 文件不存在时视为没有记录；文件损坏时报错，不会当作首次运行
+    pub fn open(path: &Path) -> Result<Self> {
+        let pins = if path.exists() {
+            let content = fs::read_to_string(path).map_err(|e| {
+                anyhow!(
+                    "无法读取合约代码哈希记录 / Cannot read code pins {}: {}",
+                    path.display(),
+                    e
+                )
+            })?;
+            serde_json::from_str(&content).map_err(|e| {
+                anyhow!(
+                    "合约代码哈希记录损坏 / Code pins file is corrupt {}: {}",
+                    path.display(),
+                    e
+                )
+            })?
+        } else {
+            Vec::new()
+        };
+        Ok(CodePins {
+            path: path.to_path_buf(),
+            pins: Mutex::new(pins),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // 已记录的合约代码哈希
+    pub fn get(&self, chain_id: u64, address: Address) -> Option<H256> {
+        self.pins
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|pin| pin.chain_id == chain_id && pin.address == address)
+            .map(|pin| pin.code_hash)
+    }
+
+    // 记录合约代码哈希，已存在时覆盖；先写临时文件再替换，中途退出不会留下损坏的文件
+    pub fn pin(&self, chain_id: u64, address: Address, code_hash: H256) -> Result<()> {
+        let mut pins = self.pins.lock().unwrap();
+        pins.retain(|pin| pin.chain_id != chain_id || pin.address != address);
+        pins.push(CodePin {
+            chain_id,
+            address,
+            code_hash,
+            pinned_at: chrono::Utc::now().timestamp(),
+        });
+
+        let write_error = |e: std::io::Error| {
+            anyhow!(
+                "无法写入合约代码哈希记录 / Cannot write code pins {}: {}",
+                self.path.display(),
+                e
+            )
+        };
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+        fs::write(&tmp, serde_json::to_vec_pretty(&*pins)?).map_err(write_error)?;
+        fs::rename(&tmp, &self.path).map_err(write_error)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path() -> PathBuf {
+        std::env::temp_dir().join(format!("pow-code-pins-{}.json", rand::random::<u64>()))
+    }
+
+    #[test]
+    fn pins_survive_reopening() {
+        let path = temp_path();
+        let address = Address::repeat_byte(0x51);
+        let pins = CodePins::open(&path).unwrap();
+        assert_eq!(pins.get(1, address), None);
+
+        pins.pin(1, address, H256::repeat_byte(1)).unwrap();
+        pins.pin(2, address, H256::repeat_byte(2)).unwrap();
+        pins.pin(1, address, H256::repeat_byte(3)).unwrap();
+
+        let reopened = CodePins::open(&path).unwrap();
+        assert_eq!(reopened.get(1, address), Some(H256::repeat_byte(3)));
+        assert_eq!(reopened.get(2, address), Some(H256::repeat_byte(2)));
+        assert_eq!(reopened.get(1, Address::zero()), None);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn corrupt_file_is_an_error() {
+        let path = temp_path();
+        fs::write(&path, "not json").unwrap();
+        assert!(CodePins::open(&path).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
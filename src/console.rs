@@ -0,0 +1,278 @@
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+use std::{
+    collections::HashMap,
+    io::IsTerminal,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::tui_monitor::{LogLevel, MonitorData};
+
+// 控制台进度刷新间隔
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+
+// 标准输出不是终端时打印汇总的间隔
+const PLAIN_SUMMARY_INTERVAL: Duration = Duration::from_secs(60);
+
+// 控制台输出：监控界面运行时写入界面日志，避免破坏界面绘制；
+// 无界面但开启监控套接字时同时写入终端与日志，供 attach 的界面显示
+lazy_static::lazy_static! {
+    static ref CAPTURE: Mutex<Option<Capture>> = Mutex::new(None);
+    // 正在绘制的控制台进度，输出时先收起进度条，避免互相覆盖
+    static ref PROGRESS: Mutex<Option<MultiProgress>> = Mutex::new(None);
+}
+
+#[derive(Clone)]
+struct Capture {
+    data: Arc<MonitorData>,
+    // 是否仍输出到终端
+    echo: bool,
+}
+
+// 日志级别由调用处指定，不依赖输出颜色（重定向时颜色被关闭）：
+// `console_println!(warn: ...)`、`console_eprintln!(error: ...)`；
+// 未指定时 console_println 为信息，console_eprintln 为警告
+macro_rules! console_println {
+    (error: $($arg:tt)*) => {
+        $crate::console::emit(format!($($arg)*), $crate::tui_monitor::LogLevel::Error, false)
+    };
+    (warn: $($arg:tt)*) => {
+        $crate::console::emit(format!($($arg)*), $crate::tui_monitor::LogLevel::Warn, false)
+    };
+    ($($arg:tt)*) => {
+        $crate::console::emit(format!($($arg)*), $crate::tui_monitor::LogLevel::Info, false)
+    };
+}
+
+macro_rules! console_eprintln {
+    (error: $($arg:tt)*) => {
+        $crate::console::emit(format!($($arg)*), $crate::tui_monitor::LogLevel::Error, true)
+    };
+    (warn: $($arg:tt)*) => {
+        $crate::console::emit(format!($($arg)*), $crate::tui_monitor::LogLevel::Warn, true)
+    };
+    ($($arg:tt)*) => {
+        $crate::console::emit(format!($($arg)*), $crate::tui_monitor::LogLevel::Warn, true)
+    };
+}
+
+// 标准输出不是终端（重定向到文件或 systemd 日志）时不输出颜色
+pub fn init() {
+    if !std::io::stdout().is_terminal() {
+        colored::control::set_override(false);
+    }
+}
+
+// 之后的输出写入监控日志
+pub fn capture(data: Arc<MonitorData>) {
+    *CAPTURE.lock().unwrap() = Some(Capture { data, echo: false });
+}
+
+// 之后的输出在终端显示的同时写入监控日志
+pub fn mirror(data: Arc<MonitorData>) {
+    *CAPTURE.lock().unwrap() = Some(Capture { data, echo: true });
+}
+
+// 恢复输出到终端
+pub fn release() {
+    *CAPTURE.lock().unwrap() = None;
+}
+
+pub fn emit(line: String, level: LogLevel, stderr: bool) {
+    let capture = CAPTURE.lock().unwrap().clone();
+    if let Some(capture) = &capture {
+        for text in strip_ansi(&line).lines().filter(|l| !l.trim().is_empty()) {
+            capture.data.push_log(text.to_string(), level);
+        }
+    }
+    if matches!(capture, Some(Capture { echo: false, .. })) {
+        return;
+    }
+
+    let print = || {
+        if stderr {
+            eprintln!("{}", line)
+        } else {
+            println!("{}", line)
+        }
+    };
+    let progress = PROGRESS.lock().unwrap().clone();
+    match progress {
+        Some(multi) => multi.suspend(print),
+        None => print(),
+    }
+}
+
+// 无界面挖矿时的控制台进度：终端中每个进行中的任务一行进度条，最下面一行汇总；
+// 标准输出不是终端时不绘制进度条，定期打印一行汇总。stop 后结束并收起进度条
+pub fn start_progress(
+    data: Arc<MonitorData>,
+    stop: Arc<AtomicBool>,
+) -> std::thread::JoinHandle<()> {
+    std::thread::spawn(move || {
+        if !std::io::stdout().is_terminal() {
+            let mut last_summary = Instant::now();
+            while !stop.load(Ordering::SeqCst) {
+                std::thread::sleep(PROGRESS_INTERVAL);
+                if last_summary.elapsed() >= PLAIN_SUMMARY_INTERVAL {
+                    last_summary = Instant::now();
+                    console_println!("汇总 / Summary: {}", summary(&data));
+                }
+            }
+            return;
+        }
+
+        let mut renderer = ProgressRenderer::new();
+        *PROGRESS.lock().unwrap() = Some(renderer.multi.clone());
+        while !stop.load(Ordering::SeqCst) {
+            renderer.sync(&data);
+            std::thread::sleep(PROGRESS_INTERVAL);
+        }
+        *PROGRESS.lock().unwrap() = None;
+        renderer.clear();
+    })
+}
+
+struct ProgressRenderer {
+    multi: MultiProgress,
+    summary: ProgressBar,
+    tasks: HashMap<usize, ProgressBar>,
+}
+
+impl ProgressRenderer {
+    fn new() -> Self {
+        let multi = MultiProgress::with_draw_target(ProgressDrawTarget::stdout());
+        let summary = multi.add(ProgressBar::new(0));
+        summary.set_style(ProgressStyle::with_template("{msg}").unwrap());
+        ProgressRenderer {
+            multi,
+            summary,
+            tasks: HashMap::new(),
+        }
+    }
+
+    // 按监控数据增删与更新任务进度条
+    fn sync(&mut self, data: &MonitorData) {
+        let ProgressRenderer {
+            multi,
+            summary: summary_bar,
+            tasks: bars,
+        } = self;
+        let tasks = data.task_progresses.lock().unwrap();
+        let hashrates = data.hashrates.lock().unwrap().clone();
+
+        bars.retain(|id, bar| {
+            let active = tasks.iter().any(|t| t.id == *id && !t.is_finished());
+            if !active {
+                bar.finish_and_clear();
+                multi.remove(bar);
+            }
+            active
+        });
+
+        for task in tasks.iter().filter(|t| !t.is_finished()) {
+            let bar = bars.entry(task.id).or_insert_with(|| {
+                let bar = multi.insert_before(summary_bar, ProgressBar::new(100));
+                bar.set_style(
+                    ProgressStyle::with_template(
+                        "{msg:<32} [{elapsed_precise}] [{bar:40.cyan/blue}] {percent}%",
+                    )
+                    .unwrap()
+                    .progress_chars("#>-"),
+                );
+                bar
+            });
+            let (stage, _) = task.status.label();
+            let rate = hashrates.get(&task.id).map_or(String::new(), |rate| {
+                format!(" {:.2}M/s", rate / 1_000_000.0)
+            });
+            bar.set_position((task.progress * 100.0) as u64);
+            bar.set_message(format!("任务 #{} {}{}", task.id, stage, rate));
+        }
+        drop(tasks);
+
+        summary_bar.set_message(summary(data));
+    }
+
+    fn clear(&mut self) {
+        for (_, bar) in self.tasks.drain() {
+            bar.finish_and_clear();
+        }
+        self.summary.finish_and_clear();
+        let _ = self.multi.clear();
+    }
+}
+
+fn summary(data: &MonitorData) -> String {
+    format!(
+        "算力 / Hashrate: {:.2}M H/s | 进行中 / Active: {} | 完成 / Solved: {} | 失败 / Failed: {} | 奖励 / Rewards: {:.6} MAG",
+        data.total_hashrate() / 1_000_000.0,
+        data.online_tasks.load(Ordering::SeqCst),
+        data.completed_tasks.load(Ordering::SeqCst),
+        data.failed_tasks(),
+        *data.total_rewards.lock().unwrap()
+    )
+}
+
+// 去掉 colored 添加的终端颜色控制序列
+fn strip_ansi(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // 跳过 ESC [ ... 结束字母
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            output.push(c);
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use colored::Colorize;
+
+    #[test]
+    fn level_is_kept_when_colours_are_disabled() {
+        colored::control::set_override(false);
+        let data = Arc::new(MonitorData::new());
+        capture(data.clone());
+        console_println!("{}", "level-test info".green());
+        console_println!(warn: "{}", "level-test warn".yellow());
+        console_eprintln!("level-test stderr");
+        console_eprintln!(error: "{}", "level-test error".red());
+        release();
+        colored::control::unset_override();
+
+        let levels: Vec<(String, LogLevel)> = data
+            .logs_after(None)
+            .into_iter()
+            .filter(|line| line.text.starts_with("level-test"))
+            .map(|line| (line.text, line.level))
+            .collect();
+        assert_eq!(
+            levels,
+            vec![
+                ("level-test info".to_string(), LogLevel::Info),
+                ("level-test warn".to_string(), LogLevel::Warn),
+                ("level-test stderr".to_string(), LogLevel::Warn),
+                ("level-test error".to_string(), LogLevel::Error),
+            ]
+        );
+    }
+
+    #[test]
+    fn colour_codes_are_stripped_from_logs() {
+        assert_eq!(strip_ansi("\x1b[31mfailed\x1b[0m: x"), "failed: x");
+        assert_eq!(strip_ansi("\x1b[1;33mwarn\x1b[0m"), "warn");
+    }
+}
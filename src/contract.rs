@@ -115,4 +115,4 @@ abigen!(
             "type": "function"
         }
     ]"#,
-); 
\ No newline at end of file
+);
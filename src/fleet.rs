@@ -0,0 +1,783 @@
+use anyhow::{anyhow, Result};
+use chrono::Local;
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use reqwest::Url;
+use std::{
+    collections::VecDeque,
+    io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tui::{
+    backend::{Backend, CrosstermBackend},
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Paragraph, Row, Table, TableState},
+    Frame, Terminal,
+};
+
+use crate::{
+    monitor_socket,
+    status_http::MinerStatus,
+    tui_monitor::{format_count, format_elapsed, short_hex, step, LogLine, TaskStatus},
+};
+
+// 轮询 HTTP 状态接口与套接字断线重连的间隔
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+// 超过该时间没有收到数据视为离线
+const OFFLINE_AFTER: Duration = Duration::from_secs(10);
+
+// 未暂停时超过该时间既没有算力也没有完成任务视为停滞，一直请求不到任务的机器也算在内
+const STALL_AFTER: Duration = Duration::from_secs(120);
+
+// 每台机器保留的日志行数
+const HOST_LOG_LINES: usize = 200;
+
+// 挖矿进程的状态来源：本机监控套接字或 --status-addr 提供的 HTTP 接口
+enum Endpoint {
+    Unix(PathBuf),
+    Http(Url),
+}
+
+impl Endpoint {
+    fn parse(value: &str) -> Result<Self> {
+        if !value.starts_with("http://") && !value.starts_with("https://") {
+            return Ok(Endpoint::Unix(PathBuf::from(value)));
+        }
+        let mut url = Url::parse(value)
+            .map_err(|e| anyhow!("状态地址无效 / Invalid status URL {}: {}", value, e))?;
+        if url.path() == "/" {
+            url.set_path("/status");
+        }
+        Ok(Endpoint::Http(url))
+    }
+
+    // 未指定名称时显示的主机名
+    fn default_name(&self) -> String {
+        match self {
+            Endpoint::Unix(path) => path.display().to_string(),
+            Endpoint::Http(url) => match (url.host_str(), url.port()) {
+                (Some(host), Some(port)) => format!("{}:{}", host, port),
+                (Some(host), None) => host.to_string(),
+                _ => url.to_string(),
+            },
+        }
+    }
+}
+
+// 每行一台机器：`[名称] <套接字路径|http://主机:端口>`，# 开头为注释
+pub fn load_hosts(path: &Path) -> Result<Vec<String>> {
+    let content = std::fs::read_to_string(path).map_err(|e| {
+        anyhow!(
+            "无法读取主机列表 / Cannot read host list {}: {}",
+            path.display(),
+            e
+        )
+    })?;
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect())
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Health {
+    Running,
+    Paused,
+    Stalled,
+    Offline,
+}
+
+impl Health {
+    fn label(self) -> (&'static str, Color) {
+        match self {
+            Health::Running => ("正常", Color::Green),
+            Health::Paused => ("已暂停", Color::Gray),
+            Health::Stalled => ("停滞", Color::Yellow),
+            Health::Offline => ("离线", Color::Red),
+        }
+    }
+}
+
+struct Host {
+    name: String,
+    status: Option<MinerStatus>,
+    logs: VecDeque<LogLine>,
+    updated: Option<Instant>,
+    // 最近一次有算力或完成任务的时间
+    last_progress: Option<Instant>,
+    error: Option<String>,
+}
+
+impl Host {
+    fn health(&self) -> Health {
+        let Some(status) = &self.status else {
+            return Health::Offline;
+        };
+        if self
+            .updated
+            .map_or(true, |updated| updated.elapsed() > OFFLINE_AFTER)
+        {
+            return Health::Offline;
+        }
+        if status.state.paused {
+            return Health::Paused;
+        }
+        if self
+            .last_progress
+            .is_some_and(|progress| progress.elapsed() > STALL_AFTER)
+        {
+            return Health::Stalled;
+        }
+        Health::Running
+    }
+
+    fn hashrate(&self) -> f64 {
+        self.status.as_ref().map_or(0.0, |status| {
+            status.data.hashrates.iter().map(|(_, rate)| rate).sum()
+        })
+    }
+
+    fn active_tasks(&self) -> usize {
+        self.status.as_ref().map_or(0, |status| {
+            status
+                .data
+                .task_progresses
+                .iter()
+                .filter(|task| !task.is_finished())
+                .count()
+        })
+    }
+
+    // 本次运行完成与失败的任务数，包括已归档的任务
+    fn finished(&self) -> (usize, usize) {
+        let Some(status) = &self.status else {
+            return (0, 0);
+        };
+        let archive = &status.data.archive;
+        status.data.task_progresses.iter().fold(
+            (archive.completed, archive.failed),
+            |(completed, failed), task| match task.status {
+                TaskStatus::Completed => (completed + 1, failed),
+                TaskStatus::Failed => (completed, failed + 1),
+                _ => (completed, failed),
+            },
+        )
+    }
+
+    fn error_rate(&self) -> Option<f64> {
+        let (completed, failed) = self.finished();
+        (completed + failed > 0).then_some(failed as f64 / (completed + failed) as f64)
+    }
+
+    fn last_reward(&self) -> String {
+        self.status
+            .as_ref()
+            .and_then(|status| status.data.last_reward)
+            .map_or("-".to_string(), |time| {
+                format!("{}前", format_elapsed(Local::now() - time))
+            })
+    }
+
+    // 套接字推送的是新增日志，HTTP 接口返回的是最近的日志
+    fn update(&mut self, mut status: MinerStatus, incremental: bool) {
+        if !incremental {
+            self.logs.clear();
+        }
+        self.logs.extend(status.logs.drain(..));
+        while self.logs.len() > HOST_LOG_LINES {
+            self.logs.pop_front();
+        }
+
+        // 暂停期间不计入停滞时间
+        let completed = self.finished().0;
+        let paused = status.state.paused;
+        self.status = Some(status);
+        if self.last_progress.is_none()
+            || paused
+            || self.hashrate() > 0.0
+            || self.finished().0 > completed
+        {
+            self.last_progress = Some(Instant::now());
+        }
+        self.updated = Some(Instant::now());
+        self.error = None;
+    }
+}
+
+type Hosts = Arc<Mutex<Vec<Host>>>;
+
+// 持续读取一台机器的状态，失败后按间隔重试
+async fn poll(endpoint: Endpoint, token: Option<String>, hosts: Hosts, index: usize) {
+    let client = reqwest::Client::builder()
+        .timeout(POLL_INTERVAL * 2)
+        .build()
+        .unwrap_or_default();
+    let update = |status: MinerStatus, incremental: bool| {
+        hosts.lock().unwrap()[index].update(status, incremental);
+    };
+
+    loop {
+        let result = match &endpoint {
+            Endpoint::Http(url) => fetch(&client, url, token.as_deref())
+                .await
+                .map(|status| update(status, false)),
+            Endpoint::Unix(path) => monitor_socket::subscribe(path, |status| update(status, true))
+                .await
+                .and_then(|()| Err(anyhow!("连接已断开 / Connection closed"))),
+        };
+        if let Err(e) = result {
+            hosts.lock().unwrap()[index].error = Some(format!("{:#}", e));
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+async fn fetch(client: &reqwest::Client, url: &Url, token: Option<&str>) -> Result<MinerStatus> {
+    let mut request = client.get(url.clone());
+    if let Some(token) = token {
+        request = request.bearer_auth(token);
+    }
+    let body = request.send().await?.error_for_status()?.bytes().await?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+// 多台挖矿进程的汇总界面：每行一台机器，Enter 查看详情，停滞与离线的机器高亮显示
+pub async fn run(entries: Vec<String>, token: Option<String>) -> Result<()> {
+    if entries.is_empty() {
+        return Err(anyhow!(
+            "没有要查看的挖矿进程，请指定状态地址或 --hosts 文件 / No miners to show; pass status endpoints or a --hosts file"
+        ));
+    }
+
+    let mut hosts = Vec::new();
+    let mut endpoints = Vec::new();
+    for entry in &entries {
+        let (name, endpoint) = match entry.split_whitespace().collect::<Vec<_>>()[..] {
+            [endpoint] => (None, Endpoint::parse(endpoint)?),
+            [name, endpoint] => (Some(name.to_string()), Endpoint::parse(endpoint)?),
+            _ => {
+                return Err(anyhow!(
+                    "主机格式应为 `[名称] <地址>` / Host entries must be `[name] <endpoint>`: {}",
+                    entry
+                ))
+            }
+        };
+        hosts.push(Host {
+            name: name.unwrap_or_else(|| endpoint.default_name()),
+            status: None,
+            logs: VecDeque::new(),
+            updated: None,
+            last_progress: None,
+            error: None,
+        });
+        endpoints.push(endpoint);
+    }
+
+    let hosts: Hosts = Arc::new(Mutex::new(hosts));
+    let pollers: Vec<_> = endpoints
+        .into_iter()
+        .enumerate()
+        .map(|(index, endpoint)| tokio::spawn(poll(endpoint, token.clone(), hosts.clone(), index)))
+        .collect();
+
+    let result = tokio::task::spawn_blocking(move || FleetApp::new(hosts).run()).await;
+    for poller in pollers {
+        poller.abort();
+    }
+    Ok(result??)
+}
+
+struct FleetApp {
+    hosts: Hosts,
+    table: TableState,
+    // 正在查看详情的机器
+    detail: Option<usize>,
+    should_quit: bool,
+}
+
+impl FleetApp {
+    fn new(hosts: Hosts) -> Self {
+        let mut table = TableState::default();
+        table.select(Some(0));
+        FleetApp {
+            hosts,
+            table,
+            detail: None,
+            should_quit: false,
+        }
+    }
+
+    fn run(&mut self) -> io::Result<()> {
+        enable_raw_mode()?;
+        let mut stdout = io::stdout();
+        execute!(stdout, EnterAlternateScreen)?;
+        let backend = CrosstermBackend::new(stdout);
+        let mut terminal = Terminal::new(backend)?;
+
+        let tick_rate = Duration::from_millis(500);
+        while !self.should_quit {
+            terminal.draw(|f| self.ui(f))?;
+            if event::poll(tick_rate)? {
+                if let Event::Key(key) = event::read()? {
+                    self.handle_key(key);
+                }
+            }
+        }
+
+        disable_raw_mode()?;
+        execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+        terminal.show_cursor()?;
+        Ok(())
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.should_quit = true
+            }
+            KeyCode::Char('q') => self.should_quit = true,
+            KeyCode::Esc | KeyCode::Backspace if self.detail.is_some() => self.detail = None,
+            KeyCode::Esc => self.should_quit = true,
+            KeyCode::Enter => self.detail = self.table.selected(),
+            KeyCode::Up => self.move_selection(-1),
+            KeyCode::Down => self.move_selection(1),
+            _ => {}
+        }
+    }
+
+    // 详情界面中上下切换机器
+    fn move_selection(&mut self, delta: isize) {
+        let len = self.hosts.lock().unwrap().len();
+        let selected = step(self.table.selected(), delta, len);
+        self.table.select(selected);
+        if self.detail.is_some() {
+            self.detail = selected;
+        }
+    }
+
+    fn ui<B: Backend>(&mut self, f: &mut Frame<B>) {
+        let hosts = self.hosts.clone();
+        let hosts = hosts.lock().unwrap();
+        match self.detail.and_then(|index| hosts.get(index)) {
+            Some(host) => render_detail(f, host),
+            None => self.render_overview(f, &hosts),
+        }
+    }
+
+    fn render_overview<B: Backend>(&mut self, f: &mut Frame<B>, hosts: &[Host]) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(5)].as_ref())
+            .split(f.size());
+
+        let online = hosts
+            .iter()
+            .filter(|host| host.health() != Health::Offline)
+            .count();
+        let stalled = hosts
+            .iter()
+            .filter(|host| host.health() == Health::Stalled)
+            .count();
+        let balance: f64 = hosts
+            .iter()
+            .filter_map(|host| host.status.as_ref())
+            .map(|status| status.data.wallet_balance)
+            .sum();
+        let title = Paragraph::new(format!(
+            "Magnet POW 矿机总览  在线: {}/{}  停滞: {}  总算力: {:.2}M 哈希/秒  总余额: {:.4} MAG  (↑↓ 选择, Enter 详情, q 退出)",
+            online,
+            hosts.len(),
+            stalled,
+            hosts.iter().map(Host::hashrate).sum::<f64>() / 1_000_000.0,
+            balance
+        ))
+        .style(
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        )
+        .block(Block::default().borders(Borders::ALL));
+        f.render_widget(title, chunks[0]);
+
+        let rows: Vec<Row> = hosts
+            .iter()
+            .map(|host| {
+                let (label, color) = host.health().label();
+                let (completed, failed) = host.finished();
+                let balance = host.status.as_ref().map_or("-".to_string(), |status| {
+                    format!("{:.4}", status.data.wallet_balance)
+                });
+                Row::new(vec![
+                    host.name.clone(),
+                    label.to_string(),
+                    format!("{}/s", format_count(host.hashrate() as u64)),
+                    host.active_tasks().to_string(),
+                    format!("{}/{}", completed, failed),
+                    host.error_rate()
+                        .map_or("-".to_string(), |rate| format!("{:.0}%", rate * 100.0)),
+                    host.last_reward(),
+                    balance,
+                    host.error.clone().unwrap_or_default(),
+                ])
+                .style(Style::default().fg(color))
+            })
+            .collect();
+
+        let header = Row::new(vec![
+            "主机",
+            "状态",
+            "算力",
+            "活动任务",
+            "完成/失败",
+            "错误率",
+            "最近奖励",
+            "钱包余额",
+            "错误",
+        ])
+        .style(
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        );
+        // 错误列占用剩余宽度
+        let fixed = [24, 8, 10, 8, 10, 7, 12, 14];
+        let rest = chunks[1]
+            .width
+            .saturating_sub(fixed.iter().sum::<u16>() + fixed.len() as u16 + 2);
+        let widths: Vec<Constraint> = fixed
+            .iter()
+            .chain([rest.max(10)].iter())
+            .map(|&width| Constraint::Length(width))
+            .collect();
+        let table = Table::new(rows)
+            .header(header)
+            .block(Block::default().title("矿机").borders(Borders::ALL))
+            .widths(&widths)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        f.render_stateful_widget(table, chunks[1], &mut self.table);
+    }
+}
+
+// 单台机器的详情：控制状态、余额、任务、节点与最近日志
+fn render_detail<B: Backend>(f: &mut Frame<B>, host: &Host) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Length(6),
+                Constraint::Min(6),
+                Constraint::Length(12),
+            ]
+            .as_ref(),
+        )
+        .split(f.size());
+
+    let (label, color) = host.health().label();
+    let title = format!("{} [{}]  (↑↓ 切换机器, Esc 返回, q 退出)", host.name, label);
+    let Some(status) = &host.status else {
+        let text = host
+            .error
+            .clone()
+            .unwrap_or_else(|| "等待数据...".to_string());
+        let paragraph = Paragraph::new(text)
+            .style(Style::default().fg(color))
+            .block(Block::default().title(title).borders(Borders::ALL));
+        f.render_widget(paragraph, f.size());
+        return;
+    };
+
+    let data = &status.data;
+    let healthy = data.rpc_status.iter().filter(|ep| ep.is_healthy()).count();
+    let mut lines = vec![
+        format!(
+            "算力: {}/s  活动任务: {}  哈希线程: {}  并行任务: {}{}",
+            format_count(host.hashrate() as u64),
+            host.active_tasks(),
+            status.state.hash_threads,
+            status.state.parallel_tasks,
+            if status.state.paused {
+                "  [已暂停]"
+            } else {
+                ""
+            }
+        ),
+        format!(
+            "钱包余额: {:.6} MAG  矿池余额: {:.6} MAG  本次奖励: {:.6} MAG  最近奖励: {}",
+            data.wallet_balance,
+            data.contract_balance,
+            data.total_rewards,
+            host.last_reward()
+        ),
+        format!(
+            "RPC节点: {}/{} 健康  错误率: {}",
+            healthy,
+            data.rpc_status.len(),
+            host.error_rate()
+                .map_or("-".to_string(), |rate| format!("{:.0}%", rate * 100.0))
+        ),
+    ];
+    if let Some(error) = &host.error {
+        lines.push(format!("连接错误: {}", error));
+    }
+    let summary = Paragraph::new(lines.join("\n"))
+        .style(Style::default().fg(color))
+        .block(Block::default().title(title).borders(Borders::ALL));
+    f.render_widget(summary, chunks[0]);
+
+    render_host_tasks(f, status, chunks[1]);
+    render_host_logs(f, host, chunks[2]);
+}
+
+fn render_host_tasks<B: Backend>(f: &mut Frame<B>, status: &MinerStatus, area: Rect) {
+    // 最新的任务在最上面
+    let rows: Vec<Row> = status
+        .data
+        .task_progresses
+        .iter()
+        .rev()
+        .map(|task| {
+            let (stage, color) = task.status.label();
+            Row::new(vec![
+                task.id.to_string(),
+                short_hex(&task.wallet),
+                stage.to_string(),
+                format_count(task.hashes),
+                format_elapsed(task.elapsed()),
+                task.reward.map_or("-".to_string(), |r| format!("{:.4}", r)),
+                task.last_error.clone().unwrap_or_default(),
+            ])
+            .style(Style::default().fg(color))
+        })
+        .collect();
+    let header = Row::new(vec![
+        "ID",
+        "钱包",
+        "阶段",
+        "已算哈希",
+        "用时",
+        "奖励",
+        "错误",
+    ])
+    .style(
+        Style::default()
+            .fg(Color::Cyan)
+            .add_modifier(Modifier::BOLD),
+    );
+    let fixed = [6, 13, 8, 8, 8, 8];
+    let rest = area
+        .width
+        .saturating_sub(fixed.iter().sum::<u16>() + fixed.len() as u16 + 2);
+    let widths: Vec<Constraint> = fixed
+        .iter()
+        .chain([rest.max(10)].iter())
+        .map(|&width| Constraint::Length(width))
+        .collect();
+    let table = Table::new(rows)
+        .header(header)
+        .block(Block::default().title("任务").borders(Borders::ALL))
+        .widths(&widths);
+    f.render_widget(table, area);
+}
+
+fn render_host_logs<B: Backend>(f: &mut Frame<B>, host: &Host, area: Rect) {
+    let rows = area.height.saturating_sub(2) as usize;
+    let lines: Vec<Spans> = host
+        .logs
+        .iter()
+        .skip(host.logs.len().saturating_sub(rows))
+        .map(|line| {
+            Spans::from(vec![
+                Span::raw(format!("{} ", line.time.format("%H:%M:%S"))),
+                Span::styled(line.text.clone(), Style::default().fg(line.level.color())),
+            ])
+        })
+        .collect();
+    let paragraph =
+        Paragraph::new(lines).block(Block::default().title("最近日志").borders(Borders::ALL));
+    f.render_widget(paragraph, area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        miner_control::ControlState,
+        tui_monitor::{LogLevel, MonitorData, TaskArchive},
+    };
+
+    fn status(data: &MonitorData, logs: &[&str]) -> MinerStatus {
+        MinerStatus {
+            state: ControlState::default(),
+            data: data.snapshot(),
+            logs: logs
+                .iter()
+                .enumerate()
+                .map(|(seq, text)| LogLine {
+                    seq: seq as u64,
+                    time: Local::now(),
+                    level: LogLevel::Info,
+                    text: text.to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    fn host() -> Host {
+        Host {
+            name: "test".to_string(),
+            status: None,
+            logs: VecDeque::new(),
+            updated: None,
+            last_progress: None,
+            error: None,
+        }
+    }
+
+    fn ago(duration: Duration) -> Option<Instant> {
+        Instant::now().checked_sub(duration)
+    }
+
+    fn log_texts(host: &Host) -> Vec<&str> {
+        host.logs.iter().map(|line| line.text.as_str()).collect()
+    }
+
+    #[test]
+    fn health_follows_updates_pause_and_progress() {
+        let mut host = host();
+        assert_eq!(host.health(), Health::Offline);
+
+        host.update(status(&MonitorData::new(), &[]), false);
+        assert_eq!(host.health(), Health::Running);
+
+        host.updated = ago(OFFLINE_AFTER + Duration::from_secs(1));
+        assert_eq!(host.health(), Health::Offline);
+
+        host.updated = Some(Instant::now());
+        host.status.as_mut().unwrap().state.paused = true;
+        host.last_progress = ago(STALL_AFTER + Duration::from_secs(1));
+        assert_eq!(host.health(), Health::Paused);
+    }
+
+    #[test]
+    fn idle_miner_without_progress_is_stalled() {
+        let mut host = host();
+        host.update(status(&MonitorData::new(), &[]), false);
+        assert_eq!(host.active_tasks(), 0);
+
+        host.last_progress = ago(STALL_AFTER + Duration::from_secs(1));
+        host.update(status(&MonitorData::new(), &[]), false);
+        assert_eq!(host.health(), Health::Stalled);
+
+        // 有算力即恢复
+        let data = MonitorData::new();
+        data.add_task(1, "0x01".to_string());
+        data.update_hashrate(1, 1_000.0);
+        host.update(status(&data, &[]), false);
+        assert_eq!(host.health(), Health::Running);
+    }
+
+    #[test]
+    fn finished_and_error_rate_include_the_archive() {
+        let mut host = host();
+        assert_eq!(host.finished(), (0, 0));
+        assert_eq!(host.error_rate(), None);
+
+        let data = MonitorData::new();
+        data.add_task(1, "0x01".to_string());
+        data.complete_task(1, true);
+        data.add_task(2, "0x01".to_string());
+        data.complete_task(2, false);
+        data.add_task(3, "0x01".to_string());
+        let mut status = status(&data, &[]);
+        status.data.archive = TaskArchive {
+            completed: 5,
+            failed: 1,
+            rewards: 15.0,
+        };
+        host.update(status, false);
+
+        assert_eq!(host.finished(), (6, 2));
+        assert_eq!(host.error_rate(), Some(0.25));
+        assert_eq!(host.active_tasks(), 1);
+    }
+
+    #[test]
+    fn socket_updates_append_logs_and_http_updates_replace_them() {
+        let data = MonitorData::new();
+        let mut host = host();
+        host.update(status(&data, &["a", "b"]), true);
+        host.update(status(&data, &["c"]), true);
+        assert_eq!(log_texts(&host), ["a", "b", "c"]);
+
+        host.update(status(&data, &["x", "y"]), false);
+        assert_eq!(log_texts(&host), ["x", "y"]);
+
+        let many: Vec<String> = (0..HOST_LOG_LINES + 5).map(|i| i.to_string()).collect();
+        let many: Vec<&str> = many.iter().map(String::as_str).collect();
+        host.update(status(&data, &many), true);
+        assert_eq!(host.logs.len(), HOST_LOG_LINES);
+        assert_eq!(
+            host.logs.back().unwrap().text,
+            (HOST_LOG_LINES + 4).to_string()
+        );
+    }
+
+    #[test]
+    fn update_clears_the_connection_error() {
+        let mut host = host();
+        host.error = Some("connection refused".to_string());
+        host.update(status(&MonitorData::new(), &[]), false);
+        assert!(host.error.is_none());
+        assert!(host.updated.is_some());
+    }
+
+    #[test]
+    fn endpoints_are_sockets_or_status_urls() {
+        match Endpoint::parse("/run/miner/pow-client.sock").unwrap() {
+            Endpoint::Unix(path) => assert_eq!(path, PathBuf::from("/run/miner/pow-client.sock")),
+            Endpoint::Http(_) => panic!("expected a socket path"),
+        }
+
+        let endpoint = Endpoint::parse("http://10.0.0.2:8080").unwrap();
+        assert_eq!(endpoint.default_name(), "10.0.0.2:8080");
+        match endpoint {
+            Endpoint::Http(url) => assert_eq!(url.as_str(), "http://10.0.0.2:8080/status"),
+            Endpoint::Unix(_) => panic!("expected a status URL"),
+        }
+
+        match Endpoint::parse("https://miner.example.com/custom").unwrap() {
+            Endpoint::Http(url) => assert_eq!(url.path(), "/custom"),
+            Endpoint::Unix(_) => panic!("expected a status URL"),
+        }
+        assert!(Endpoint::parse("http://").is_err());
+    }
+
+    #[test]
+    fn host_list_skips_comments_and_blank_lines() {
+        let path =
+            std::env::temp_dir().join(format!("pow-client-hosts-test-{}", rand::random::<u64>()));
+        std::fs::write(
+            &path,
+            "# 机房 A\nrig-1 http://10.0.0.2:8080\n\n   \n  /run/pow-client.sock  \n",
+        )
+        .unwrap();
+        let hosts = load_hosts(&path);
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(
+            hosts.unwrap(),
+            ["rig-1 http://10.0.0.2:8080", "/run/pow-client.sock"]
+        );
+        assert!(load_hosts(&path).is_err());
+    }
+}
@@ -0,0 +1,211 @@
+// 状态接口、控制接口与 WebSocket 中继共用的最小 HTTP/1.1 辅助函数
+use anyhow::{anyhow, Result};
+use colored::*;
+use std::{future::Future, io, sync::Arc, time::Duration};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::Semaphore,
+};
+
+// 请求头的最大长度
+const MAX_HEAD_LEN: usize = 16 * 1024;
+
+// 单个连接读取请求并写完响应的时间上限
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+// 同时处理的连接数上限，超出的连接直接关闭
+const MAX_CONNECTIONS: usize = 32;
+
+// 接受连接失败后重试前的等待时间
+const ACCEPT_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+// 接受连接并逐个交给 handle；限制并发连接数与每个连接的处理时间，
+// 慢速或大量连接不会占满任务与内存
+pub fn spawn_server<F, Fut>(listener: TcpListener, label: &'static str, handle: F)
+where
+    F: Fn(TcpStream) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<()>> + Send + 'static,
+{
+    spawn_server_with(listener, label, REQUEST_TIMEOUT, MAX_CONNECTIONS, handle)
+}
+
+fn spawn_server_with<F, Fut>(
+    listener: TcpListener,
+    label: &'static str,
+    timeout: Duration,
+    max_connections: usize,
+    handle: F,
+) where
+    F: Fn(TcpStream) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<()>> + Send + 'static,
+{
+    let connections = Arc::new(Semaphore::new(max_connections));
+    tokio::spawn(async move {
+        loop {
+            let (client, _) = accept_with_backoff(label, || listener.accept()).await;
+            let Ok(permit) = connections.clone().try_acquire_owned() else {
+                continue;
+            };
+            let request = handle(client);
+            tokio::spawn(async move {
+                let result = tokio::time::timeout(timeout, request)
+                    .await
+                    .unwrap_or_else(|_| Err(anyhow!("请求超时 / Request timed out")));
+                if let Err(e) = result {
+                    console_eprintln!(warn: "{}", format!("{}: {}", label, e).yellow());
+                }
+                drop(permit);
+            });
+        }
+    });
+}
+
+// 接受下一个连接；文件描述符耗尽 (EMFILE/ENFILE) 等错误会立即再次出现，
+// 记录后稍等再试以免空转
+pub async fn accept_with_backoff<S, F, Fut>(label: &str, mut accept: F) -> S
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = io::Result<S>>,
+{
+    loop {
+        match accept().await {
+            Ok(stream) => return stream,
+            Err(e) => {
+                console_eprintln!(warn: "{}", format!("{}: {}", label, e).yellow());
+                tokio::time::sleep(ACCEPT_RETRY_DELAY).await;
+            }
+        }
+    }
+}
+
+// 逐字节读取到空行为止，避免读入请求头之后的数据
+pub async fn read_head(stream: &mut TcpStream) -> Result<String> {
+    let mut head = Vec::new();
+    while !head.ends_with(b"\r\n\r\n") {
+        if head.len() >= MAX_HEAD_LEN {
+            return Err(anyhow!("请求头过长 / Header too long"));
+        }
+        let byte = stream.read_u8().await?;
+        head.push(byte);
+    }
+    Ok(String::from_utf8_lossy(&head).into_owned())
+}
+
+// 请求头中的 `Authorization: Bearer <令牌>` 是否与 token 一致
+pub fn bearer_matches(head: &str, token: &str) -> bool {
+    head.lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.eq_ignore_ascii_case("authorization"))
+        .and_then(|(_, value)| value.trim().strip_prefix("Bearer "))
+        .is_some_and(|given| token_matches(given.trim(), token))
+}
+
+// 逐字节比较全部内容，比较耗时不随匹配的前缀长度变化
+pub fn token_matches(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+// 写出 JSON 响应并关闭连接
+pub async fn respond(client: &mut TcpStream, status: &str, body: &[u8]) -> Result<()> {
+    let head = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        body.len()
+    );
+    client.write_all(head.as_bytes()).await?;
+    client.write_all(body).await?;
+    client.shutdown().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 服务端关闭连接时读到 EOF
+    async fn closed_within(stream: &mut TcpStream, limit: Duration) -> bool {
+        let mut buf = [0; 1];
+        matches!(
+            tokio::time::timeout(limit, stream.read(&mut buf)).await,
+            Ok(Ok(0)) | Ok(Err(_))
+        )
+    }
+
+    #[test]
+    fn bearer_token_must_match_exactly() {
+        let head = "GET /status HTTP/1.1\r\nauthorization: Bearer secret\r\n\r\n";
+        assert!(bearer_matches(head, "secret"));
+        assert!(!bearer_matches(head, "secret2"));
+        assert!(!bearer_matches(head, "secreT"));
+        assert!(!bearer_matches("GET /status HTTP/1.1\r\n\r\n", "secret"));
+        assert!(!bearer_matches(
+            "GET /status HTTP/1.1\r\nAuthorization: Basic secret\r\n\r\n",
+            "secret"
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn accept_errors_are_retried_after_a_delay() {
+        let start = tokio::time::Instant::now();
+        let mut attempts = 0;
+        let accepted = accept_with_backoff("test", || {
+            attempts += 1;
+            let result = if attempts < 3 {
+                Err(io::Error::from_raw_os_error(24))
+            } else {
+                Ok(attempts)
+            };
+            async move { result }
+        })
+        .await;
+        assert_eq!(accepted, 3);
+        assert_eq!(start.elapsed(), ACCEPT_RETRY_DELAY * 2);
+    }
+
+    #[tokio::test]
+    async fn slow_clients_time_out() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        spawn_server_with(
+            listener,
+            "test",
+            Duration::from_millis(100),
+            4,
+            |mut client| async move {
+                read_head(&mut client).await?;
+                Ok(())
+            },
+        );
+
+        let mut idle = TcpStream::connect(addr).await.unwrap();
+        idle.write_all(b"GET /status HTTP/1.1\r\n").await.unwrap();
+        assert!(closed_within(&mut idle, Duration::from_secs(2)).await);
+    }
+
+    #[tokio::test]
+    async fn connections_over_the_limit_are_closed() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        spawn_server_with(
+            listener,
+            "test",
+            Duration::from_secs(30),
+            1,
+            |mut client| async move {
+                read_head(&mut client).await?;
+                Ok(())
+            },
+        );
+
+        let mut first = TcpStream::connect(addr).await.unwrap();
+        let mut second = TcpStream::connect(addr).await.unwrap();
+        assert!(closed_within(&mut second, Duration::from_secs(2)).await);
+        assert!(!closed_within(&mut first, Duration::from_millis(200)).await);
+    }
+}
@@ -0,0 +1,380 @@
+use anyhow::{anyhow, Result};
+use chrono::{Local, TimeZone};
+use ethers::types::{Address, TransactionReceipt, H256, I256, U256};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::{collections::BTreeMap, path::Path, sync::Mutex};
+
+pub const DEFAULT_LEDGER_PATH: &str = "pow-rewards.db";
+
+// 一条挖矿奖励记录，金额均以 wei 为单位
+#[derive(Debug, Clone)]
+pub struct RewardRecord {
+    pub tx_hash: H256,
+    pub log_index: u64,
+    pub wallet: Address,
+    pub block_number: u64,
+    // 奖励所在区块的时间戳
+    pub timestamp: i64,
+    pub reward: U256,
+    // 提交交易的 gas 消耗与实际 gas 价格
+    pub gas_used: Option<U256>,
+    pub effective_gas_price: Option<U256>,
+    // 该任务所有交易（请求、提交及失败重试）花费的手续费；
+    // 从链上日志回填时只能取得提交交易的手续费，挖矿时记录的值会覆盖它
+    pub fee: Option<U256>,
+}
+
+impl RewardRecord {
+    pub fn net_profit(&self) -> Option<I256> {
+        let fee = self.fee?;
+        Some(I256::from_raw(self.reward) - I256::from_raw(fee))
+    }
+}
+
+// 一条 NewMiningTask 事件记录
+#[derive(Debug, Clone)]
+pub struct TaskRecord {
+    pub tx_hash: H256,
+    pub log_index: u64,
+    pub wallet: Address,
+    pub block_number: u64,
+    pub timestamp: i64,
+    pub difficulty: U256,
+}
+
+// 汇总维度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum SummaryPeriod {
+    Hour,
+    Day,
+    Wallet,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct RewardSummary {
+    pub key: String,
+    pub count: usize,
+    pub reward: U256,
+    pub fee: U256,
+    pub net_profit: I256,
+}
+
+// 交易手续费：gasUsed * effectiveGasPrice
+pub fn receipt_fee(receipt: &TransactionReceipt) -> U256 {
+    match (receipt.gas_used, receipt.effective_gas_price) {
+        (Some(gas_used), Some(price)) => gas_used * price,
+        _ => U256::zero(),
+    }
+}
+
+// 基于 SQLite 的本地奖励账本
+pub struct RewardLedger {
+    conn: Mutex<Connection>,
+}
+
+impl RewardLedger {
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path).map_err(|e| {
+            anyhow!(
+                "无法打开奖励账本 / Cannot open reward ledger {}: {}",
+                path.display(),
+                e
+            )
+        })?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS rewards (
+                tx_hash TEXT NOT NULL,
+                log_index INTEGER NOT NULL,
+                wallet TEXT NOT NULL,
+                block_number INTEGER NOT NULL,
+                timestamp INTEGER NOT NULL,
+                reward_wei TEXT NOT NULL,
+                gas_used TEXT,
+                effective_gas_price TEXT,
+                fee_wei TEXT,
+                net_profit_wei TEXT,
+                PRIMARY KEY (tx_hash, log_index)
+            );
+            CREATE INDEX IF NOT EXISTS rewards_wallet ON rewards (wallet, timestamp);
+            CREATE TABLE IF NOT EXISTS tasks (
+                tx_hash TEXT NOT NULL,
+                log_index INTEGER NOT NULL,
+                wallet TEXT NOT NULL,
+                block_number INTEGER NOT NULL,
+                timestamp INTEGER NOT NULL,
+                difficulty TEXT NOT NULL,
+                PRIMARY KEY (tx_hash, log_index)
+            );",
+        )?;
+
+        Ok(RewardLedger {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    // 挖矿时写入奖励记录：手续费是整个任务的花费，已有回填的记录时以此为准；
+    // 返回是否为新记录
+    pub fn record(&self, record: &RewardRecord) -> Result<bool> {
+        self.upsert_reward(
+            record,
+            "gas_used = excluded.gas_used,
+             effective_gas_price = excluded.effective_gas_price,
+             fee_wei = excluded.fee_wei,
+             net_profit_wei = excluded.net_profit_wei",
+        )
+    }
+
+    // 从链上日志回填奖励记录：区块号与时间戳以链上为准，
+    // 已有挖矿时记录的任务手续费时保留；返回是否为新记录
+    pub fn import(&self, record: &RewardRecord) -> Result<bool> {
+        self.upsert_reward(
+            record,
+            "block_number = excluded.block_number,
+             timestamp = excluded.timestamp,
+             gas_used = COALESCE(rewards.gas_used, excluded.gas_used),
+             effective_gas_price = COALESCE(rewards.effective_gas_price, excluded.effective_gas_price),
+             fee_wei = COALESCE(rewards.fee_wei, excluded.fee_wei),
+             net_profit_wei = COALESCE(rewards.net_profit_wei, excluded.net_profit_wei)",
+        )
+    }
+
+    fn upsert_reward(&self, record: &RewardRecord, update: &str) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let tx_hash = format!("{:?}", record.tx_hash);
+        let exists = conn
+            .query_row(
+                "SELECT 1 FROM rewards WHERE tx_hash = ?1 AND log_index = ?2",
+                params![tx_hash, record.log_index as i64],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some();
+        conn.execute(
+            &format!(
+                "INSERT INTO rewards (
+                    tx_hash, log_index, wallet, block_number, timestamp, reward_wei,
+                    gas_used, effective_gas_price, fee_wei, net_profit_wei
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+                ON CONFLICT (tx_hash, log_index) DO UPDATE SET {}",
+                update
+            ),
+            params![
+                tx_hash,
+                record.log_index as i64,
+                format!("{:?}", record.wallet),
+                record.block_number as i64,
+                record.timestamp,
+                record.reward.to_string(),
+                record.gas_used.map(|v| v.to_string()),
+                record.effective_gas_price.map(|v| v.to_string()),
+                record.fee.map(|v| v.to_string()),
+                record.net_profit().map(|v| v.to_string()),
+            ],
+        )?;
+        Ok(!exists)
+    }
+
+    // 写入一条任务记录，已存在时忽略；返回是否为新记录
+    pub fn record_task(&self, record: &TaskRecord) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let inserted = conn.execute(
+            "INSERT OR IGNORE INTO tasks (
+                tx_hash, log_index, wallet, block_number, timestamp, difficulty
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                format!("{:?}", record.tx_hash),
+                record.log_index as i64,
+                format!("{:?}", record.wallet),
+                record.block_number as i64,
+                record.timestamp,
+                record.difficulty.to_string(),
+            ],
+        )?;
+        Ok(inserted > 0)
+    }
+
+    pub fn records(&self, wallet: Option<Address>) -> Result<Vec<RewardRecord>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT tx_hash, log_index, wallet, block_number, timestamp, reward_wei,
+                    gas_used, effective_gas_price, fee_wei
+             FROM rewards
+             WHERE ?1 IS NULL OR wallet = ?1
+             ORDER BY timestamp, block_number, log_index",
+        )?;
+
+        let rows = stmt.query_map(params![wallet.map(|w| format!("{:?}", w))], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, i64>(3)?,
+                row.get::<_, i64>(4)?,
+                row.get::<_, String>(5)?,
+                row.get::<_, Option<String>>(6)?,
+                row.get::<_, Option<String>>(7)?,
+                row.get::<_, Option<String>>(8)?,
+            ))
+        })?;
+
+        let mut records = Vec::new();
+        for row in rows {
+            let (tx_hash, log_index, wallet, block_number, timestamp, reward, gas_used, price, fee) =
+                row?;
+            records.push(RewardRecord {
+                tx_hash: tx_hash.parse()?,
+                log_index: log_index as u64,
+                wallet: wallet.parse()?,
+                block_number: block_number as u64,
+                timestamp,
+                reward: U256::from_dec_str(&reward)?,
+                gas_used: gas_used.map(|v| U256::from_dec_str(&v)).transpose()?,
+                effective_gas_price: price.map(|v| U256::from_dec_str(&v)).transpose()?,
+                fee: fee.map(|v| U256::from_dec_str(&v)).transpose()?,
+            });
+        }
+        Ok(records)
+    }
+
+    // 按小时、天或钱包汇总奖励
+    pub fn summarize(
+        &self,
+        period: SummaryPeriod,
+        wallet: Option<Address>,
+    ) -> Result<Vec<RewardSummary>> {
+        let mut groups: BTreeMap<String, RewardSummary> = BTreeMap::new();
+
+        for record in self.records(wallet)? {
+            let key = match period {
+                SummaryPeriod::Hour => format_timestamp(record.timestamp, "%Y-%m-%d %H:00"),
+                SummaryPeriod::Day => format_timestamp(record.timestamp, "%Y-%m-%d"),
+                SummaryPeriod::Wallet => format!("{:?}", record.wallet),
+            };
+            let fee = record.fee.unwrap_or_default();
+
+            let summary = groups.entry(key.clone()).or_insert_with(|| RewardSummary {
+                key,
+                ..Default::default()
+            });
+            summary.count += 1;
+            summary.reward += record.reward;
+            summary.fee += fee;
+            summary.net_profit += I256::from_raw(record.reward) - I256::from_raw(fee);
+        }
+
+        Ok(groups.into_values().collect())
+    }
+}
+
+fn format_timestamp(timestamp: i64, format: &str) -> String {
+    match Local.timestamp_opt(timestamp, 0).single() {
+        Some(time) => time.format(format).to_string(),
+        None => timestamp.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ledger() -> RewardLedger {
+        RewardLedger::open(Path::new(":memory:")).unwrap()
+    }
+
+    // 挖矿时记录：任务总手续费，区块时间
+    fn mined() -> RewardRecord {
+        RewardRecord {
+            tx_hash: H256::repeat_byte(1),
+            log_index: 0,
+            wallet: Address::repeat_byte(2),
+            block_number: 100,
+            timestamp: 1_700_000_000,
+            reward: U256::from(3_000),
+            gas_used: Some(U256::from(21)),
+            effective_gas_price: Some(U256::from(10)),
+            fee: Some(U256::from(500)),
+        }
+    }
+
+    // 链上回填：只有提交交易的手续费
+    fn scanned() -> RewardRecord {
+        RewardRecord {
+            fee: Some(U256::from(210)),
+            ..mined()
+        }
+    }
+
+    fn only_record(ledger: &RewardLedger) -> RewardRecord {
+        let records = ledger.records(None).unwrap();
+        assert_eq!(records.len(), 1);
+        records.into_iter().next().unwrap()
+    }
+
+    #[test]
+    fn import_after_record_keeps_task_fee() {
+        let ledger = ledger();
+        assert!(ledger.record(&mined()).unwrap());
+        assert!(!ledger.import(&scanned()).unwrap());
+
+        let record = only_record(&ledger);
+        assert_eq!(record.fee, Some(U256::from(500)));
+        assert_eq!(record.net_profit(), Some(I256::from(2_500)));
+    }
+
+    #[test]
+    fn record_after_import_replaces_fee() {
+        let ledger = ledger();
+        assert!(ledger.import(&scanned()).unwrap());
+        assert!(!ledger.record(&mined()).unwrap());
+
+        let record = only_record(&ledger);
+        assert_eq!(record.fee, Some(U256::from(500)));
+        assert_eq!(record.timestamp, 1_700_000_000);
+    }
+
+    #[test]
+    fn import_corrects_fallback_timestamp() {
+        let ledger = ledger();
+        // 挖矿时取不到区块，使用了本地时间
+        let local = RewardRecord {
+            timestamp: 1_700_000_042,
+            ..mined()
+        };
+        ledger.record(&local).unwrap();
+        ledger.import(&scanned()).unwrap();
+
+        assert_eq!(only_record(&ledger).timestamp, 1_700_000_000);
+    }
+
+    #[test]
+    fn import_fills_missing_fee() {
+        let ledger = ledger();
+        ledger
+            .record(&RewardRecord {
+                fee: None,
+                ..mined()
+            })
+            .unwrap();
+        ledger.import(&scanned()).unwrap();
+
+        assert_eq!(only_record(&ledger).fee, Some(U256::from(210)));
+    }
+
+    #[test]
+    fn summarize_groups_by_wallet() {
+        let ledger = ledger();
+        ledger.record(&mined()).unwrap();
+        ledger
+            .record(&RewardRecord {
+                tx_hash: H256::repeat_byte(9),
+                ..mined()
+            })
+            .unwrap();
+
+        let summary = ledger.summarize(SummaryPeriod::Wallet, None).unwrap();
+        assert_eq!(summary.len(), 1);
+        assert_eq!(summary[0].count, 2);
+        assert_eq!(summary[0].reward, U256::from(6_000));
+        assert_eq!(summary[0].net_profit, I256::from(5_000));
+    }
+}
@@ -1,17 +1,16 @@
 use anyhow::{anyhow, Result};
+use clap::{Parser, Subcommand};
 use colored::*;
-use dialoguer::{Input, Select};
+use dialoguer::Input;
 use ethers::{
-    abi::Token,
-    prelude::*,
-    providers::{Http, Provider},
+    abi::Token, prelude::*, providers::Provider, types::transaction::eip2718::TypedTransaction,
     utils::keccak256,
 };
 use futures::future::join_all;
-use indicatif::{ProgressBar, ProgressStyle};
 use num_bigint::BigUint;
 use std::{
-    convert::TryFrom,
+    net::SocketAddr,
+    path::{Path, PathBuf},
     sync::{
         atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
         Arc,
@@ -20,11 +19,53 @@ use std::{
 };
 use tokio::time::sleep;
 
+#[macro_use]
+mod console;
+
+mod audit;
+mod chain_events;
+mod chain_guard;
+mod code_pins;
 mod contract;
+mod control_api;
+mod fleet;
+mod http_util;
+mod ledger;
+mod miner_control;
+#[cfg(test)]
+mod mock_rpc;
+mod monitor_socket;
+mod rate_limit;
+mod remote_signer;
+mod resources;
+mod revert;
+mod rpc_config;
+mod rpc_http;
+mod rpc_pool;
+mod scanner;
+mod status_http;
 mod tui_monitor;
-
-use contract::MiningContract;
-use tui_monitor::{start_monitor, MonitorData};
+mod tx_policy;
+mod ws_relay;
+
+use audit::{AuditLog, AuditSigner, AuditSignerError, DEFAULT_AUDIT_PATH};
+use chain_events::ChainEvents;
+use chain_guard::VerifyOptions;
+use code_pins::{CodePins, DEFAULT_CODE_PINS_PATH};
+use contract::{MiningContract, MiningRewardFilter};
+use control_api::DEFAULT_API_TOKEN_FILE;
+use ledger::{receipt_fee, RewardLedger, RewardRecord, SummaryPeriod, DEFAULT_LEDGER_PATH};
+use miner_control::MinerControl;
+use monitor_socket::{MonitorServer, DEFAULT_MONITOR_SOCKET};
+use remote_signer::{MinerSigner, RemoteSigner};
+use revert::{decode_revert_data, diagnose_failed_tx, revert_from_error, RevertInfo};
+use rpc_config::{EndpointConfig, RpcConfig};
+use rpc_pool::{BroadcastResult, Quorum, RpcPool};
+use tui_monitor::{start_monitor, MonitorData, DEFAULT_CHART_MINUTES, DEFAULT_TASK_HISTORY};
+use tx_policy::{PolicySigner, PolicySignerError, PolicyViolation, TxPolicy};
+
+// 挖矿使用的签名者，签名前经过交易策略检查，签名后写入审计日志
+type MiningSigner = AuditSigner<PolicySigner<MinerSigner>>;
 
 // 定义常量
 const CONTRACT_ADDRESS: &str = "0x51e0ab7f7db4a2bf4500dfa59f7a4957afc8c02e";
@@ -37,8 +78,16 @@ const RPC_OPTIONS: [&str; 4] = [
 const MIN_WALLET_BALANCE: f64 = 0.1;
 const MIN_CONTRACT_BALANCE: f64 = 3.0;
 const MAX_RETRIES: usize = 5;
+const RPC_HEALTH_INTERVAL_SECS: u64 = 15; // RPC节点健康检查间隔
+const TASK_WATCH_INTERVAL_SECS: u64 = 5; // 链上任务轮询间隔
+const TASK_WATCH_SUBSCRIBED_SECS: u64 = 30; // 已订阅任务事件时的兜底轮询间隔
+const RECEIPT_TIMEOUT_SECS: u64 = 120; // 等待交易收据的超时时间
+const DEFAULT_RPC_RATE: f64 = 10.0; // 每个节点每秒最多请求数
+const DEFAULT_MAX_TX_PER_HOUR: u32 = 600; // 每小时最多签名的交易数
+const DEFAULT_MAX_DAILY_GAS: f64 = 10.0; // 每日最多花费的 gas（MAG）
 const MINING_TIMEOUT_SECS: u64 = 600; // 10分钟
-                                      // 并行任务数
+const POLICY_DENIED_BACKOFF_SECS: u64 = 60; // 交易被签名策略拒绝后任务槽的等待时间
+                                            // 并行任务数
 const PARALLEL_TASKS: usize = 6; // 同时处理的任务数量
                                  // MagnetChain的chainId
 const CHAIN_ID: u64 = 114514; // 修正为正确的链ID
@@ -46,84 +95,361 @@ const CHAIN_ID: u64 = 114514; // 修正为正确的链ID
 // 全局变量
 lazy_static::lazy_static! {
     static ref MONITOR_DATA: Arc<MonitorData> = Arc::new(MonitorData::new());
-    static ref MONITOR_ENABLED: AtomicBool = AtomicBool::new(false);
     static ref CURRENT_NONCE: std::sync::Mutex<Option<U256>> = std::sync::Mutex::new(None);
 }
 
+#[derive(Parser)]
+#[command(
+    name = "pow-client",
+    version,
+    about = "Magnet POW 区块链挖矿客户端 / Magnet POW blockchain mining client"
+)]
+struct Cli {
+    /// 以监控界面挖矿，等同于 mine --tui / Mine with the monitor UI, same as mine --tui
+    #[arg(long)]
+    monitor: bool,
+
+    /// 监控界面余额与奖励图表显示的历史时长（分钟） / Minutes of balance and reward history shown in the monitor charts
+    #[arg(long, global = true, default_value_t = DEFAULT_CHART_MINUTES)]
+    chart_minutes: u64,
+
+    /// 监控界面保留的已结束任务数，更早的任务只计入汇总 / Finished tasks kept in the monitor task table; older ones are only counted in a summary
+    #[arg(long, global = true, default_value_t = DEFAULT_TASK_HISTORY)]
+    task_history: usize,
+
+    /// 在 Unix 套接字上提供监控数据，供 attach 连接无界面运行的挖矿进程；attach 默认连接 pow-client.sock / Serve the monitor over a Unix socket so `attach` can connect to a headless miner; attach defaults to pow-client.sock
+    #[arg(long, global = true)]
+    monitor_socket: Option<PathBuf>,
+
+    /// 在该地址提供只读的 HTTP 状态接口（GET /status），供 fleet 汇总查看；非本机地址需设置 --status-token / Serve a read-only HTTP status endpoint (GET /status) at this address for fleet; non-loopback addresses need --status-token
+    #[arg(long, global = true)]
+    status_addr: Option<SocketAddr>,
+
+    /// 状态接口的访问令牌，请求需带 `Authorization: Bearer <令牌>` / Access token for the status endpoint, sent as `Authorization: Bearer <token>`
+    #[arg(long, global = true, requires = "status_addr")]
+    status_token: Option<String>,
+
+    /// 在该本机地址提供需令牌的 HTTP/JSON 控制接口，可查看状态、暂停/继续、设置线程数、刷新余额、切换节点与停止挖矿 / Serve a token-protected HTTP/JSON control API on this loopback address for status, pause/resume, thread count, balance refresh, RPC switching and stop
+    #[arg(long, global = true)]
+    api_addr: Option<SocketAddr>,
+
+    /// 控制接口令牌，未指定时从 --api-token-file 读取，文件不存在则生成 / Control API token; read from --api-token-file if omitted, generated there if missing
+    #[arg(long, global = true)]
+    api_token: Option<String>,
+
+    /// 控制接口令牌文件 / Control API token file
+    #[arg(long, global = true, default_value = DEFAULT_API_TOKEN_FILE)]
+    api_token_file: PathBuf,
+
+    /// 奖励账本路径 / Reward ledger path
+    #[arg(long, global = true, default_value = DEFAULT_LEDGER_PATH)]
+    ledger: PathBuf,
+
+    /// 审计日志路径 / Audit log path
+    #[arg(long, global = true, default_value = DEFAULT_AUDIT_PATH)]
+    audit_log: PathBuf,
+
+    /// RPC 节点，可重复指定，默认使用全部内置节点 / RPC endpoint, may be repeated; defaults to all built-in nodes
+    #[arg(long = "rpc", global = true)]
+    rpc_urls: Vec<String>,
+
+    /// RPC 配置文件（TOML），可为节点设置请求头、认证、CA 证书与代理 / RPC config file (TOML) with per-endpoint headers, auth, CA certificates and proxies
+    #[arg(long, global = true)]
+    rpc_config: Option<PathBuf>,
+
+    /// 每个节点每秒最多请求数，0 为不限速 / Max requests per second per endpoint, 0 disables limiting
+    #[arg(long, global = true, default_value_t = DEFAULT_RPC_RATE)]
+    rpc_rate: f64,
+
+    /// 仲裁读取：余额与合约只读调用需 N 个节点中 M 个在同一区块上结果一致，格式 M/N / Quorum reads: M of N endpoints must agree at the same block, e.g. 2/3
+    #[arg(long, global = true)]
+    quorum: Option<Quorum>,
+
+    /// 本地签名后将交易同时广播到所有健康节点 / Sign locally and broadcast transactions to every healthy endpoint
+    #[arg(long, global = true)]
+    broadcast: bool,
+
+    /// 远程签名服务（Clef/web3signer），本机 HTTP 地址或 Unix 套接字路径（其他主机需 HTTPS 与 --allow-remote-signer）；不指定时输入私钥 / Remote signer (Clef/web3signer) as a localhost HTTP URL or Unix socket path (other hosts need HTTPS and --allow-remote-signer); prompts for a private key when omitted
+    #[arg(long, global = true)]
+    signer: Option<String>,
+
+    /// 远程签名服务中使用的账户，服务只有一个账户时可省略 / Account to use on the remote signer, optional if it has only one
+    #[arg(long, global = true, requires = "signer")]
+    signer_address: Option<Address>,
+
+    /// 允许使用其他主机上的 HTTPS 签名服务 / Allow a remote signer on another host over HTTPS
+    #[arg(long, global = true, requires = "signer")]
+    allow_remote_signer: bool,
+
+    /// 额外允许签名的操作：合约函数名（如 withdrawEther）或 transfer，可重复指定 / Extra operations the signer may sign: a contract function name (e.g. withdrawEther) or transfer; may be repeated
+    #[arg(long, global = true)]
+    policy_allow: Vec<String>,
+
+    /// 每小时最多签名的交易数，0 为不限制 / Max transactions signed per hour, 0 disables the limit
+    #[arg(long, global = true, default_value_t = DEFAULT_MAX_TX_PER_HOUR)]
+    max_tx_per_hour: u32,
+
+    /// 每日最多花费的 gas（MAG），0 为不限制 / Max gas spend per day in MAG, 0 disables the limit
+    #[arg(long, global = true, default_value_t = DEFAULT_MAX_DAILY_GAS)]
+    max_daily_gas: f64,
+
+    /// 节点链ID与设置不符时仍继续 / Continue even if the endpoint chain ID does not match
+    #[arg(long, global = true)]
+    allow_chain_mismatch: bool,
+
+    /// 期望的合约代码哈希，默认首次信任：使用首次运行时各节点一致返回并记录的哈希 / Expected contract code hash; defaults to trust on first use: the hash all endpoints agreed on at first run is pinned
+    #[arg(long, global = true)]
+    contract_code_hash: Option<H256>,
+
+    /// 首次信任记录的合约代码哈希文件，与奖励账本分开保存 / File holding the contract code hashes pinned on first use, kept apart from the reward ledger
+    #[arg(long, global = true, default_value = DEFAULT_CODE_PINS_PATH)]
+    code_pins: PathBuf,
+
+    /// 合约代码校验失败时仍继续 / Continue even if contract code verification fails
+    #[arg(long, global = true)]
+    allow_unverified_contract: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// 开始挖矿（默认） / Start mining (default)
+    Mine {
+        /// 在监控界面中显示挖矿状态 / Show the live mining state in the monitor UI
+        #[arg(long)]
+        tui: bool,
+    },
+    /// 连接以 --monitor-socket 运行的挖矿进程并打开监控界面，退出界面不影响挖矿 / Open the monitor UI on a miner running with --monitor-socket; quitting only detaches
+    Attach {
+        /// 允许在界面中操作挖矿进程，默认只读 / Allow operating the miner from the UI; read-only by default
+        #[arg(long)]
+        control: bool,
+    },
+    /// 汇总查看多台挖矿进程 / Dashboard aggregating many miners
+    Fleet {
+        /// 监控套接字路径或状态接口地址（http://主机:端口），可在前面加名称：`名称 地址` / Monitor socket path or status URL (http://host:port), optionally prefixed by a name: `name endpoint`
+        endpoints: Vec<String>,
+
+        /// 主机列表文件，每行一台 / Host list file, one miner per line
+        #[arg(long)]
+        hosts: Option<PathBuf>,
+
+        /// 读取 HTTP 状态接口时使用的令牌（各机器的 --status-token） / Token for HTTP status endpoints (the miners' --status-token)
+        #[arg(long)]
+        token: Option<String>,
+    },
+    /// 汇总奖励账本 / Summarize the reward ledger
+    Rewards {
+        /// 汇总维度 / Group rewards by
+        #[arg(long, value_enum, default_value_t = SummaryPeriod::Day)]
+        by: SummaryPeriod,
+
+        /// 只统计指定钱包 / Only include this wallet
+        #[arg(long)]
+        wallet: Option<Address>,
+    },
+    /// 从链上日志回填历史奖励 / Backfill historical rewards from chain logs
+    Scan {
+        /// 钱包地址，可重复指定 / Wallet address, may be repeated
+        #[arg(long = "address", required = true)]
+        addresses: Vec<Address>,
+
+        /// 起始区块 / Start block
+        #[arg(long, default_value_t = 0)]
+        from_block: u64,
+
+        /// 结束区块，默认为最新区块 / End block, defaults to the latest block
+        #[arg(long)]
+        to_block: Option<u64>,
+    },
+    /// 审计日志操作 / Audit log operations
+    Audit {
+        #[command(subcommand)]
+        action: AuditCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum AuditCommand {
+    /// 校验审计日志的哈希链 / Verify the audit log hash chain
+    Verify,
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
-    // 处理命令行参数
-    let args: Vec<String> = std::env::args().collect();
-    let mut monitor_mode = false;
-
-    for arg in args.iter() {
-        if arg == "h" || arg == "--monitor" {
-            monitor_mode = true;
-            break;
+    console::init();
+
+    // 处理命令行参数，保留旧的 `h` 参数作为 --monitor 的简写
+    let cli = Cli::parse_from(std::env::args().enumerate().map(|(i, arg)| {
+        if i > 0 && arg == "h" {
+            "--monitor".to_string()
+        } else {
+            arg
         }
+    }));
+
+    // 命令行节点与配置文件节点合并；都未指定时使用内置节点
+    let mut rpc_endpoints: Vec<EndpointConfig> = cli
+        .rpc_urls
+        .iter()
+        .map(|url| EndpointConfig::from_url(url))
+        .collect();
+    if let Some(path) = &cli.rpc_config {
+        rpc_endpoints.extend(RpcConfig::load(path)?.endpoint);
+    }
+    if rpc_endpoints.is_empty() {
+        rpc_endpoints = RPC_OPTIONS
+            .iter()
+            .map(|url| EndpointConfig::from_url(url))
+            .collect();
+    }
+    for endpoint in &mut rpc_endpoints {
+        endpoint.rate_limit.get_or_insert(cli.rpc_rate);
     }
 
-    if monitor_mode {
-        // 启动监控
-        MONITOR_ENABLED.store(true, Ordering::SeqCst);
-        let monitor_data = start_monitor();
-        std::mem::forget(monitor_data); // 防止数据被释放
+    let tui = cli.monitor || matches!(cli.command, Some(Command::Mine { tui: true }));
+    let verify_options = VerifyOptions {
+        allow_chain_mismatch: cli.allow_chain_mismatch,
+        allow_unverified_contract: cli.allow_unverified_contract,
+        code_hash: cli.contract_code_hash,
+    };
 
-        // 等待用户输入退出命令
-        println!("已进入监控模式，按 'exit' 退出");
-        loop {
-            let mut input = String::new();
-            if std::io::stdin().read_line(&mut input).is_ok() {
-                if input.trim() == "exit" {
-                    break;
-                }
+    match cli.command {
+        Some(Command::Rewards { by, wallet }) => {
+            return print_reward_summary(&cli.ledger, by, wallet);
+        }
+        Some(Command::Scan {
+            addresses,
+            from_block,
+            to_block,
+        }) => {
+            let pool = connect_rpc_pool(&rpc_endpoints, cli.quorum).await?;
+            pool.check_health().await;
+            let ledger = RewardLedger::open(&cli.ledger)?;
+            // 与挖矿相同的链与合约校验，错误或伪造的节点不能写入奖励记录
+            chain_guard::verify(
+                &pool,
+                &CodePins::open(&cli.code_pins)?,
+                CHAIN_ID,
+                CONTRACT_ADDRESS.parse()?,
+                verify_options,
+            )
+            .await?;
+            let provider = Provider::new(pool);
+            scanner::scan_history(
+                &provider,
+                &ledger,
+                CONTRACT_ADDRESS.parse()?,
+                &addresses,
+                from_block,
+                to_block,
+            )
+            .await?;
+            return Ok(());
+        }
+        Some(Command::Audit {
+            action: AuditCommand::Verify,
+        }) => {
+            let count = audit::verify(&cli.audit_log)?;
+            console_println!(
+                "{}",
+                format!(
+                    "审计日志完整，共 {} 条记录 / Audit log intact, {} entries",
+                    count, count
+                )
+                .green()
+            );
+            return Ok(());
+        }
+        Some(Command::Attach { control }) => {
+            let path = cli
+                .monitor_socket
+                .unwrap_or_else(|| PathBuf::from(DEFAULT_MONITOR_SOCKET));
+            return monitor_socket::attach(&path, control);
+        }
+        Some(Command::Fleet {
+            endpoints,
+            hosts,
+            token,
+        }) => {
+            let mut entries = endpoints;
+            if let Some(path) = &hosts {
+                entries.extend(fleet::load_hosts(path)?);
             }
-            tokio::time::sleep(Duration::from_millis(100)).await;
+            return fleet::run(entries, token).await;
         }
+        Some(Command::Mine { .. }) | None => {}
+    }
 
-        println!("正在退出监控模式...");
-        return Ok(());
+    // 挖矿状态同步到 MONITOR_DATA，监控界面、控制台进度、监控套接字与状态接口都从中读取
+    let serve_monitor =
+        cli.monitor_socket.is_some() || cli.status_addr.is_some() || cli.api_addr.is_some();
+    MONITOR_DATA.set_chart_minutes(cli.chart_minutes);
+    MONITOR_DATA.set_task_history(cli.task_history);
+    spawn_hashrate_sampler();
+    // 无界面时输出同时写入监控日志，attach 的界面可以看到启动以来的输出
+    if !tui && serve_monitor {
+        console::mirror(MONITOR_DATA.clone());
     }
 
     print_welcome_message();
 
-    // 选择RPC节点
-    let rpc_url = select_rpc_node()?;
-    println!(
+    // 打开奖励账本
+    let ledger = Arc::new(RewardLedger::open(&cli.ledger)?);
+    console_println!(
         "{}",
-        format!("已选择 RPC / Selected RPC: {}", rpc_url).green()
+        format!("奖励账本 / Reward ledger: {}", cli.ledger.display()).green()
     );
 
-    // 初始化以太坊提供者
-    let provider = Provider::<Http>::try_from(rpc_url)?;
+    // 打开审计日志
+    let audit = Arc::new(AuditLog::open(&cli.audit_log)?);
+    console_println!(
+        "{}",
+        format!("审计日志 / Audit log: {}", cli.audit_log.display()).green()
+    );
 
-    // 显示链ID信息
-    match provider.get_chainid().await {
-        Ok(chainid) => {
-            println!(
-                "{}",
-                format!(
-                    "连接到链ID: {} / Connected to chain ID: {}",
-                    chainid, chainid
-                )
-                .green()
-            );
-            if chainid != U256::from(CHAIN_ID) {
-                println!("{}", format!("警告：检测到的链ID与设置的不符！ / Warning: Detected chain ID does not match configuration!").yellow());
-            }
-        }
-        Err(e) => {
-            println!(
-                "{}",
-                format!("无法获取链ID: {} / Could not get chain ID: {}", e, e).yellow()
-            );
-        }
-    }
+    // 初始化RPC节点池并检查各节点状态
+    let pool = connect_rpc_pool(&rpc_endpoints, cli.quorum).await?;
+    pool.check_health().await;
+    rpc_pool::print_status(&pool.status());
+    spawn_rpc_health_monitor(pool.clone());
 
-    // 输入私钥并创建钱包
-    let wallet = input_private_key(provider).await?;
+    // 初始化以太坊提供者
+    let provider = Provider::new(pool.clone());
+
+    // 校验链ID与合约代码，未通过时拒绝继续
+    chain_guard::verify(
+        &pool,
+        &CodePins::open(&cli.code_pins)?,
+        CHAIN_ID,
+        CONTRACT_ADDRESS.parse()?,
+        verify_options,
+    )
+    .await?;
+
+    // 交易策略：签名前检查目标、函数与频率/花费上限
+    let policy = TxPolicy::new(
+        CONTRACT_ADDRESS.parse()?,
+        &cli.policy_allow,
+        cli.max_tx_per_hour,
+        ethers::utils::parse_ether(cli.max_daily_gas)?,
+    )?;
+
+    // 连接远程签名服务，或输入私钥创建钱包
+    let signer = match &cli.signer {
+        Some(url) => {
+            connect_remote_signer(url, cli.signer_address, cli.allow_remote_signer).await?
+        }
+        None => input_private_key().await?,
+    };
+    let wallet = SignerMiddleware::new(
+        provider,
+        AuditSigner::new(PolicySigner::new(signer, Arc::new(policy)), audit.clone()),
+    );
     let wallet_address = wallet.address();
-    println!(
+    console_println!(
         "{}",
         format!("钱包地址 / Wallet address: {}", wallet_address).green()
     );
@@ -131,66 +457,233 @@ async fn main() -> Result<()> {
     // 检查钱包余额
     let balance = check_wallet_balance(&wallet).await?;
 
-    if MONITOR_ENABLED.load(Ordering::SeqCst) {
-        // 如果启用了监控，更新钱包余额
-        MONITOR_DATA.update_balance(
-            ethers::utils::format_ether(balance)
-                .parse::<f64>()
-                .unwrap_or(0.0),
-        );
-    }
+    // 更新钱包余额
+    MONITOR_DATA.update_balance(
+        ethers::utils::format_ether(balance)
+            .parse::<f64>()
+            .unwrap_or(0.0),
+    );
 
     // 初始化合约
     let contract = init_contract(wallet).await?;
 
-    // 检查合约余额
-    check_contract_balance(&contract).await?;
+    // 一次批量读取合约余额、当前任务、nonce 与 gas 价格
+    let snapshot = fetch_chain_snapshot(&contract, &pool).await?;
+    report_contract_balance(snapshot.contract_balance)?;
+    report_chain_snapshot(&snapshot);
 
     // 初始化nonce
-    initialize_nonce(&contract.client()).await?;
+    initialize_nonce(snapshot.nonce);
+
+    // 订阅新区块与任务事件，节点不支持时自动轮询
+    let events = ChainEvents::spawn(pool.clone(), contract.address(), wallet_address);
+    if cli.broadcast {
+        console_println!(
+            "{}",
+            "交易将广播到所有健康节点 / Transactions will be broadcast to every healthy endpoint"
+                .cyan()
+        );
+    }
+    let control = Arc::new(MinerControl::new(
+        pool.clone(),
+        num_cpus::get(),
+        PARALLEL_TASKS,
+    ));
+    let ctx = MiningContext {
+        ledger,
+        audit,
+        events,
+        pool,
+        control: control.clone(),
+        broadcast: cli.broadcast,
+    };
 
     // 开始挖矿循环
-    println!("{}", "\n挖矿模式 / Mining Mode:".bold());
-    println!(
+    console_println!("{}", "\n挖矿模式 / Mining Mode:".bold());
+    console_println!(
         "{}",
         "免费挖矿 (3 MAG 每次哈希) / Free Mining (3 MAG per hash)".cyan()
     );
-    println!("{}", "\n开始挖矿 / Starting mining...".bold().green());
+    console_println!("{}", "\n开始挖矿 / Starting mining...".bold().green());
+
+    MONITOR_DATA.update_rpc_status(ctx.pool.status());
+    // 套接字在挖矿结束、本函数返回时删除
+    let _monitor_server = match &cli.monitor_socket {
+        Some(path) => {
+            let server = MonitorServer::bind(path, MONITOR_DATA.clone(), control.clone())?;
+            console_println!(
+                "{}",
+                format!(
+                    "监控套接字 / Monitor socket: {}，使用 attach 连接 / connect with attach",
+                    path.display()
+                )
+                .green()
+            );
+            Some(server)
+        }
+        None => None,
+    };
+    if let Some(addr) = cli.status_addr {
+        let addr = status_http::spawn(
+            addr,
+            cli.status_token.clone(),
+            MONITOR_DATA.clone(),
+            control.clone(),
+        )
+        .await?;
+        console_println!(
+            "{}",
+            format!("状态接口 / Status endpoint: http://{}/status", addr).green()
+        );
+    }
+
+    // 与任务共用的停止标志，设置后进行中的任务在下一个检查点放弃
+    let stop_mining = control.stop_flag();
+    if let Some(addr) = cli.api_addr {
+        let token = match cli.api_token {
+            Some(token) => token,
+            None => {
+                let token = control_api::load_or_create_token(&cli.api_token_file)?;
+                console_println!(
+                    "{}",
+                    format!(
+                        "控制接口令牌文件 / Control API token file: {}",
+                        cli.api_token_file.display()
+                    )
+                    .green()
+                );
+                token
+            }
+        };
+        let addr = control_api::spawn(addr, token, MONITOR_DATA.clone(), control.clone()).await?;
+        console_println!(
+            "{}",
+            format!("控制接口 / Control API: http://{}/api", addr).green()
+        );
+    }
+    if !tui {
+        // 无界面时在控制台绘制任务进度与汇总
+        let progress = console::start_progress(MONITOR_DATA.clone(), stop_mining.clone());
+        let result = start_mining_loop(contract, ctx, stop_mining.clone()).await;
+        stop_mining.store(true, Ordering::SeqCst);
+        let _ = progress.join();
+        return result;
+    }
+
+    // 监控界面接管终端，退出界面即停止挖矿
+    let monitor = start_monitor(MONITOR_DATA.clone(), stop_mining.clone(), control);
+    let result = start_mining_loop(contract, ctx, stop_mining.clone()).await;
+    stop_mining.store(true, Ordering::SeqCst);
+    let _ = monitor.join();
+    result
+}
+
+// 连接RPC节点池，按需启用仲裁读取
+async fn connect_rpc_pool(endpoints: &[EndpointConfig], quorum: Option<Quorum>) -> Result<RpcPool> {
+    let pool = RpcPool::connect(endpoints).await?;
+    let Some(quorum) = quorum else {
+        return Ok(pool);
+    };
+
+    console_println!(
+        "{}",
+        format!(
+            "仲裁读取：{}/{} 个节点一致 / Quorum reads: {} of {} endpoints must agree",
+            quorum.threshold, quorum.size, quorum.threshold, quorum.size
+        )
+        .cyan()
+    );
+    Ok(pool.with_quorum(quorum))
+}
 
-    start_mining_loop(contract).await?;
+// 打印奖励账本汇总
+fn print_reward_summary(path: &Path, by: SummaryPeriod, wallet: Option<Address>) -> Result<()> {
+    let ledger = RewardLedger::open(path)?;
+    let summaries = ledger.summarize(by, wallet)?;
+
+    if summaries.is_empty() {
+        console_println!(warn: "{}", "账本中暂无奖励记录 / No rewards in ledger".yellow());
+        return Ok(());
+    }
+
+    let key_title = match by {
+        SummaryPeriod::Hour => "小时 / Hour",
+        SummaryPeriod::Day => "日期 / Day",
+        SummaryPeriod::Wallet => "钱包 / Wallet",
+    };
+    console_println!(
+        "{}",
+        format!(
+            "{:<44} {:>8} {:>18} {:>18} {:>18}",
+            key_title, "次数/Count", "奖励/Reward", "手续费/Fee", "净收益/Net"
+        )
+        .bold()
+    );
+
+    let mut total_count = 0;
+    let mut total_reward = U256::zero();
+    let mut total_fee = U256::zero();
+    let mut total_net = I256::zero();
+    for summary in &summaries {
+        console_println!(
+            "{:<44} {:>8} {:>18} {:>18} {:>18}",
+            summary.key,
+            summary.count,
+            ethers::utils::format_ether(summary.reward),
+            ethers::utils::format_ether(summary.fee),
+            ethers::utils::format_ether(summary.net_profit)
+        );
+        total_count += summary.count;
+        total_reward += summary.reward;
+        total_fee += summary.fee;
+        total_net += summary.net_profit;
+    }
+
+    console_println!(
+        "{}",
+        format!(
+            "{:<44} {:>8} {:>18} {:>18} {:>18}",
+            "合计 / Total",
+            total_count,
+            ethers::utils::format_ether(total_reward),
+            ethers::utils::format_ether(total_fee),
+            ethers::utils::format_ether(total_net)
+        )
+        .green()
+    );
 
     Ok(())
 }
 
 fn print_welcome_message() {
-    println!(
+    console_println!(
         "{}",
         " 你好，欢迎使用 Magnet POW 区块链挖矿客户端！ "
             .bold()
             .on_cyan()
             .black()
     );
-    println!(
+    console_println!(
         "{}",
         " Hello, welcome to Magnet POW Blockchain Mining Client! "
             .bold()
             .on_cyan()
             .black()
     );
-    println!(
+    console_println!(
         "{}",
         "启动挖矿客户端，需要确保钱包里有0.1MAG，如果没有，加入TG群免费领取0.1 MAG空投。"
             .bold()
             .magenta()
     );
-    println!("{}", "To start the mining client, ensure your wallet has 0.1 MAG. If not, join the Telegram group for a free 0.1 MAG airdrop.".bold().magenta());
-    println!(
+    console_println!("{}", "To start the mining client, ensure your wallet has 0.1 MAG. If not, join the Telegram group for a free 0.1 MAG airdrop.".bold().magenta());
+    console_println!(
         "{}",
         "TG群链接 / Telegram group link: https://t.me/MagnetPOW"
             .bold()
             .magenta()
     );
-    println!(
+    console_println!(
         "{}",
         format!(
             "网络信息 / Network Info: 链ID / Chain ID: {}, 货币符号 / Symbol: MAG",
@@ -200,25 +693,56 @@ fn print_welcome_message() {
     );
 }
 
-fn select_rpc_node() -> Result<&'static str> {
-    println!("{}", "\n选择 RPC 节点 / Select RPC Node:".bold());
-
-    for (i, rpc) in RPC_OPTIONS.iter().enumerate() {
-        println!("{}", format!("{}. {}", i + 1, rpc).cyan());
-    }
+// 定期检查RPC节点健康状况，并同步到监控数据
+fn spawn_rpc_health_monitor(pool: RpcPool) {
+    tokio::spawn(async move {
+        loop {
+            sleep(Duration::from_secs(RPC_HEALTH_INTERVAL_SECS)).await;
+            pool.check_health().await;
+
+            let status = pool.status();
+            if status.iter().all(|ep| !ep.is_healthy()) {
+                console_eprintln!(
+                    error: "{}",
+                    "所有 RPC 节点均不健康 / No healthy RPC endpoint available".red()
+                );
+                rpc_pool::print_status(&status);
+            }
+            MONITOR_DATA.update_rpc_status(status);
+        }
+    });
+}
 
-    let selection = Select::new()
-        .with_prompt("选择节点 / Select node")
-        .items(&RPC_OPTIONS)
-        .default(0)
-        .interact()?;
+// 每秒记录一次总算力，供监控图表使用
+fn spawn_hashrate_sampler() {
+    tokio::spawn(async {
+        loop {
+            sleep(Duration::from_secs(1)).await;
+            MONITOR_DATA.sample_hashrate();
+        }
+    });
+}
 
-    Ok(RPC_OPTIONS[selection])
+// 使用远程签名服务，本机不保存私钥
+async fn connect_remote_signer(
+    url: &str,
+    address: Option<Address>,
+    allow_remote: bool,
+) -> Result<MinerSigner> {
+    let signer = RemoteSigner::connect(url, address, CHAIN_ID, allow_remote).await?;
+    console_println!(
+        "{}",
+        format!(
+            "使用远程签名服务 {} / Using remote signer {}",
+            signer.url(),
+            signer.url()
+        )
+        .green()
+    );
+    Ok(MinerSigner::Remote(signer))
 }
 
-async fn input_private_key<P: JsonRpcClient + 'static + Clone>(
-    provider: Provider<P>,
-) -> Result<SignerMiddleware<Provider<P>, LocalWallet>> {
+async fn input_private_key() -> Result<MinerSigner> {
     let max_attempts = 3;
     let mut attempts = 0;
 
@@ -238,7 +762,7 @@ async fn input_private_key<P: JsonRpcClient + 'static + Clone>(
             Ok(mut wallet) => {
                 // 设置钱包的chainId
                 wallet = wallet.with_chain_id(CHAIN_ID);
-                println!(
+                console_println!(
                     "{}",
                     format!(
                         "已设置钱包chainId为: {} / Set wallet chainId to: {}",
@@ -247,13 +771,12 @@ async fn input_private_key<P: JsonRpcClient + 'static + Clone>(
                     .green()
                 );
 
-                let client = SignerMiddleware::new(provider.clone(), wallet);
-                return Ok(client);
+                return Ok(MinerSigner::Local(wallet));
             }
             Err(e) => {
                 attempts += 1;
-                eprintln!(
-                    "{}",
+                console_eprintln!(
+                    error: "{}",
                     format!(
                         "私钥解析错误 / Private key parsing error: {}. 还剩 {} 次尝试。 / {} attempts left.",
                         e,
@@ -276,10 +799,16 @@ async fn input_private_key<P: JsonRpcClient + 'static + Clone>(
 }
 
 async fn check_wallet_balance<M: Middleware + 'static>(
-    wallet: &SignerMiddleware<M, LocalWallet>,
+    wallet: &SignerMiddleware<M, MiningSigner>,
 ) -> Result<U256> {
     let balance = wallet.get_balance(wallet.address(), None).await?;
-    println!(
+    report_wallet_balance(balance)?;
+    Ok(balance)
+}
+
+// 打印钱包余额并检查是否足够
+fn report_wallet_balance(balance: U256) -> Result<()> {
+    console_println!(
         "{}",
         format!(
             "当前余额 / Current balance: {} MAG",
@@ -288,14 +817,12 @@ async fn check_wallet_balance<M: Middleware + 'static>(
         .green()
     );
 
-    // 如果启用了监控，更新钱包余额
-    if MONITOR_ENABLED.load(Ordering::SeqCst) {
-        MONITOR_DATA.update_balance(
-            ethers::utils::format_ether(balance)
-                .parse::<f64>()
-                .unwrap_or(0.0),
-        );
-    }
+    // 更新钱包余额
+    MONITOR_DATA.update_balance(
+        ethers::utils::format_ether(balance)
+            .parse::<f64>()
+            .unwrap_or(0.0),
+    );
 
     let min_balance = ethers::utils::parse_ether(MIN_WALLET_BALANCE)?;
     if balance < min_balance {
@@ -307,19 +834,19 @@ async fn check_wallet_balance<M: Middleware + 'static>(
         ));
     }
 
-    Ok(balance)
+    Ok(())
 }
 
 async fn init_contract<M: Middleware + 'static>(
-    wallet: SignerMiddleware<M, LocalWallet>,
-) -> Result<MiningContract<SignerMiddleware<M, LocalWallet>>> {
+    wallet: SignerMiddleware<M, MiningSigner>,
+) -> Result<MiningContract<SignerMiddleware<M, MiningSigner>>> {
     let contract_address = CONTRACT_ADDRESS.parse::<Address>()?;
 
     // 显示当前钱包信息和设置
-    println!("{}", format!("钱包信息 / Wallet info:").cyan());
-    println!("{}", format!("地址 / Address: {}", wallet.address()).cyan());
-    println!("{}", format!("链ID / Chain ID: {}", CHAIN_ID).cyan());
-    println!(
+    console_println!("{}", "钱包信息 / Wallet info:".cyan());
+    console_println!("{}", format!("地址 / Address: {}", wallet.address()).cyan());
+    console_println!("{}", format!("链ID / Chain ID: {}", CHAIN_ID).cyan());
+    console_println!(
         "{}",
         format!("合约地址 / Contract address: {}", contract_address).cyan()
     );
@@ -328,11 +855,9 @@ async fn init_contract<M: Middleware + 'static>(
     Ok(contract)
 }
 
-async fn check_contract_balance<M: Middleware + 'static>(
-    contract: &MiningContract<M>,
-) -> Result<U256> {
-    let contract_balance = contract.get_contract_balance().call().await?;
-    println!(
+// 打印合约余额并检查是否足够
+fn report_contract_balance(contract_balance: U256) -> Result<()> {
+    console_println!(
         "{}",
         format!(
             "池中余额 / Pool balance: {} MAG",
@@ -341,6 +866,12 @@ async fn check_contract_balance<M: Middleware + 'static>(
         .green()
     );
 
+    MONITOR_DATA.update_contract_balance(
+        ethers::utils::format_ether(contract_balance)
+            .parse::<f64>()
+            .unwrap_or(0.0),
+    );
+
     let min_contract_balance = ethers::utils::parse_ether(MIN_CONTRACT_BALANCE)?;
     if contract_balance < min_contract_balance {
         return Err(anyhow!(
@@ -351,13 +882,240 @@ async fn check_contract_balance<M: Middleware + 'static>(
         ));
     }
 
-    Ok(contract_balance)
+    Ok(())
+}
+
+// 一次批量请求取得的链上状态
+struct ChainSnapshot {
+    wallet_balance: U256,
+    contract_balance: U256,
+    task: (U256, U256, bool),
+    nonce: U256,
+    gas_price: U256,
+}
+
+// 将钱包余额、合约余额、当前任务、nonce 与 gas 价格合并为一个批量请求
+async fn fetch_chain_snapshot<M: Middleware + 'static>(
+    contract: &MiningContract<SignerMiddleware<M, MiningSigner>>,
+    pool: &RpcPool,
+) -> Result<ChainSnapshot> {
+    let address = contract.client().address();
+    let view_call = |data: Option<Bytes>| serde_json::json!([{ "from": address, "to": contract.address(), "data": data }, "latest"]);
+    let calls = [
+        ("eth_getBalance", serde_json::json!([address, "latest"])),
+        (
+            "eth_call",
+            view_call(contract.get_contract_balance().calldata()),
+        ),
+        ("eth_call", view_call(contract.get_my_task().calldata())),
+        (
+            "eth_getTransactionCount",
+            serde_json::json!([address, "latest"]),
+        ),
+        ("eth_gasPrice", serde_json::json!([])),
+    ];
+
+    let mut results = pool.batch(&calls).await.into_iter().zip(calls.iter());
+    let mut next = || -> Result<serde_json::Value> {
+        let (result, (method, _)) = results
+            .next()
+            .ok_or_else(|| anyhow!("批量读取结果缺失 / Missing batched read result"))?;
+        result.map_err(|e| {
+            anyhow!(
+                "批量读取 {} 失败 / Batched {} failed: {}",
+                method,
+                method,
+                e
+            )
+        })
+    };
+
+    let wallet_balance: U256 = serde_json::from_value(next()?)?;
+    let contract_balance: Bytes = serde_json::from_value(next()?)?;
+    let task: Bytes = serde_json::from_value(next()?)?;
+    let nonce: U256 = serde_json::from_value(next()?)?;
+    let gas_price: U256 = serde_json::from_value(next()?)?;
+
+    Ok(ChainSnapshot {
+        wallet_balance,
+        contract_balance: contract.decode_output("getContractBalance", contract_balance)?,
+        task: contract.decode_output("getMyTask", task)?,
+        nonce,
+        gas_price,
+    })
+}
+
+// 打印批量读取到的任务、nonce 与 gas 价格
+fn report_chain_snapshot(snapshot: &ChainSnapshot) {
+    let (task_nonce, difficulty, active) = snapshot.task;
+    console_println!(
+        "{}",
+        format!(
+            "当前任务 / Current task: {} (nonce: {}, 难度 / difficulty: {}), 链上 nonce / Account nonce: {}, Gas 价格 / Gas price: {} gwei",
+            if active { "进行中 / active" } else { "无 / none" },
+            task_nonce,
+            difficulty,
+            snapshot.nonce,
+            ethers::utils::format_units(snapshot.gas_price, "gwei").unwrap_or_default()
+        )
+        .cyan()
+    );
+}
+
+// 挖矿任务共享的上下文
+#[derive(Clone)]
+struct MiningContext {
+    ledger: Arc<RewardLedger>,
+    audit: Arc<AuditLog>,
+    events: Arc<ChainEvents>,
+    pool: RpcPool,
+    // 监控界面的暂停、线程数、取消等控制
+    control: Arc<MinerControl>,
+    // 启用时交易将发往所有健康节点
+    broadcast: bool,
+}
+
+// 已发送的交易
+enum SentTx {
+    Direct(H256),
+    Broadcast(BroadcastResult),
+}
+
+impl SentTx {
+    fn tx_hash(&self) -> H256 {
+        match self {
+            SentTx::Direct(hash) => *hash,
+            SentTx::Broadcast(result) => result.tx_hash,
+        }
+    }
+}
+
+// 发送交易失败的原因，能解码回滚数据时附带回滚信息
+struct SendFailure {
+    error: String,
+    revert: Option<RevertInfo>,
+    // 交易未签名就失败，nonce 未被使用
+    unsent: bool,
+    // 被签名策略拒绝，重试也不会通过
+    denied: Option<PolicyViolation>,
+}
+
+impl SendFailure {
+    fn from_middleware<E: MiddlewareError>(error: &E) -> Self {
+        SendFailure {
+            error: error.to_string(),
+            revert: revert_from_error(error),
+            unsent: false,
+            denied: None,
+        }
+    }
+
+    fn unsent(mut self) -> Self {
+        self.unsent = true;
+        self
+    }
+}
+
+// 发送交易：在本地签名后提交原始交易，启用广播时并行提交到所有健康节点
+async fn send_mining_tx<M: Middleware + 'static>(
+    contract: &MiningContract<SignerMiddleware<M, MiningSigner>>,
+    ctx: &MiningContext,
+    mut tx: TypedTransaction,
+) -> std::result::Result<SentTx, SendFailure> {
+    let client = contract.client();
+    client
+        .fill_transaction(&mut tx, None)
+        .await
+        .map_err(|e| SendFailure::from_middleware(&e).unsent())?;
+    let signature = match client.signer().sign_transaction(&tx).await {
+        Ok(signature) => signature,
+        Err(AuditSignerError::Signer(PolicySignerError::Denied(violation))) => {
+            return Err(SendFailure {
+                error: violation.to_string(),
+                revert: None,
+                unsent: true,
+                denied: Some(violation),
+            })
+        }
+        Err(e) => {
+            return Err(SendFailure {
+                error: e.to_string(),
+                revert: None,
+                unsent: true,
+                denied: None,
+            })
+        }
+    };
+    let raw = tx.rlp_signed(&signature);
+
+    if !ctx.broadcast {
+        return match client.inner().send_raw_transaction(raw).await {
+            Ok(pending) => Ok(SentTx::Direct(pending.tx_hash())),
+            Err(e) => Err(SendFailure::from_middleware(&e)),
+        };
+    }
+
+    match ctx.pool.broadcast_raw_transaction(&raw).await {
+        Ok(result) => {
+            console_println!(
+                "{}",
+                format!(
+                    "交易 {:?} 已被 {} 个节点接受 / Transaction {:?} accepted by {} endpoints: {}",
+                    result.tx_hash,
+                    result.accepted.len(),
+                    result.tx_hash,
+                    result.accepted.len(),
+                    result.accepted.join(", ")
+                )
+                .cyan()
+            );
+            Ok(SentTx::Broadcast(result))
+        }
+        Err(e) => Err(SendFailure::from_middleware(&ProviderError::from(e))),
+    }
+}
+
+// 等待交易收据，广播的交易同时记录最先报告打包的节点
+async fn wait_for_mining_tx<M: Middleware + 'static>(
+    contract: &MiningContract<SignerMiddleware<M, MiningSigner>>,
+    ctx: &MiningContext,
+    sent: &SentTx,
+) -> Result<Option<TransactionReceipt>> {
+    let timeout = Duration::from_secs(RECEIPT_TIMEOUT_SECS);
+    let receipt = match sent {
+        SentTx::Broadcast(result) => {
+            ctx.events
+                .wait_for_broadcast(&ctx.pool, result, timeout)
+                .await?
+        }
+        SentTx::Direct(_) => {
+            ctx.events
+                .wait_for_receipt(&*contract.client(), sent.tx_hash(), timeout)
+                .await?
+        }
+    };
+
+    // 在审计日志中追加执行结果；交易已上链，写入失败不影响结果，否则会被当作失败重发
+    if let Some(receipt) = &receipt {
+        if let Err(e) = ctx.audit.record_receipt(receipt) {
+            console_eprintln!(
+                error: "{}",
+                format!(
+                    "无法在审计日志中记录交易 {:?} 的收据 / Cannot record receipt of {:?} in the audit log: {}",
+                    receipt.transaction_hash, receipt.transaction_hash, e
+                )
+                .red()
+            );
+        }
+    }
+    Ok(receipt)
 }
 
 async fn start_mining_loop<M: Middleware + 'static>(
-    contract: MiningContract<SignerMiddleware<M, LocalWallet>>,
+    contract: MiningContract<SignerMiddleware<M, MiningSigner>>,
+    ctx: MiningContext,
+    stop_mining: Arc<AtomicBool>,
 ) -> Result<()> {
-    let stop_mining = Arc::new(AtomicBool::new(false));
     let active_tasks = Arc::new(AtomicUsize::new(0));
     let completed_tasks = Arc::new(AtomicUsize::new(0));
     let total_tasks_count = Arc::new(AtomicUsize::new(0));
@@ -365,16 +1123,20 @@ async fn start_mining_loop<M: Middleware + 'static>(
     // Ctrl+C 处理
     let stop_mining_clone = stop_mining.clone();
     ctrlc::set_handler(move || {
-        println!("{}", "\n接收到停止信号，正在安全停止挖矿... / Received stop signal, safely stopping mining...".yellow());
+        console_println!(warn: "{}", "\n接收到停止信号，正在安全停止挖矿... / Received stop signal, safely stopping mining...".yellow());
         stop_mining_clone.store(true, Ordering::SeqCst);
     }).expect("Error setting Ctrl-C handler");
 
-    println!(
+    console_println!(
         "{}",
-        format!("并行任务数 / Parallel tasks: {}", PARALLEL_TASKS).cyan()
+        format!(
+            "并行任务数 / Parallel tasks: {}",
+            ctx.control.parallel_tasks()
+        )
+        .cyan()
     );
-    println!(
-        "{}",
+    console_println!(
+        warn: "{}",
         "按 Ctrl+C 停止挖矿 / Press Ctrl+C to stop mining".yellow()
     );
 
@@ -385,9 +1147,9 @@ async fn start_mining_loop<M: Middleware + 'static>(
             break;
         }
 
-        // 检查是否有足够的线程槽用于新任务
-        while active_tasks.load(Ordering::SeqCst) < PARALLEL_TASKS {
-            if stop_mining.load(Ordering::SeqCst) {
+        // 检查是否有足够的线程槽用于新任务，暂停时不再请求新任务
+        while active_tasks.load(Ordering::SeqCst) < ctx.control.parallel_tasks() {
+            if stop_mining.load(Ordering::SeqCst) || ctx.control.is_paused() {
                 break;
             }
 
@@ -397,18 +1159,19 @@ async fn start_mining_loop<M: Middleware + 'static>(
             let active_tasks_clone = active_tasks.clone();
             let completed_tasks_clone = completed_tasks.clone();
             let total_mined_clone = total_mined.clone();
+            let ctx_clone = ctx.clone();
+            let stop_clone = stop_mining.clone();
 
-            // 如果启用了监控，添加任务到监控数据
-            if MONITOR_ENABLED.load(Ordering::SeqCst) {
-                MONITOR_DATA.add_task(task_id);
-            }
+            // 添加任务到监控数据
+            MONITOR_DATA.add_task(task_id, format!("{:?}", contract.client().address()));
 
             tokio::spawn(async move {
-                let result = mine_once(&contract_clone, task_id).await;
+                let result = mine_once(&contract_clone, task_id, &ctx_clone).await;
+                ctx_clone.control.finish_task(task_id);
 
                 if let Err(e) = result {
-                    eprintln!(
-                        "{}",
+                    console_eprintln!(
+                        error: "{}",
                         format!(
                             "任务 #{} 失败: {} / Task #{} failed: {}",
                             task_id, e, task_id, e
@@ -416,35 +1179,42 @@ async fn start_mining_loop<M: Middleware + 'static>(
                         .red()
                     );
 
-                    // 如果启用了监控，更新任务状态为失败
-                    if MONITOR_ENABLED.load(Ordering::SeqCst) {
-                        MONITOR_DATA.complete_task(task_id, false);
+                    // 更新任务状态为失败
+                    MONITOR_DATA.set_task_error(task_id, e.to_string());
+                    MONITOR_DATA.complete_task(task_id, false);
+
+                    // 被签名策略拒绝时（如达到频率或花费上限）占住任务槽一段时间，
+                    // 避免不断创建注定被拒绝的任务
+                    if e.downcast_ref::<PolicyViolation>().is_some() {
+                        let until =
+                            Instant::now() + Duration::from_secs(POLICY_DENIED_BACKOFF_SECS);
+                        while Instant::now() < until && !stop_clone.load(Ordering::SeqCst) {
+                            sleep(Duration::from_millis(500)).await;
+                        }
                     }
                 } else {
                     completed_tasks_clone.fetch_add(1, Ordering::SeqCst);
                     total_mined_clone.fetch_add(1, Ordering::SeqCst);
 
-                    // 如果启用了监控，更新任务状态为成功
-                    if MONITOR_ENABLED.load(Ordering::SeqCst) {
-                        MONITOR_DATA.complete_task(task_id, true);
-
-                        // 更新余额
-                        if let Ok(balance) = contract_clone
-                            .client()
-                            .get_balance(contract_clone.client().address(), None)
-                            .await
-                        {
-                            MONITOR_DATA.update_balance(
-                                ethers::utils::format_ether(balance)
-                                    .parse::<f64>()
-                                    .unwrap_or(0.0),
-                            );
-                        }
+                    // 更新任务状态为成功
+                    MONITOR_DATA.complete_task(task_id, true);
+
+                    // 更新余额
+                    if let Ok(balance) = contract_clone
+                        .client()
+                        .get_balance(contract_clone.client().address(), None)
+                        .await
+                    {
+                        MONITOR_DATA.update_balance(
+                            ethers::utils::format_ether(balance)
+                                .parse::<f64>()
+                                .unwrap_or(0.0),
+                        );
                     }
 
                     let completed = completed_tasks_clone.load(Ordering::SeqCst);
                     if completed % 5 == 0 {
-                        println!(
+                        console_println!(
                             "{}",
                             format!(
                                 "已成功完成 {} 个挖矿任务 / Successfully completed {} mining tasks",
@@ -463,25 +1233,36 @@ async fn start_mining_loop<M: Middleware + 'static>(
 
         sleep(Duration::from_millis(500)).await;
 
-        // 每隔一段时间检查一下余额
+        // 每隔一段时间或按需检查一下余额
         let completed = completed_tasks.load(Ordering::SeqCst);
-        if completed > 0 && completed % 10 == 0 {
-            match check_wallet_balance(&contract.client()).await {
-                Ok(_) => {}
-                Err(e) => {
-                    eprintln!(
-                        "{}",
-                        format!("检查余额错误 / Balance check error: {}", e).yellow()
+        let refresh = ctx.control.take_refresh();
+        if refresh || (completed > 0 && completed % 10 == 0) {
+            match fetch_chain_snapshot(&contract, &ctx.pool).await {
+                Ok(snapshot) => {
+                    if let Err(e) = report_wallet_balance(snapshot.wallet_balance) {
+                        console_eprintln!(
+                            warn: "{}",
+                            format!("检查余额错误 / Balance check error: {}", e).yellow()
+                        );
+                    }
+                    if let Err(e) = report_contract_balance(snapshot.contract_balance) {
+                        console_eprintln!(
+                            warn: "{}",
+                            format!("检查合约余额错误 / Contract balance check error: {}", e)
+                                .yellow()
+                        );
+                    }
+                    report_chain_snapshot(&snapshot);
+                    MONITOR_DATA.update_balance(
+                        ethers::utils::format_ether(snapshot.wallet_balance)
+                            .parse::<f64>()
+                            .unwrap_or(0.0),
                     );
                 }
-            }
-
-            match check_contract_balance(&contract).await {
-                Ok(_) => {}
                 Err(e) => {
-                    eprintln!(
-                        "{}",
-                        format!("检查合约余额错误 / Contract balance check error: {}", e).yellow()
+                    console_eprintln!(
+                        warn: "{}",
+                        format!("检查余额错误 / Balance check error: {}", e).yellow()
                     );
                 }
             }
@@ -489,8 +1270,8 @@ async fn start_mining_loop<M: Middleware + 'static>(
     }
 
     // 等待所有活跃任务完成
-    println!(
-        "{}",
+    console_println!(
+        warn: "{}",
         "等待活跃任务完成 / Waiting for active tasks to complete...".yellow()
     );
     while active_tasks.load(Ordering::SeqCst) > 0 {
@@ -498,7 +1279,7 @@ async fn start_mining_loop<M: Middleware + 'static>(
     }
 
     let completed = completed_tasks.load(Ordering::SeqCst);
-    println!(
+    console_println!(
         "{}",
         format!(
             "挖矿已停止。总共完成 {} 个任务。/ Mining stopped. Completed {} tasks in total.",
@@ -511,18 +1292,25 @@ async fn start_mining_loop<M: Middleware + 'static>(
 }
 
 async fn mine_once<M: Middleware + 'static>(
-    contract: &MiningContract<SignerMiddleware<M, LocalWallet>>,
+    contract: &MiningContract<SignerMiddleware<M, MiningSigner>>,
     task_id: usize,
+    ctx: &MiningContext,
 ) -> Result<()> {
     let mut retry_count = 0;
+    // 本任务所有已上链交易的手续费总和
+    let mut task_fee = U256::zero();
 
     // 请求挖矿任务
     loop {
+        if ctx.control.is_cancelled(task_id) {
+            return Err(task_cancelled(task_id));
+        }
+
         // 获取下一个nonce值并创建交易的自定义发送逻辑
         let next_nonce = match get_next_nonce(&contract.client()).await {
             Ok(n) => n,
             Err(e) => {
-                let result = handle_mining_error(
+                handle_mining_error(
                     anyhow!(
                         "任务 #{}: 获取nonce失败 / Task #{}: Failed to get nonce: {}",
                         task_id,
@@ -531,10 +1319,7 @@ async fn mine_once<M: Middleware + 'static>(
                     ),
                     &mut retry_count,
                 )
-                .await;
-                if result.is_err() {
-                    return result;
-                }
+                .await?;
                 continue;
             }
         };
@@ -545,10 +1330,10 @@ async fn mine_once<M: Middleware + 'static>(
         tx_request.set_nonce(next_nonce);
 
         // 手动发送带有nonce的交易
-        match contract.client().send_transaction(tx_request, None).await {
-            Ok(tx) => {
-                let tx_hash = tx.tx_hash();
-                println!(
+        match send_mining_tx(contract, ctx, tx_request.clone()).await {
+            Ok(sent) => {
+                let tx_hash = sent.tx_hash();
+                console_println!(
                     "{}",
                     format!(
                         "任务 #{}: 已发送请求挖矿任务交易 / Task #{}: Sent request mining task tx: {} (nonce: {})",
@@ -556,10 +1341,16 @@ async fn mine_once<M: Middleware + 'static>(
                     )
                     .cyan()
                 );
+                MONITOR_DATA.add_task_tx(task_id, format!("{:?}", tx_hash));
+                MONITOR_DATA.task_event(
+                    task_id,
+                    format!("已发送请求任务交易 (nonce {})", next_nonce),
+                );
 
-                match tx.await {
-                    Ok(_) => {
-                        println!(
+                match wait_for_mining_tx(contract, ctx, &sent).await {
+                    Ok(Some(receipt)) if receipt.status == Some(U64::one()) => {
+                        task_fee += receipt_fee(&receipt);
+                        console_println!(
                             "{}",
                             format!(
                                 "任务 #{}: 请求挖矿任务交易已确认 / Task #{}: Request mining task tx confirmed",
@@ -567,21 +1358,50 @@ async fn mine_once<M: Middleware + 'static>(
                             )
                             .green()
                         );
+                        MONITOR_DATA.task_event(task_id, "请求任务交易已确认".to_string());
                         break;
                     }
+                    Ok(Some(receipt)) => {
+                        task_fee += receipt_fee(&receipt);
+                        let info = diagnose_failed_tx(
+                            &*contract.client(),
+                            &tx_request,
+                            receipt.block_number,
+                        )
+                        .await;
+                        handle_revert(
+                            format!("任务 #{}: 请求挖矿任务交易被回滚 / Task #{}: Request mining task tx reverted", task_id, task_id),
+                            info,
+                            task_id,
+                            &mut retry_count,
+                        )
+                        .await?;
+                        continue;
+                    }
+                    Ok(None) => {
+                        handle_mining_error(anyhow!("任务 #{}: 请求挖矿任务交易没有收据 / Task #{}: Request mining task tx has no receipt", task_id, task_id), &mut retry_count).await?;
+                        continue;
+                    }
                     Err(e) => {
-                        let result = handle_mining_error(anyhow!("任务 #{}: 请求挖矿任务交易失败 / Task #{}: Request mining task tx failed: {}", task_id, task_id, e), &mut retry_count).await;
-                        if result.is_err() {
-                            return result;
-                        }
+                        handle_mining_error(anyhow!("任务 #{}: 请求挖矿任务交易失败 / Task #{}: Request mining task tx failed: {}", task_id, task_id, e), &mut retry_count).await?;
                         continue;
                     }
                 }
             }
             Err(e) => {
-                let result = handle_mining_error(anyhow!("任务 #{}: 发送请求挖矿任务交易失败 / Task #{}: Failed to send request mining task tx: {}", task_id, task_id, e), &mut retry_count).await;
-                if result.is_err() {
-                    return result;
+                if e.unsent {
+                    release_nonce(next_nonce);
+                }
+                let context = format!("任务 #{}: 发送请求挖矿任务交易失败 / Task #{}: Failed to send request mining task tx", task_id, task_id);
+                if let Some(violation) = e.denied {
+                    return Err(policy_denied(context, violation));
+                }
+                match e.revert {
+                    Some(info) => handle_revert(context, info, task_id, &mut retry_count).await?,
+                    None => {
+                        handle_mining_error(anyhow!("{}: {}", context, e.error), &mut retry_count)
+                            .await?
+                    }
                 }
                 continue;
             }
@@ -589,7 +1409,7 @@ async fn mine_once<M: Middleware + 'static>(
     }
 
     // 获取挖矿任务
-    let (nonce, difficulty, active) = match contract.get_my_task().call().await {
+    let (mut nonce, mut difficulty, active) = match contract.get_my_task().call().await {
         Ok(task) => task,
         Err(e) => {
             return Err(anyhow!(
@@ -609,7 +1429,7 @@ async fn mine_once<M: Middleware + 'static>(
         ));
     }
 
-    println!(
+    console_println!(
         "{}",
         format!(
             "任务 #{}: 获取到新挖矿任务 - Nonce: {}, 难度: {} / Task #{}: Got new mining task - Nonce: {}, Difficulty: {}",
@@ -617,35 +1437,50 @@ async fn mine_once<M: Middleware + 'static>(
         )
         .green()
     );
+    MONITOR_DATA.set_task_difficulty(task_id, difficulty.to_string());
+    MONITOR_DATA.task_event(task_id, format!("获取到任务 nonce {}", nonce));
 
     // 解决挖矿任务
     let wallet_address = contract.client().address();
 
-    // 设置进度条
-    let pb = ProgressBar::new(100);
-    pb.set_style(
-        ProgressStyle::default_bar()
-            .template("任务 #{msg} 挖矿中: [{elapsed_precise}] [{bar:40.cyan/blue}] {percent}%")
-            .unwrap()
-            .progress_chars("#>-"),
-    );
-    pb.set_message(format!("{}", task_id));
-
     // 超时检查
-    let start_time = Instant::now();
+    let mut start_time = Instant::now();
     let timeout = Duration::from_secs(MINING_TIMEOUT_SECS);
 
-    // 求解
-    let solution = match tokio::time::timeout(
-        timeout,
-        mine_solution(nonce, wallet_address, difficulty, task_id),
-    )
-    .await
-    {
-        Ok(result) => match result {
-            Ok(solution) => {
-                pb.finish_and_clear();
-                println!(
+    // 求解，链上任务变化时在新的nonce/难度上重新开始
+    let solution = loop {
+        let cancel = Arc::new(AtomicBool::new(false));
+        ctx.control.watch_task(task_id, cancel.clone());
+        let changed_task = Arc::new(std::sync::Mutex::new(None));
+        let watcher = spawn_task_watcher(
+            contract.clone(),
+            ctx.events.clone(),
+            nonce,
+            difficulty,
+            cancel.clone(),
+            changed_task.clone(),
+        );
+
+        let result = tokio::time::timeout(
+            timeout,
+            mine_solution(
+                nonce,
+                wallet_address,
+                difficulty,
+                task_id,
+                cancel.clone(),
+                ctx.control.clone(),
+            ),
+        )
+        .await;
+
+        // 停止监视器以及仍在运行的哈希批次
+        cancel.store(true, Ordering::SeqCst);
+        watcher.abort();
+
+        match result {
+            Ok(Ok(Some(solution))) => {
+                console_println!(
                     "{}",
                     format!(
                         "任务 #{}: 找到解决方案: {} (耗时: {:?}) / Task #{}: Found solution: {} (Time: {:?})",
@@ -658,10 +1493,51 @@ async fn mine_once<M: Middleware + 'static>(
                     )
                     .green()
                 );
-                solution
+                MONITOR_DATA.start_submitting(task_id);
+                MONITOR_DATA.task_event(
+                    task_id,
+                    format!(
+                        "找到解 {} (耗时 {:.1}s)",
+                        solution,
+                        start_time.elapsed().as_secs_f64()
+                    ),
+                );
+                break solution;
             }
-            Err(e) => {
-                pb.finish_and_clear();
+            Ok(Ok(None)) if ctx.control.is_cancelled(task_id) => {
+                return Err(task_cancelled(task_id));
+            }
+            Ok(Ok(None)) => {
+                let changed = changed_task.lock().unwrap().take();
+                match changed {
+                    Some((new_nonce, new_difficulty, true)) => {
+                        console_println!(
+                            warn: "{}",
+                            format!(
+                                "任务 #{}: 链上任务已变化，重新开始 - Nonce: {}, 难度: {} / Task #{}: On-chain task changed, restarting - Nonce: {}, Difficulty: {}",
+                                task_id, new_nonce, new_difficulty, task_id, new_nonce, new_difficulty
+                            )
+                            .yellow()
+                        );
+                        nonce = new_nonce;
+                        difficulty = new_difficulty;
+                        start_time = Instant::now();
+                        MONITOR_DATA.set_task_difficulty(task_id, difficulty.to_string());
+                        MONITOR_DATA.task_event(
+                            task_id,
+                            format!("链上任务已变化，重新开始 nonce {}", nonce),
+                        );
+                    }
+                    _ => {
+                        return Err(anyhow!(
+                            "任务 #{}: 链上任务已失效，停止计算 / Task #{}: On-chain task no longer active, hashing aborted",
+                            task_id,
+                            task_id
+                        ));
+                    }
+                }
+            }
+            Ok(Err(e)) => {
                 return Err(anyhow!(
                     "任务 #{}: 解决挖矿任务失败 / Task #{}: Failed to solve mining task: {}",
                     task_id,
@@ -669,26 +1545,81 @@ async fn mine_once<M: Middleware + 'static>(
                     e
                 ));
             }
-        },
-        Err(_) => {
-            pb.finish_and_clear();
-            return Err(anyhow!(
-                "任务 #{}: 挖矿超时 / Task #{}: Mining timed out after {} seconds",
-                task_id,
-                task_id,
-                MINING_TIMEOUT_SECS
-            ));
+            Err(_) => {
+                return Err(anyhow!(
+                    "任务 #{}: 挖矿超时 / Task #{}: Mining timed out after {} seconds",
+                    task_id,
+                    task_id,
+                    MINING_TIMEOUT_SECS
+                ));
+            }
         }
     };
 
     // 提交结果
     retry_count = 0;
     loop {
+        if ctx.control.is_cancelled(task_id) {
+            return Err(task_cancelled(task_id));
+        }
+
+        // 发送前确认链上任务未变化，避免为过期的解浪费一笔交易
+        match contract.get_my_task().call().await {
+            Ok((current_nonce, current_difficulty, current_active)) => {
+                if !current_active || current_nonce != nonce || current_difficulty != difficulty {
+                    return Err(anyhow!(
+                        "任务 #{}: 链上任务已变化，放弃提交 / Task #{}: On-chain task changed, skipping submission (nonce: {}, difficulty: {}, active: {})",
+                        task_id,
+                        task_id,
+                        current_nonce,
+                        current_difficulty,
+                        current_active
+                    ));
+                }
+            }
+            Err(e) => {
+                handle_mining_error(
+                    anyhow!(
+                        "任务 #{}: 获取挖矿任务失败 / Task #{}: Failed to get mining task: {}",
+                        task_id,
+                        task_id,
+                        e
+                    ),
+                    &mut retry_count,
+                )
+                .await?;
+                continue;
+            }
+        }
+
+        // 使用 eth_call 模拟提交，确认解会被合约接受
+        if let Err(e) = contract.submit_mining_result(solution).call().await {
+            let context = format!(
+                "任务 #{}: 模拟提交挖矿结果失败 / Task #{}: Submit mining result simulation failed",
+                task_id, task_id
+            );
+            match e.as_revert() {
+                Some(data) => {
+                    handle_revert(context, decode_revert_data(data), task_id, &mut retry_count)
+                        .await?
+                }
+                None => {
+                    handle_mining_error(anyhow!("{}: {}", context, e), &mut retry_count).await?
+                }
+            }
+            continue;
+        }
+
+        // 模拟期间可能已停止挖矿，发送前再确认一次
+        if ctx.control.is_cancelled(task_id) {
+            return Err(task_cancelled(task_id));
+        }
+
         // 获取下一个nonce值
         let next_nonce = match get_next_nonce(&contract.client()).await {
             Ok(n) => n,
             Err(e) => {
-                let result = handle_mining_error(
+                handle_mining_error(
                     anyhow!(
                         "任务 #{}: 获取nonce失败 / Task #{}: Failed to get nonce: {}",
                         task_id,
@@ -697,10 +1628,7 @@ async fn mine_once<M: Middleware + 'static>(
                     ),
                     &mut retry_count,
                 )
-                .await;
-                if result.is_err() {
-                    return result;
-                }
+                .await?;
                 continue;
             }
         };
@@ -711,10 +1639,10 @@ async fn mine_once<M: Middleware + 'static>(
         tx_request.set_nonce(next_nonce);
 
         // 手动发送带有nonce的交易
-        match contract.client().send_transaction(tx_request, None).await {
-            Ok(tx) => {
-                let tx_hash = tx.tx_hash();
-                println!(
+        match send_mining_tx(contract, ctx, tx_request.clone()).await {
+            Ok(sent) => {
+                let tx_hash = sent.tx_hash();
+                console_println!(
                     "{}",
                     format!(
                         "任务 #{}: 已发送提交挖矿结果交易 / Task #{}: Sent submit mining result tx: {} (nonce: {})",
@@ -722,12 +1650,18 @@ async fn mine_once<M: Middleware + 'static>(
                     )
                     .cyan()
                 );
+                MONITOR_DATA.add_task_tx(task_id, format!("{:?}", tx_hash));
+                MONITOR_DATA.task_event(
+                    task_id,
+                    format!("已发送提交结果交易 (nonce {})", next_nonce),
+                );
 
-                match tx.await {
+                match wait_for_mining_tx(contract, ctx, &sent).await {
                     Ok(receipt) => {
                         if let Some(receipt) = receipt {
+                            task_fee += receipt_fee(&receipt);
                             if receipt.status == Some(U64::one()) {
-                                println!(
+                                console_println!(
                                     "{}",
                                     format!(
                                         "任务 #{}: 提交挖矿结果交易已确认，获得奖励！/ Task #{}: Submit mining result tx confirmed, reward received!",
@@ -735,50 +1669,58 @@ async fn mine_once<M: Middleware + 'static>(
                                     )
                                     .green()
                                 );
+                                MONITOR_DATA.task_event(task_id, "提交结果交易已确认".to_string());
 
-                                // 解析事件以获取奖励数量
-                                if let Some(logs) = receipt.logs.iter().find(|log| {
-                                    log.topics.len() > 1
-                                        && log.topics[0]
-                                            == keccak256("MiningReward(address,uint256)").into()
-                                }) {
-                                    if logs.data.0.len() >= 32 {
-                                        let reward = U256::from_big_endian(&logs.data.0[0..32]);
-                                        println!(
-                                            "{}",
-                                            format!(
-                                                "任务 #{}: 挖矿奖励: {} MAG / Task #{}: Mining reward: {} MAG",
-                                                task_id,
-                                                ethers::utils::format_ether(reward),
-                                                task_id,
-                                                ethers::utils::format_ether(reward)
-                                            )
-                                            .green()
-                                        );
-                                    }
-                                }
+                                record_mining_reward(
+                                    contract,
+                                    &ctx.ledger,
+                                    &receipt,
+                                    task_fee,
+                                    task_id,
+                                )
+                                .await;
 
                                 return Ok(());
                             } else {
-                                return Err(anyhow!("任务 #{}: 提交挖矿结果交易失败 / Task #{}: Submit mining result tx failed with status: {:?}", task_id, task_id, receipt.status));
+                                let info = diagnose_failed_tx(
+                                    &*contract.client(),
+                                    &tx_request,
+                                    receipt.block_number,
+                                )
+                                .await;
+                                handle_revert(
+                                    format!("任务 #{}: 提交挖矿结果交易被回滚 / Task #{}: Submit mining result tx reverted", task_id, task_id),
+                                    info,
+                                    task_id,
+                                    &mut retry_count,
+                                )
+                                .await?;
+                                continue;
                             }
                         } else {
                             return Err(anyhow!("任务 #{}: 提交挖矿结果交易没有收据 / Task #{}: Submit mining result tx has no receipt", task_id, task_id));
                         }
                     }
                     Err(e) => {
-                        let result = handle_mining_error(anyhow!("任务 #{}: 提交挖矿结果交易失败 / Task #{}: Submit mining result tx failed: {}", task_id, task_id, e), &mut retry_count).await;
-                        if result.is_err() {
-                            return result;
-                        }
+                        handle_mining_error(anyhow!("任务 #{}: 提交挖矿结果交易失败 / Task #{}: Submit mining result tx failed: {}", task_id, task_id, e), &mut retry_count).await?;
                         continue;
                     }
                 }
             }
             Err(e) => {
-                let result = handle_mining_error(anyhow!("任务 #{}: 发送提交挖矿结果交易失败 / Task #{}: Failed to send submit mining result tx: {}", task_id, task_id, e), &mut retry_count).await;
-                if result.is_err() {
-                    return result;
+                if e.unsent {
+                    release_nonce(next_nonce);
+                }
+                let context = format!("任务 #{}: 发送提交挖矿结果交易失败 / Task #{}: Failed to send submit mining result tx", task_id, task_id);
+                if let Some(violation) = e.denied {
+                    return Err(policy_denied(context, violation));
+                }
+                match e.revert {
+                    Some(info) => handle_revert(context, info, task_id, &mut retry_count).await?,
+                    None => {
+                        handle_mining_error(anyhow!("{}: {}", context, e.error), &mut retry_count)
+                            .await?
+                    }
                 }
                 continue;
             }
@@ -786,12 +1728,125 @@ async fn mine_once<M: Middleware + 'static>(
     }
 }
 
+// 解析 MiningReward 事件，打印奖励并写入账本
+async fn record_mining_reward<M: Middleware + 'static>(
+    contract: &MiningContract<SignerMiddleware<M, MiningSigner>>,
+    ledger: &RewardLedger,
+    receipt: &TransactionReceipt,
+    task_fee: U256,
+    task_id: usize,
+) {
+    let wallet_address = contract.client().address();
+    let Some((log_index, event)) = receipt
+        .logs
+        .iter()
+        .filter(|log| log.address == contract.address())
+        .find_map(|log| {
+            let event = parse_log::<MiningRewardFilter>(log.clone()).ok()?;
+            (event.user == wallet_address).then_some((log.log_index, event))
+        })
+    else {
+        return;
+    };
+
+    console_println!(
+        "{}",
+        format!(
+            "任务 #{}: 挖矿奖励: {} MAG / Task #{}: Mining reward: {} MAG",
+            task_id,
+            ethers::utils::format_ether(event.reward),
+            task_id,
+            ethers::utils::format_ether(event.reward)
+        )
+        .green()
+    );
+    MONITOR_DATA.add_reward(
+        task_id,
+        ethers::utils::format_ether(event.reward)
+            .parse::<f64>()
+            .unwrap_or(0.0),
+    );
+
+    // 与 scan 回填一致使用区块时间；取不到区块时先用本地时间，之后 scan 会修正
+    let block_number = receipt.block_number.unwrap_or_default();
+    let timestamp = match contract.client().get_block(block_number).await {
+        Ok(Some(block)) => block.timestamp.as_u64() as i64,
+        _ => chrono::Local::now().timestamp(),
+    };
+    let record = RewardRecord {
+        tx_hash: receipt.transaction_hash,
+        log_index: log_index.unwrap_or_default().as_u64(),
+        wallet: wallet_address,
+        block_number: block_number.as_u64(),
+        timestamp,
+        reward: event.reward,
+        gas_used: receipt.gas_used,
+        effective_gas_price: receipt.effective_gas_price,
+        fee: Some(task_fee),
+    };
+    if let Err(e) = ledger.record(&record) {
+        console_eprintln!(
+            warn: "{}",
+            format!(
+                "任务 #{}: 写入奖励账本失败 / Task #{}: Failed to write reward ledger: {}",
+                task_id, task_id, e
+            )
+            .yellow()
+        );
+    }
+}
+
+// 收到 NewMiningTask 事件或定期轮询时检查链上任务，一旦nonce/难度变化或任务失效，立即取消本地哈希计算
+fn spawn_task_watcher<M: Middleware + 'static>(
+    contract: MiningContract<SignerMiddleware<M, MiningSigner>>,
+    events: Arc<ChainEvents>,
+    nonce: U256,
+    difficulty: U256,
+    cancel: Arc<AtomicBool>,
+    changed_task: Arc<std::sync::Mutex<Option<(U256, U256, bool)>>>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut new_tasks = events.subscribe_tasks();
+        loop {
+            // 订阅正常时主要依赖事件推送，轮询只作兜底
+            let interval = if events.is_subscribed() {
+                TASK_WATCH_SUBSCRIBED_SECS
+            } else {
+                TASK_WATCH_INTERVAL_SECS
+            };
+            tokio::select! {
+                _ = sleep(Duration::from_secs(interval)) => {}
+                _ = new_tasks.recv() => {}
+            }
+            if cancel.load(Ordering::SeqCst) {
+                break;
+            }
+
+            // 查询失败时保持计算，下一轮再检查
+            let Ok((current_nonce, current_difficulty, active)) =
+                contract.get_my_task().call().await
+            else {
+                continue;
+            };
+
+            if !active || current_nonce != nonce || current_difficulty != difficulty {
+                *changed_task.lock().unwrap() = Some((current_nonce, current_difficulty, active));
+                cancel.store(true, Ordering::SeqCst);
+                break;
+            }
+        }
+    })
+}
+
+// 返回 Ok(None) 表示计算被取消（链上任务已变化、用户取消或停止挖矿）
 async fn mine_solution(
     nonce: U256,
     address: Address,
     difficulty: U256,
     task_id: usize,
-) -> Result<U256> {
+    cancel: Arc<AtomicBool>,
+    control: Arc<MinerControl>,
+) -> Result<Option<U256>> {
     let difficulty_bytes = difficulty.to_string();
     let difficulty_len = difficulty_bytes.len();
     let difficulty_biguint =
@@ -805,7 +1860,7 @@ async fn mine_solution(
 
     // 显示估计的哈希次数
     let estimated_hashes = 2u128.pow(difficulty_len as u32 * 4) as f64;
-    println!(
+    console_println!(
         "{}",
         format!(
             "任务 #{}: 难度: {} (约 {:.1e} 次哈希) / Task #{}: Difficulty: {} (approx. {:.1e} hashes)",
@@ -815,34 +1870,30 @@ async fn mine_solution(
     );
 
     // 并行计算哈希
-    let num_cpus = num_cpus::get();
     let mut guesses_per_batch = 100_000; // 每个批次的猜测次数
 
     let mut counter = 0u64;
     let mut last_update = Instant::now();
     let start_time = Instant::now();
 
-    // 创建进度条
-    let pb = ProgressBar::new(100);
-    pb.set_style(
-        ProgressStyle::default_bar()
-            .template("任务 #{msg} 挖矿中: [{elapsed_precise}] [{bar:40.cyan/blue}] {percent}%")
-            .unwrap()
-            .progress_chars("#>-"),
-    );
-    pb.set_message(format!("{}", task_id));
-
     let solution_found = Arc::new(AtomicBool::new(false));
     let solution_value = Arc::new(std::sync::Mutex::new(None));
+    let stop = control.stop_flag();
 
     loop {
         if solution_found.load(Ordering::SeqCst) {
             break;
         }
 
-        let mut futures = Vec::with_capacity(num_cpus);
+        if cancel.load(Ordering::SeqCst) || stop.load(Ordering::SeqCst) {
+            return Ok(None);
+        }
+
+        // 线程数可在监控界面中随时调整
+        let threads = control.hash_threads();
+        let mut futures = Vec::with_capacity(threads);
 
-        for _ in 0..num_cpus {
+        for _ in 0..threads {
             let start_value = counter;
             counter += guesses_per_batch as u64;
 
@@ -851,10 +1902,15 @@ async fn mine_solution(
             let difficulty_biguint_clone = difficulty_biguint.clone();
             let solution_found_clone = solution_found.clone();
             let solution_value_clone = solution_value.clone();
+            let cancel_clone = cancel.clone();
+            let stop_clone = stop.clone();
 
             let future = tokio::task::spawn_blocking(move || {
                 for i in 0..guesses_per_batch {
-                    if solution_found_clone.load(Ordering::SeqCst) {
+                    if solution_found_clone.load(Ordering::SeqCst)
+                        || cancel_clone.load(Ordering::SeqCst)
+                        || stop_clone.load(Ordering::SeqCst)
+                    {
                         return None;
                     }
 
@@ -900,13 +1956,10 @@ async fn mine_solution(
         let results = join_all(futures).await;
 
         // 检查是否找到解决方案
-        for result in results {
-            if let Ok(Some(solution)) = result {
-                solution_found.store(true, Ordering::SeqCst);
-                let mut sol_value = solution_value.lock().unwrap();
-                *sol_value = Some(solution);
-                break;
-            }
+        if let Some(solution) = results.into_iter().flatten().flatten().next() {
+            solution_found.store(true, Ordering::SeqCst);
+            let mut sol_value = solution_value.lock().unwrap();
+            *sol_value = Some(solution);
         }
 
         // 如果已经找到解决方案，退出循环
@@ -914,24 +1967,17 @@ async fn mine_solution(
             break;
         }
 
-        // 更新进度条
+        // 更新任务进度与算力
         let elapsed = start_time.elapsed().as_secs_f64();
         if elapsed > 0.0 {
             let hashes_per_second = counter as f64 / elapsed;
             let estimated_total_hashes = estimated_hashes.min(1e18); // 限制最大值以避免数值溢出
             let progress_percent = (counter as f64 / estimated_total_hashes * 100.0).min(99.0);
 
-            pb.set_position(progress_percent as u64);
-            pb.set_message(format!(
-                "{:.2}M 哈希/秒 / {:.2}M hashes/s",
-                hashes_per_second / 1_000_000.0,
-                hashes_per_second / 1_000_000.0
-            ));
-
-            // 如果启用了监控，更新任务进度
-            if MONITOR_ENABLED.load(Ordering::SeqCst) {
-                MONITOR_DATA.update_task_progress(task_id, progress_percent / 100.0);
-            }
+            // 更新任务进度与算力
+            MONITOR_DATA.update_task_progress(task_id, progress_percent / 100.0);
+            MONITOR_DATA.update_task_hashes(task_id, counter, estimated_total_hashes);
+            MONITOR_DATA.update_hashrate(task_id, hashes_per_second);
         }
 
         // 每隔一段时间调整批处理大小
@@ -943,21 +1989,19 @@ async fn mine_solution(
             if elapsed > 5.0 {
                 let hashes_per_second = counter as f64 / elapsed;
                 // 每个CPU核心每秒处理的哈希数
-                let hashes_per_cpu_per_second = hashes_per_second / num_cpus as f64;
+                let hashes_per_cpu_per_second = hashes_per_second / control.hash_threads() as f64;
 
                 // 调整每批次的猜测次数，使每个批次大约运行0.1秒
                 guesses_per_batch = (hashes_per_cpu_per_second * 0.1) as usize;
-                guesses_per_batch = guesses_per_batch.max(10_000).min(1_000_000);
+                guesses_per_batch = guesses_per_batch.clamp(10_000, 1_000_000);
             }
         }
     }
 
-    pb.finish_and_clear();
-
     // 获取找到的解决方案
     let solution = solution_value.lock().unwrap();
     match *solution {
-        Some(value) => Ok(value),
+        Some(value) => Ok(Some(value)),
         None => Err(anyhow!(
             "任务 #{}: 未找到解决方案 / Task #{}: No solution found",
             task_id,
@@ -966,8 +2010,22 @@ async fn mine_solution(
     }
 }
 
+fn task_cancelled(task_id: usize) -> anyhow::Error {
+    anyhow!(
+        "任务 #{}: 已被用户取消 / Task #{}: Cancelled by user",
+        task_id,
+        task_id
+    )
+}
+
+// 签名策略拒绝的交易重试也不会通过，直接结束任务；保留 PolicyViolation 供调用方识别
+fn policy_denied(context: String, violation: PolicyViolation) -> anyhow::Error {
+    let message = format!("{}: {}（不再重试 / not retrying）", context, violation);
+    anyhow::Error::new(violation).context(message)
+}
+
 async fn handle_mining_error(error: anyhow::Error, retry_count: &mut usize) -> Result<()> {
-    eprintln!("{}", format!("挖矿错误 / Mining error: {}", error).red());
+    console_eprintln!(error: "{}", format!("挖矿错误 / Mining error: {}", error).red());
 
     *retry_count += 1;
     if *retry_count >= MAX_RETRIES {
@@ -976,8 +2034,8 @@ async fn handle_mining_error(error: anyhow::Error, retry_count: &mut usize) -> R
         ));
     }
 
-    println!(
-        "{}",
+    console_println!(
+        warn: "{}",
         format!(
             "5秒后重试（第 {}/{} 次） / Retrying in 5 seconds (Attempt {}/{})",
             retry_count, MAX_RETRIES, retry_count, MAX_RETRIES
@@ -989,6 +2047,31 @@ async fn handle_mining_error(error: anyhow::Error, retry_count: &mut usize) -> R
     Ok(())
 }
 
+// 处理被回滚的交易：记录解码后的原因，并根据分类决定是否继续重试
+async fn handle_revert(
+    context: String,
+    info: RevertInfo,
+    task_id: usize,
+    retry_count: &mut usize,
+) -> Result<()> {
+    MONITOR_DATA.set_task_error(task_id, info.reason.clone());
+
+    let error = anyhow!("{}: {}", context, info);
+    if info.should_abort() {
+        console_eprintln!(
+            error: "{}",
+            format!(
+                "任务 #{}: 合约拒绝执行，不再重试 / Task #{}: Rejected by contract, not retrying: {}",
+                task_id, task_id, info
+            )
+            .red()
+        );
+        return Err(error);
+    }
+
+    handle_mining_error(error, retry_count).await
+}
+
 // 替换旧的encode_packed函数，添加与JavaScript一致的实现
 // 特定的solidityPack实现，对应JS版本中的ethers.utils.solidityPack(['uint256', 'address'], [nonce, address])
 fn solidity_pack_uint_address(num: U256, addr: Address) -> Result<Vec<u8>> {
@@ -1059,9 +2142,19 @@ fn encode_packed(tokens: &[Token]) -> Result<Vec<u8>> {
     Ok(result)
 }
 
+// 交易未发出时归还nonce：仍是最后分配的值时回退一位，
+// 之后已分配了更大的nonce时从链上 pending 计数重新同步，由下一笔交易填上空缺
+fn release_nonce(nonce: U256) {
+    let mut nonce_guard = CURRENT_NONCE.lock().unwrap();
+    *nonce_guard = match *nonce_guard {
+        Some(current) if current == nonce => nonce.checked_sub(U256::one()),
+        _ => None,
+    };
+}
+
 // 获取下一个nonce值
 async fn get_next_nonce<M: Middleware + 'static>(
-    client: &SignerMiddleware<M, LocalWallet>,
+    client: &SignerMiddleware<M, MiningSigner>,
 ) -> Result<U256> {
     // 检查是否已初始化
     let current_nonce_opt = {
@@ -1079,8 +2172,11 @@ async fn get_next_nonce<M: Middleware + 'static>(
             next
         }
         None => {
-            // 未初始化，从链上获取
-            let chain_nonce = client.get_transaction_count(client.address(), None).await?;
+            // 未初始化，从链上获取；使用 pending 计数，其他任务仍在交易池中的交易也计入，
+            // 不会重复使用它们的nonce
+            let chain_nonce = client
+                .get_transaction_count(client.address(), Some(BlockNumber::Pending.into()))
+                .await?;
             let mut nonce_guard = CURRENT_NONCE.lock().unwrap();
             *nonce_guard = Some(chain_nonce);
             chain_nonce
@@ -1091,33 +2187,171 @@ async fn get_next_nonce<M: Middleware + 'static>(
 }
 
 // 初始化全局nonce
-async fn initialize_nonce<M: Middleware + 'static>(
-    client: &SignerMiddleware<M, LocalWallet>,
-) -> Result<()> {
-    // 检查是否需要初始化
-    let need_init = {
-        let nonce_guard = CURRENT_NONCE.lock().unwrap();
-        nonce_guard.is_none()
-    };
+fn initialize_nonce(chain_nonce: U256) {
+    let mut nonce_guard = CURRENT_NONCE.lock().unwrap();
+    // 已初始化时保持本地nonce
+    if nonce_guard.is_none() {
+        *nonce_guard = Some(chain_nonce);
+        console_println!(
+            "初始化nonce: {} / Initialized nonce: {}",
+            chain_nonce,
+            chain_nonce
+        );
+    }
+}
 
-    // 如果需要初始化，则从链上获取nonce
-    if need_init {
-        // 从链上获取，不持有锁
-        let chain_nonce = client.get_transaction_count(client.address(), None).await?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_rpc::{self, MockRpc, Reply};
+    use serde_json::json;
 
-        // 获取完毕后锁定并保存nonce
-        {
-            let mut nonce_guard = CURRENT_NONCE.lock().unwrap();
-            *nonce_guard = Some(chain_nonce);
-        }
+    lazy_static::lazy_static! {
+        // 修改全局 CURRENT_NONCE 的测试依次运行
+        static ref NONCE_TEST: tokio::sync::Mutex<()> = tokio::sync::Mutex::new(());
+    }
 
-        println!(
-            "初始化nonce: {} / Initialized nonce: {}",
-            chain_nonce, chain_nonce
+    // 链上 nonce 为 5、gas 价格为 1 gwei 的节点
+    async fn node() -> MockRpc {
+        MockRpc::start(|method, _| match method {
+            "eth_chainId" => Reply::Result(json!(format!("{:#x}", CHAIN_ID))),
+            "eth_blockNumber" => Reply::Result(json!("0x10")),
+            "eth_getTransactionCount" => Reply::Result(json!("0x5")),
+            "eth_gasPrice" => Reply::Result(json!("0x3b9aca00")),
+            "eth_estimateGas" => Reply::Result(json!("0x5208")),
+            "eth_sendRawTransaction" => Reply::Result(json!(H256::repeat_byte(1))),
+            _ => Reply::Error(-32601, "method not found".to_string()),
+        })
+        .await
+    }
+
+    // 签名策略的每日 gas 上限为 1 wei，任何交易都会被拒绝
+    async fn denying_miner(
+        node: &MockRpc,
+        audit_path: &Path,
+    ) -> (
+        MiningContract<SignerMiddleware<Provider<RpcPool>, MiningSigner>>,
+        MiningContext,
+    ) {
+        let pool = mock_rpc::pool(&[node]).await;
+        let contract_address: Address = CONTRACT_ADDRESS.parse().unwrap();
+        let policy = TxPolicy::new(contract_address, &[], 0, U256::one()).unwrap();
+        let audit = Arc::new(AuditLog::open(audit_path).unwrap());
+        let wallet = LocalWallet::from_bytes(&[1; 32])
+            .unwrap()
+            .with_chain_id(CHAIN_ID);
+        let signer = AuditSigner::new(
+            PolicySigner::new(MinerSigner::Local(wallet), Arc::new(policy)),
+            audit.clone(),
         );
+        let client = SignerMiddleware::new(Provider::new(pool.clone()), signer);
+        let contract = MiningContract::new(contract_address, Arc::new(client));
+
+        let ctx = MiningContext {
+            ledger: Arc::new(RewardLedger::open(Path::new(":memory:")).unwrap()),
+            audit,
+            events: ChainEvents::spawn(pool.clone(), contract_address, Address::zero()),
+            pool: pool.clone(),
+            control: Arc::new(MinerControl::new(pool, 1, 1)),
+            broadcast: false,
+        };
+        (contract, ctx)
     }
 
-    Ok(())
-}
+    #[tokio::test]
+    async fn denied_transaction_is_not_retried_and_keeps_the_nonce() {
+        let _guard = NONCE_TEST.lock().await;
+        let node = node().await;
+        let audit_path = std::env::temp_dir().join(format!(
+            "pow-client-test-audit-{}.log",
+            rand::random::<u64>()
+        ));
+        let (contract, ctx) = denying_miner(&node, &audit_path).await;
+        *CURRENT_NONCE.lock().unwrap() = None;
+
+        // 重试会等待 5 秒，被拒绝时应立即结束
+        let result = tokio::time::timeout(Duration::from_secs(3), mine_once(&contract, 0, &ctx))
+            .await
+            .expect("denied transaction was retried");
+        let error = result.unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<PolicyViolation>(),
+            Some(PolicyViolation::DailyGas(_))
+        ));
+        assert!(node.calls("eth_sendRawTransaction").is_empty());
+
+        // 下一笔交易仍使用同一个nonce，不留空缺
+        assert_eq!(
+            get_next_nonce(&contract.client()).await.unwrap(),
+            U256::from(5)
+        );
+        // 从链上同步时计入交易池中的交易
+        let counts = node.calls("eth_getTransactionCount");
+        assert!(!counts.is_empty());
+        assert!(counts.iter().all(|params| params[1] == json!("pending")));
+        let _ = std::fs::remove_file(&audit_path);
+    }
+
+    #[tokio::test]
+    async fn stop_ends_hashing_between_batches() {
+        let node = node().await;
+        let pool = mock_rpc::pool(&[&node]).await;
+        let control = Arc::new(MinerControl::new(pool, 2, 1));
+        let stop = control.stop_flag();
+        tokio::spawn(async move {
+            sleep(Duration::from_millis(200)).await;
+            stop.store(true, Ordering::SeqCst);
+        });
+
+        // 难度 1 不可能找到解，只有停止才会结束
+        let result = tokio::time::timeout(
+            Duration::from_secs(5),
+            mine_solution(
+                U256::one(),
+                Address::zero(),
+                U256::one(),
+                0,
+                Arc::new(AtomicBool::new(false)),
+                control.clone(),
+            ),
+        )
+        .await
+        .expect("hashing ignored the stop flag");
+        assert_eq!(result.unwrap(), None);
+        assert!(control.is_cancelled(0));
+    }
+
+    #[tokio::test]
+    async fn stopped_miner_sends_nothing() {
+        let node = node().await;
+        let audit_path = std::env::temp_dir().join(format!(
+            "pow-client-test-audit-{}.log",
+            rand::random::<u64>()
+        ));
+        let (contract, ctx) = denying_miner(&node, &audit_path).await;
+        ctx.control.stop_flag().store(true, Ordering::SeqCst);
+
+        let error = mine_once(&contract, 0, &ctx).await.unwrap_err();
+        assert!(error.to_string().contains("Cancelled"));
+        assert!(node.calls("eth_getTransactionCount").is_empty());
+        assert!(node.calls("eth_sendRawTransaction").is_empty());
+        let _ = std::fs::remove_file(&audit_path);
+    }
 
-// 获取并增加nonce
+    #[test]
+    fn released_nonce_is_reused_or_resynced() {
+        let _guard = NONCE_TEST.blocking_lock();
+        *CURRENT_NONCE.lock().unwrap() = Some(U256::from(7));
+        release_nonce(U256::from(7));
+        assert_eq!(*CURRENT_NONCE.lock().unwrap(), Some(U256::from(6)));
+
+        // 之后已分配了更大的nonce，从链上重新同步
+        *CURRENT_NONCE.lock().unwrap() = Some(U256::from(9));
+        release_nonce(U256::from(7));
+        assert_eq!(*CURRENT_NONCE.lock().unwrap(), None);
+
+        *CURRENT_NONCE.lock().unwrap() = Some(U256::zero());
+        release_nonce(U256::zero());
+        assert_eq!(*CURRENT_NONCE.lock().unwrap(), None);
+    }
+}
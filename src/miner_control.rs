@@ -0,0 +1,354 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{rpc_pool::RpcPool, tui_monitor::MonitorData};
+
+// 可在运行中调整的上限
+pub const MAX_HASH_THREADS: usize = 256;
+pub const MAX_PARALLEL_TASKS: usize = 64;
+
+// 监控界面与控制接口对挖矿的操作；attach 时经监控套接字发送给挖矿进程
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MinerCommand {
+    TogglePause,
+    SetPaused { paused: bool },
+    AdjustHashThreads { delta: isize },
+    SetHashThreads { threads: usize },
+    AdjustParallelTasks { delta: isize },
+    RefreshBalance,
+    CancelTask { task_id: usize },
+    // None 恢复自动选择节点
+    PreferEndpoint { url: Option<String> },
+    SetEndpointDisabled { url: String, disabled: bool },
+}
+
+// 监控界面标题栏显示的控制状态
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct ControlState {
+    pub paused: bool,
+    pub hash_threads: usize,
+    pub parallel_tasks: usize,
+}
+
+// 监控界面操作挖矿的方式：同一进程内直接调用，attach 时经套接字转发
+pub trait Controller: Send + Sync {
+    fn state(&self) -> ControlState;
+
+    // 只读连接不能操作
+    fn can_control(&self) -> bool {
+        true
+    }
+
+    fn send(&self, command: MinerCommand, data: &MonitorData);
+}
+
+// 挖矿运行时控制：由监控界面修改，挖矿循环与哈希计算在每一轮读取
+pub struct MinerControl {
+    pool: RpcPool,
+    // 暂停时不再请求新任务，进行中的任务继续完成
+    paused: AtomicBool,
+    // 每个任务的哈希线程数
+    hash_threads: AtomicUsize,
+    // 同时处理的任务数
+    parallel_tasks: AtomicUsize,
+    // 请求立即刷新余额
+    refresh_requested: AtomicBool,
+    // 各任务当前哈希计算的取消标志
+    running: Mutex<HashMap<usize, Arc<AtomicBool>>>,
+    // 用户取消的任务
+    cancelled: Mutex<HashSet<usize>>,
+    // 停止挖矿：Ctrl+C、退出监控界面或控制接口设置，进行中的任务在下一个检查点放弃
+    stop: Arc<AtomicBool>,
+}
+
+impl MinerControl {
+    pub fn new(pool: RpcPool, hash_threads: usize, parallel_tasks: usize) -> Self {
+        MinerControl {
+            pool,
+            paused: AtomicBool::new(false),
+            hash_threads: AtomicUsize::new(hash_threads.clamp(1, MAX_HASH_THREADS)),
+            parallel_tasks: AtomicUsize::new(parallel_tasks.clamp(1, MAX_PARALLEL_TASKS)),
+            refresh_requested: AtomicBool::new(false),
+            running: Mutex::new(HashMap::new()),
+            cancelled: Mutex::new(HashSet::new()),
+            stop: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    // 停止挖矿的标志，哈希线程在每一轮检查
+    pub fn stop_flag(&self) -> Arc<AtomicBool> {
+        self.stop.clone()
+    }
+
+    pub fn is_stopping(&self) -> bool {
+        self.stop.load(Ordering::SeqCst)
+    }
+
+    // 停止挖矿，返回是否为第一次请求停止
+    pub fn stop(&self) -> bool {
+        !self.stop.swap(true, Ordering::SeqCst)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    // 切换暂停状态，返回切换后是否暂停
+    pub fn toggle_pause(&self) -> bool {
+        !self.paused.fetch_xor(true, Ordering::SeqCst)
+    }
+
+    // 设置暂停状态，返回状态是否改变
+    pub fn set_paused(&self, paused: bool) -> bool {
+        self.paused.swap(paused, Ordering::SeqCst) != paused
+    }
+
+    pub fn hash_threads(&self) -> usize {
+        self.hash_threads.load(Ordering::SeqCst)
+    }
+
+    // 设置哈希线程数，返回限制在允许范围内的实际值
+    pub fn set_hash_threads(&self, threads: usize) -> usize {
+        let threads = threads.clamp(1, MAX_HASH_THREADS);
+        self.hash_threads.store(threads, Ordering::SeqCst);
+        threads
+    }
+
+    // 调整哈希线程数，返回调整后的值
+    pub fn adjust_hash_threads(&self, delta: isize) -> usize {
+        adjust(&self.hash_threads, delta, MAX_HASH_THREADS)
+    }
+
+    pub fn parallel_tasks(&self) -> usize {
+        self.parallel_tasks.load(Ordering::SeqCst)
+    }
+
+    // 调整同时处理的任务数，返回调整后的值；减少时多出的任务完成后不再补充
+    pub fn adjust_parallel_tasks(&self, delta: isize) -> usize {
+        adjust(&self.parallel_tasks, delta, MAX_PARALLEL_TASKS)
+    }
+
+    pub fn request_refresh(&self) {
+        self.refresh_requested.store(true, Ordering::SeqCst);
+    }
+
+    // 取出刷新请求
+    pub fn take_refresh(&self) -> bool {
+        self.refresh_requested.swap(false, Ordering::SeqCst)
+    }
+
+    // 登记任务本轮哈希计算的取消标志；任务已被取消时立即停止
+    pub fn watch_task(&self, task_id: usize, cancel: Arc<AtomicBool>) {
+        if self.is_cancelled(task_id) {
+            cancel.store(true, Ordering::SeqCst);
+        }
+        self.running.lock().unwrap().insert(task_id, cancel);
+    }
+
+    // 取消任务：停止正在进行的哈希计算，之后的步骤也会放弃
+    pub fn cancel_task(&self, task_id: usize) {
+        self.cancelled.lock().unwrap().insert(task_id);
+        if let Some(cancel) = self.running.lock().unwrap().get(&task_id) {
+            cancel.store(true, Ordering::SeqCst);
+        }
+    }
+
+    // 任务被取消或正在停止挖矿
+    pub fn is_cancelled(&self, task_id: usize) -> bool {
+        self.is_stopping() || self.cancelled.lock().unwrap().contains(&task_id)
+    }
+
+    pub fn has_endpoint(&self, url: &str) -> bool {
+        self.pool.status().iter().any(|ep| ep.url == url)
+    }
+
+    // 任务结束后清理登记
+    pub fn finish_task(&self, task_id: usize) {
+        self.running.lock().unwrap().remove(&task_id);
+        self.cancelled.lock().unwrap().remove(&task_id);
+    }
+
+    // 执行监控界面的操作并输出结果，节点操作后立即更新监控数据中的节点状态
+    pub fn apply(&self, command: MinerCommand, data: &MonitorData) {
+        match command {
+            MinerCommand::TogglePause => {
+                if self.toggle_pause() {
+                    console_println!("已暂停请求新任务，进行中的任务将继续完成 / Paused requesting new tasks; in-flight tasks will finish");
+                } else {
+                    console_println!("已继续请求新任务 / Resumed requesting new tasks");
+                }
+            }
+            MinerCommand::SetPaused { paused } => {
+                if !self.set_paused(paused) {
+                    return;
+                }
+                if paused {
+                    console_println!("已暂停请求新任务，进行中的任务将继续完成 / Paused requesting new tasks; in-flight tasks will finish");
+                } else {
+                    console_println!("已继续请求新任务 / Resumed requesting new tasks");
+                }
+            }
+            MinerCommand::AdjustHashThreads { delta } => {
+                let threads = self.adjust_hash_threads(delta);
+                console_println!("哈希线程数 / Hashing threads: {}", threads);
+            }
+            MinerCommand::SetHashThreads { threads } => {
+                let threads = self.set_hash_threads(threads);
+                console_println!("哈希线程数 / Hashing threads: {}", threads);
+            }
+            MinerCommand::AdjustParallelTasks { delta } => {
+                let tasks = self.adjust_parallel_tasks(delta);
+                console_println!("并行任务数 / Parallel tasks: {}", tasks);
+            }
+            MinerCommand::RefreshBalance => {
+                self.request_refresh();
+                console_println!("正在刷新余额 / Refreshing balances");
+            }
+            MinerCommand::CancelTask { task_id } => {
+                let tasks = data.task_progresses.lock().unwrap();
+                if !tasks.iter().any(|t| t.id == task_id && !t.is_finished()) {
+                    return;
+                }
+                drop(tasks);
+                self.cancel_task(task_id);
+                console_println!("正在取消任务 #{} / Cancelling task #{}", task_id, task_id);
+            }
+            MinerCommand::PreferEndpoint { url: None } => {
+                self.pool.prefer(None);
+                console_println!("已恢复自动选择节点 / Endpoint selection is automatic again");
+                data.update_rpc_status(self.pool.status());
+            }
+            MinerCommand::PreferEndpoint { url: Some(url) } => {
+                self.pool.prefer(Some(&url));
+                console_println!("优先使用节点 / Preferring endpoint {}", url);
+                data.update_rpc_status(self.pool.status());
+            }
+            MinerCommand::SetEndpointDisabled { url, disabled } => {
+                if !self.pool.set_disabled(&url, disabled) {
+                    console_eprintln!(
+                        "不能停用最后一个可用节点 / Cannot disable the last enabled endpoint"
+                    );
+                } else if disabled {
+                    console_println!("已停用节点 / Disabled endpoint {}", url);
+                } else {
+                    console_println!("已启用节点 / Enabled endpoint {}", url);
+                }
+                data.update_rpc_status(self.pool.status());
+            }
+        }
+    }
+}
+
+impl Controller for MinerControl {
+    fn state(&self) -> ControlState {
+        ControlState {
+            paused: self.is_paused(),
+            hash_threads: self.hash_threads(),
+            parallel_tasks: self.parallel_tasks(),
+        }
+    }
+
+    fn send(&self, command: MinerCommand, data: &MonitorData) {
+        self.apply(command, data);
+    }
+}
+
+fn adjust(value: &AtomicUsize, delta: isize, max: usize) -> usize {
+    let result = value.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |current| {
+        Some(current.saturating_add_signed(delta).clamp(1, max))
+    });
+    // 闭包总是返回 Some
+    let previous = result.unwrap_or_else(|current| current);
+    previous.saturating_add_signed(delta).clamp(1, max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_rpc::idle_control;
+
+    #[test]
+    fn adjust_clamps_to_range() {
+        let value = AtomicUsize::new(2);
+        assert_eq!(adjust(&value, -5, 8), 1);
+        assert_eq!(value.load(Ordering::SeqCst), 1);
+        assert_eq!(adjust(&value, 3, 8), 4);
+        assert_eq!(adjust(&value, 100, 8), 8);
+        assert_eq!(adjust(&value, isize::MAX, 8), 8);
+        assert_eq!(adjust(&value, isize::MIN, 8), 1);
+        assert_eq!(value.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn thread_and_task_counts_stay_in_range() {
+        let control = idle_control().await;
+        assert_eq!(control.set_hash_threads(0), 1);
+        assert_eq!(control.hash_threads(), 1);
+        assert_eq!(
+            control.set_hash_threads(MAX_HASH_THREADS + 1),
+            MAX_HASH_THREADS
+        );
+        assert_eq!(control.adjust_hash_threads(1), MAX_HASH_THREADS);
+        assert_eq!(control.adjust_hash_threads(-2), MAX_HASH_THREADS - 2);
+        assert_eq!(control.adjust_parallel_tasks(-1), 1);
+        assert_eq!(
+            control.adjust_parallel_tasks(MAX_PARALLEL_TASKS as isize),
+            MAX_PARALLEL_TASKS
+        );
+    }
+
+    #[tokio::test]
+    async fn pause_reports_the_new_state_and_changes() {
+        let control = idle_control().await;
+        assert!(!control.is_paused());
+        assert!(control.toggle_pause());
+        assert!(control.is_paused());
+        assert!(!control.toggle_pause());
+        assert!(!control.is_paused());
+
+        assert!(control.set_paused(true));
+        assert!(!control.set_paused(true));
+        assert!(control.is_paused());
+        assert!(control.set_paused(false));
+        assert!(!control.set_paused(false));
+    }
+
+    #[tokio::test]
+    async fn cancel_reaches_running_and_later_hashing_rounds() {
+        let control = idle_control().await;
+
+        // 正在计算时取消
+        let running = Arc::new(AtomicBool::new(false));
+        control.watch_task(1, running.clone());
+        control.cancel_task(1);
+        assert!(running.load(Ordering::SeqCst));
+        assert!(control.is_cancelled(1));
+
+        // 取消发生在任务登记之前，登记时立即停止
+        control.cancel_task(2);
+        let late = Arc::new(AtomicBool::new(false));
+        control.watch_task(2, late.clone());
+        assert!(late.load(Ordering::SeqCst));
+
+        // 其他任务不受影响，结束后清除取消记录
+        let other = Arc::new(AtomicBool::new(false));
+        control.watch_task(3, other.clone());
+        assert!(!other.load(Ordering::SeqCst));
+        assert!(!control.is_cancelled(3));
+        control.finish_task(2);
+        assert!(!control.is_cancelled(2));
+
+        // 停止挖矿时所有任务都视为已取消
+        assert!(control.stop());
+        assert!(!control.stop());
+        assert!(control.is_cancelled(3));
+    }
+}
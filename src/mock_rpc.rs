@@ -0,0 +1,139 @@
+// 测试用的本地 JSON-RPC HTTP 节点，按方法名和参数返回预设的结果
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+use crate::{
+    http_util::read_head, miner_control::MinerControl, rpc_config::EndpointConfig,
+    rpc_pool::RpcPool,
+};
+
+pub enum Reply {
+    Result(Value),
+    Error(i64, String),
+    // HTTP 429，可带 Retry-After 秒数
+    RateLimited(Option<u64>),
+}
+
+type Handler = dyn Fn(&str, &Value) -> Reply + Send + Sync;
+
+pub struct MockRpc {
+    pub url: String,
+    calls: Arc<Mutex<Vec<(String, Value)>>>,
+}
+
+impl MockRpc {
+    pub async fn start(handler: impl Fn(&str, &Value) -> Reply + Send + Sync + 'static) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let handler: Arc<Handler> = Arc::new(handler);
+
+        let recorded = calls.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let handler = handler.clone();
+                let recorded = recorded.clone();
+                tokio::spawn(serve(stream, handler, recorded));
+            }
+        });
+
+        MockRpc { url, calls }
+    }
+
+    // 收到的某个方法的全部参数
+    pub fn calls(&self, method: &str) -> Vec<Value> {
+        self.calls
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(m, _)| m == method)
+            .map(|(_, params)| params.clone())
+            .collect()
+    }
+}
+
+// 依次连接这些节点的 RPC 池
+pub async fn pool(nodes: &[&MockRpc]) -> RpcPool {
+    let configs: Vec<EndpointConfig> = nodes
+        .iter()
+        .map(|node| EndpointConfig::from_url(&node.url))
+        .collect();
+    RpcPool::connect(&configs).await.unwrap()
+}
+
+// 节点对所有请求都返回 null 的挖矿控制，用于不读取链上数据的测试
+pub async fn idle_control() -> Arc<MinerControl> {
+    let node = MockRpc::start(|_, _| Reply::Result(Value::Null)).await;
+    Arc::new(MinerControl::new(pool(&[&node]).await, 1, 1))
+}
+
+// 一个连接上可能有多个请求（keep-alive）
+async fn serve(
+    mut stream: TcpStream,
+    handler: Arc<Handler>,
+    calls: Arc<Mutex<Vec<(String, Value)>>>,
+) {
+    while let Ok(head) = read_head(&mut stream).await {
+        let length = head
+            .lines()
+            .filter_map(|line| line.split_once(':'))
+            .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+            .and_then(|(_, value)| value.trim().parse().ok())
+            .unwrap_or(0);
+        let mut body = vec![0; length];
+        if stream.read_exact(&mut body).await.is_err() {
+            return;
+        }
+        let request: Value = serde_json::from_slice(&body).unwrap();
+
+        let requests = match &request {
+            Value::Array(requests) => requests.clone(),
+            single => vec![single.clone()],
+        };
+        let mut responses = Vec::new();
+        let mut rate_limited = None;
+        for request in requests {
+            let method = request["method"].as_str().unwrap_or_default().to_string();
+            let params = request["params"].clone();
+            calls.lock().unwrap().push((method.clone(), params.clone()));
+            match handler(&method, &params) {
+                Reply::Result(result) => responses
+                    .push(json!({ "jsonrpc": "2.0", "id": request["id"], "result": result })),
+                Reply::Error(code, message) => responses.push(json!({
+                    "jsonrpc": "2.0",
+                    "id": request["id"],
+                    "error": { "code": code, "message": message }
+                })),
+                Reply::RateLimited(retry_after) => rate_limited = Some(retry_after),
+            }
+        }
+
+        let response = match rate_limited {
+            Some(retry_after) => format!(
+                "HTTP/1.1 429 Too Many Requests\r\n{}Content-Length: 0\r\n\r\n",
+                retry_after
+                    .map(|secs| format!("Retry-After: {}\r\n", secs))
+                    .unwrap_or_default()
+            ),
+            None => {
+                let body = match request {
+                    Value::Array(_) => Value::Array(responses),
+                    _ => responses.remove(0),
+                }
+                .to_string();
+                format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                    body.len(),
+                    body
+                )
+            }
+        };
+        if stream.write_all(response.as_bytes()).await.is_err() {
+            return;
+        }
+    }
+}
@@ -0,0 +1,439 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use crate::{
+    http_util::accept_with_backoff,
+    miner_control::{ControlState, MinerCommand, MinerControl},
+    status_http::MinerStatus,
+    tui_monitor::{LogLine, MonitorData, MonitorSnapshot},
+};
+
+// attach 未指定 --monitor-socket 时连接的路径
+pub const DEFAULT_MONITOR_SOCKET: &str = "pow-client.sock";
+
+// 向界面推送监控数据的间隔，与界面刷新频率一致
+const SNAPSHOT_INTERVAL: Duration = Duration::from_millis(500);
+
+// 等待对方第一条消息的时间
+const HELLO_TIMEOUT: Duration = Duration::from_secs(5);
+
+// 监控套接字上的消息，每行一个 JSON
+
+// 界面发给挖矿进程
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientMessage {
+    // 连接后的第一条消息，control 为 false 时只能查看
+    Hello { control: bool },
+    Command { command: MinerCommand },
+}
+
+// 挖矿进程发给界面
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ServerMessage {
+    // 挖矿进程号用于在界面中显示其内存占用
+    Welcome {
+        pid: u32,
+        control: bool,
+    },
+    Snapshot {
+        state: ControlState,
+        data: Box<MonitorSnapshot>,
+    },
+    // 上次推送后新增的日志
+    Logs {
+        lines: Vec<LogLine>,
+    },
+}
+
+// 挖矿进程一侧：在 Unix 套接字上提供监控数据与事件流，可同时连接多个界面，
+// 界面连接或断开都不影响挖矿；套接字文件只允许当前用户访问，进程结束时删除
+pub struct MonitorServer {
+    path: PathBuf,
+}
+
+#[cfg(unix)]
+impl MonitorServer {
+    pub fn bind(path: &Path, data: Arc<MonitorData>, control: Arc<MinerControl>) -> Result<Self> {
+        // 上次异常退出留下的套接字文件无人监听时删除
+        if path.exists() {
+            if std::os::unix::net::UnixStream::connect(path).is_ok() {
+                return Err(anyhow!(
+                    "监控套接字 {} 正被另一个挖矿进程使用 / Monitor socket {} is in use by another miner",
+                    path.display(),
+                    path.display()
+                ));
+            }
+            std::fs::remove_file(path)?;
+        }
+
+        let listener = bind_private(path).map_err(|e| {
+            anyhow!(
+                "无法创建监控套接字 / Cannot create monitor socket {}: {}",
+                path.display(),
+                e
+            )
+        })?;
+
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = accept_with_backoff(
+                    "监控套接字接受连接失败 / Monitor socket accept failed",
+                    || listener.accept(),
+                )
+                .await;
+                let data = data.clone();
+                let control = control.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = serve(stream, &data, &control).await {
+                        console_eprintln!("监控界面连接出错 / Monitor viewer error: {}", e);
+                    }
+                });
+            }
+        });
+
+        Ok(MonitorServer {
+            path: path.to_path_buf(),
+        })
+    }
+}
+
+// 先在仅当前用户可进入 (0700) 的临时目录中创建套接字并改为 0600，再移动到目标路径，
+// 避免创建与修改权限之间其他用户连接上来
+#[cfg(unix)]
+fn bind_private(path: &Path) -> std::io::Result<tokio::net::UnixListener> {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+
+    let dir = path.with_file_name(format!(
+        ".{}.{}",
+        path.file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        std::process::id()
+    ));
+    std::fs::DirBuilder::new().mode(0o700).create(&dir)?;
+    let staged = dir.join("socket");
+    let result = tokio::net::UnixListener::bind(&staged).and_then(|listener| {
+        std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o600))?;
+        std::fs::rename(&staged, path)?;
+        Ok(listener)
+    });
+    let _ = std::fs::remove_file(&staged);
+    let _ = std::fs::remove_dir(&dir);
+    result
+}
+
+#[cfg(not(unix))]
+impl MonitorServer {
+    pub fn bind(
+        _path: &Path,
+        _data: Arc<MonitorData>,
+        _control: Arc<MinerControl>,
+    ) -> Result<Self> {
+        Err(anyhow!(
+            "监控套接字仅支持 Unix 系统 / The monitor socket is only supported on Unix"
+        ))
+    }
+}
+
+impl Drop for MonitorServer {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+// 处理一个界面连接：定时推送监控数据与新日志，执行界面发来的操作
+#[cfg(unix)]
+async fn serve(
+    stream: tokio::net::UnixStream,
+    data: &MonitorData,
+    control: &MinerControl,
+) -> Result<()> {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    async fn send(
+        writer: &mut (impl AsyncWriteExt + Unpin),
+        message: &ServerMessage,
+    ) -> Result<()> {
+        let mut line = serde_json::to_vec(message)?;
+        line.push(b'\n');
+        writer.write_all(&line).await?;
+        Ok(())
+    }
+
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    let hello = tokio::time::timeout(HELLO_TIMEOUT, lines.next_line())
+        .await
+        .map_err(|_| anyhow!("等待握手超时 / Timed out waiting for hello"))??;
+    let Some(Ok(ClientMessage::Hello { control: allowed })) =
+        hello.map(|line| serde_json::from_str::<ClientMessage>(&line))
+    else {
+        return Err(anyhow!("握手消息无效 / Invalid hello message"));
+    };
+
+    send(
+        &mut writer,
+        &ServerMessage::Welcome {
+            pid: std::process::id(),
+            control: allowed,
+        },
+    )
+    .await?;
+    let mode = if allowed {
+        "可操作 / control"
+    } else {
+        "只读 / read-only"
+    };
+    console_println!(
+        "监控界面已连接（{}） / Monitor viewer attached ({})",
+        mode,
+        mode
+    );
+
+    let mut last_log = None;
+    let mut interval = tokio::time::interval(SNAPSHOT_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {
+                let logs = data.logs_after(last_log);
+                if let Some(line) = logs.last() {
+                    last_log = Some(line.seq);
+                    send(&mut writer, &ServerMessage::Logs { lines: logs }).await?;
+                }
+                let snapshot = ServerMessage::Snapshot {
+                    state: crate::miner_control::Controller::state(control),
+                    data: Box::new(data.snapshot()),
+                };
+                send(&mut writer, &snapshot).await?;
+            }
+            line = lines.next_line() => {
+                let Some(line) = line? else {
+                    break;
+                };
+                match serde_json::from_str::<ClientMessage>(&line) {
+                    Ok(ClientMessage::Command { command }) if allowed => control.apply(command, data),
+                    Ok(ClientMessage::Command { .. }) => console_eprintln!(
+                        "忽略只读界面发来的操作 / Ignoring a command from a read-only viewer"
+                    ),
+                    _ => return Err(anyhow!("无效的消息 / Invalid message: {}", line)),
+                }
+            }
+        }
+    }
+
+    console_println!("监控界面已断开 / Monitor viewer detached");
+    Ok(())
+}
+
+// 以只读方式订阅挖矿进程的监控数据，每收到一次数据调用 update，logs 为新增的日志；
+// 连接断开时返回
+#[cfg(unix)]
+pub async fn subscribe(path: &Path, mut update: impl FnMut(MinerStatus)) -> Result<()> {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let mut stream = tokio::net::UnixStream::connect(path).await?;
+    let mut hello = serde_json::to_vec(&ClientMessage::Hello { control: false })?;
+    hello.push(b'\n');
+    stream.write_all(&hello).await?;
+
+    let mut lines = BufReader::new(stream).lines();
+    let mut logs = Vec::new();
+    while let Some(line) = lines.next_line().await? {
+        match serde_json::from_str(&line)? {
+            ServerMessage::Logs { lines } => logs.extend(lines),
+            ServerMessage::Snapshot { state, data } => update(MinerStatus {
+                state,
+                data: *data,
+                logs: std::mem::take(&mut logs),
+            }),
+            ServerMessage::Welcome { .. } => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub async fn subscribe(_path: &Path, _update: impl FnMut(MinerStatus)) -> Result<()> {
+    Err(anyhow!(
+        "监控套接字仅支持 Unix 系统 / The monitor socket is only supported on Unix"
+    ))
+}
+
+// 界面一侧：转发操作到挖矿进程，标题栏显示挖矿进程推送的控制状态
+#[cfg(unix)]
+struct RemoteControl {
+    writer: std::sync::Mutex<std::os::unix::net::UnixStream>,
+    state: std::sync::Mutex<ControlState>,
+    control: bool,
+}
+
+#[cfg(unix)]
+impl crate::miner_control::Controller for RemoteControl {
+    fn state(&self) -> ControlState {
+        *self.state.lock().unwrap()
+    }
+
+    fn can_control(&self) -> bool {
+        self.control
+    }
+
+    fn send(&self, command: MinerCommand, _data: &MonitorData) {
+        let mut writer = self.writer.lock().unwrap();
+        if let Err(e) = write_message(&mut *writer, &ClientMessage::Command { command }) {
+            console_eprintln!("无法发送操作 / Cannot send command: {}", e);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn write_message(writer: &mut impl std::io::Write, message: &ClientMessage) -> Result<()> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    writer.write_all(&line)?;
+    Ok(())
+}
+
+// 读取一条消息，连接关闭时返回 None
+#[cfg(unix)]
+fn read_message(reader: &mut impl std::io::BufRead) -> Result<Option<ServerMessage>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(serde_json::from_str(&line)?))
+}
+
+// 连接运行中的挖矿进程并打开监控界面；退出界面只断开连接，不影响挖矿
+#[cfg(unix)]
+pub fn attach(path: &Path, control: bool) -> Result<()> {
+    use std::{
+        io::BufReader,
+        net::Shutdown,
+        os::unix::net::UnixStream,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Mutex,
+        },
+    };
+
+    use crate::{resources::ResourceSampler, tui_monitor::TuiApp};
+
+    let stream = UnixStream::connect(path).map_err(|e| {
+        anyhow!(
+            "无法连接监控套接字，挖矿进程是否以 --monitor-socket 运行？ / Cannot connect to monitor socket {}, is the miner running with --monitor-socket? {}",
+            path.display(),
+            e
+        )
+    })?;
+    let mut writer = stream.try_clone()?;
+    write_message(&mut writer, &ClientMessage::Hello { control })?;
+
+    stream.set_read_timeout(Some(HELLO_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let Some(ServerMessage::Welcome { pid, control }) = read_message(&mut reader)? else {
+        return Err(anyhow!(
+            "挖矿进程没有响应握手 / The miner did not answer the hello"
+        ));
+    };
+    stream.set_read_timeout(None)?;
+
+    let data = Arc::new(MonitorData::new());
+    let stop = Arc::new(AtomicBool::new(false));
+    let remote = Arc::new(RemoteControl {
+        writer: Mutex::new(writer),
+        state: Mutex::new(ControlState::default()),
+        control,
+    });
+
+    // 接收线程：连接断开时关闭界面
+    let receiver = {
+        let data = data.clone();
+        let remote = remote.clone();
+        let stop = stop.clone();
+        std::thread::spawn(move || {
+            let result = (|| -> Result<()> {
+                while let Some(message) = read_message(&mut reader)? {
+                    match message {
+                        ServerMessage::Snapshot {
+                            state,
+                            data: snapshot,
+                        } => {
+                            *remote.state.lock().unwrap() = state;
+                            data.restore(*snapshot);
+                        }
+                        ServerMessage::Logs { lines } => data.append_logs(lines),
+                        ServerMessage::Welcome { .. } => {}
+                    }
+                }
+                Ok(())
+            })();
+            stop.store(true, Ordering::SeqCst);
+            result
+        })
+    };
+
+    crate::console::capture(data.clone());
+    let mut app = TuiApp::new(data, stop, remote, ResourceSampler::for_process(pid));
+    let result = app.run();
+    crate::console::release();
+
+    // 界面先退出时主动断开，让接收线程结束
+    let disconnected = receiver.is_finished();
+    let _ = stream.shutdown(Shutdown::Both);
+    let received = receiver.join().unwrap_or(Ok(()));
+    result?;
+    if disconnected {
+        received?;
+        return Err(anyhow!(
+            "挖矿进程已断开连接 / The miner closed the connection"
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn attach(_path: &Path, _control: bool) -> Result<()> {
+    Err(anyhow!(
+        "监控套接字仅支持 Unix 系统 / The monitor socket is only supported on Unix"
+    ))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::mock_rpc::idle_control;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[tokio::test]
+    async fn socket_is_private_and_exclusive() {
+        let control = idle_control().await;
+        let data = Arc::new(MonitorData::new());
+        let path = std::env::temp_dir().join(format!(
+            "pow-client-monitor-test-{}.sock",
+            rand::random::<u64>()
+        ));
+
+        let server = MonitorServer::bind(&path, data.clone(), control.clone()).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert!(std::os::unix::net::UnixStream::connect(&path).is_ok());
+        // 临时目录已清理
+        let staging = path.with_file_name(format!(
+            ".{}.{}",
+            path.file_name().unwrap().to_string_lossy(),
+            std::process::id()
+        ));
+        assert!(!staging.exists());
+
+        assert!(MonitorServer::bind(&path, data, control).is_err());
+        drop(server);
+        assert!(!path.exists());
+    }
+}
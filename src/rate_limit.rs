@@ -0,0 +1,173 @@
+use std::{sync::Mutex, time::Duration};
+// 使用 tokio 的时钟，测试中可以暂停并快进时间
+use tokio::time::{sleep, Instant};
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+    // 节点返回 429 后暂停发送的截止时间
+    paused_until: Option<Instant>,
+}
+
+// 令牌桶限速器：每秒补充 rate 个令牌，最多积累 burst 个
+#[derive(Debug)]
+pub struct RateLimiter {
+    rate: f64,
+    burst: f64,
+    bucket: Mutex<Bucket>,
+}
+
+impl RateLimiter {
+    // rate 为 0 时不限速
+    pub fn new(rate: f64) -> Self {
+        let burst = rate.max(1.0);
+        RateLimiter {
+            rate,
+            burst,
+            bucket: Mutex::new(Bucket {
+                tokens: burst,
+                updated: Instant::now(),
+                paused_until: None,
+            }),
+        }
+    }
+
+    // 取得一个令牌，必要时等待
+    pub async fn acquire(&self) {
+        if self.rate <= 0.0 {
+            return;
+        }
+
+        loop {
+            let wait = {
+                let now = Instant::now();
+                let mut bucket = self.bucket.lock().unwrap();
+                match bucket.paused_until {
+                    Some(until) if now < until => until - now,
+                    _ => {
+                        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+                        bucket.tokens = (bucket.tokens + elapsed * self.rate).min(self.burst);
+                        bucket.updated = now;
+                        if bucket.tokens >= 1.0 {
+                            bucket.tokens -= 1.0;
+                            return;
+                        }
+                        Duration::from_secs_f64((1.0 - bucket.tokens) / self.rate)
+                    }
+                }
+            };
+            sleep(wait).await;
+        }
+    }
+
+    // 节点要求退避时暂停发送，并清空已积累的令牌
+    pub fn pause(&self, duration: Duration) {
+        let until = Instant::now() + duration;
+        let mut bucket = self.bucket.lock().unwrap();
+        bucket.tokens = 0.0;
+        // 已有更长的暂停时保持不变，令牌从暂停结束时开始补充
+        if bucket.paused_until.map_or(true, |current| current < until) {
+            bucket.paused_until = Some(until);
+            bucket.updated = until;
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        let bucket = self.bucket.lock().unwrap();
+        bucket
+            .paused_until
+            .is_some_and(|until| Instant::now() < until)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::time::advance;
+
+    // 时钟暂停时，等待的时长正好是限速器要求的时长
+    async fn timed_acquire(limiter: &RateLimiter) -> Duration {
+        let start = Instant::now();
+        limiter.acquire().await;
+        start.elapsed()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn unlimited_never_waits() {
+        let limiter = RateLimiter::new(0.0);
+        let start = Instant::now();
+        for _ in 0..1000 {
+            limiter.acquire().await;
+        }
+        assert_eq!(start.elapsed(), Duration::ZERO);
+        assert!(!limiter.is_paused());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn burst_then_refill_at_rate() {
+        let limiter = RateLimiter::new(20.0);
+        // 初始可以连续取得 burst 个令牌
+        for _ in 0..20 {
+            assert_eq!(timed_acquire(&limiter).await, Duration::ZERO);
+        }
+
+        // 之后每 50ms 补充一个
+        let waited = timed_acquire(&limiter).await;
+        assert!(
+            waited >= Duration::from_millis(49) && waited <= Duration::from_millis(51),
+            "{:?}",
+            waited
+        );
+        advance(Duration::from_millis(50)).await;
+        assert_eq!(timed_acquire(&limiter).await, Duration::ZERO);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn refill_is_capped_at_burst() {
+        let limiter = RateLimiter::new(10.0);
+        for _ in 0..10 {
+            limiter.acquire().await;
+        }
+        advance(Duration::from_secs(60)).await;
+
+        for _ in 0..10 {
+            assert_eq!(timed_acquire(&limiter).await, Duration::ZERO);
+        }
+        assert!(timed_acquire(&limiter).await >= Duration::from_millis(99));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn pause_blocks_until_it_ends() {
+        let limiter = RateLimiter::new(100.0);
+        limiter.pause(Duration::from_millis(150));
+        assert!(limiter.is_paused());
+
+        let waited = timed_acquire(&limiter).await;
+        assert!(waited >= Duration::from_millis(150), "{:?}", waited);
+        assert!(waited < Duration::from_millis(200), "{:?}", waited);
+        assert!(!limiter.is_paused());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn shorter_pause_does_not_shorten_a_longer_one() {
+        let limiter = RateLimiter::new(100.0);
+        limiter.pause(Duration::from_millis(300));
+        limiter.pause(Duration::from_millis(10));
+
+        advance(Duration::from_millis(50)).await;
+        assert!(limiter.is_paused());
+        let waited = timed_acquire(&limiter).await;
+        assert!(waited >= Duration::from_millis(250), "{:?}", waited);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn longer_pause_extends_a_shorter_one() {
+        let limiter = RateLimiter::new(100.0);
+        limiter.pause(Duration::from_millis(10));
+        limiter.pause(Duration::from_millis(200));
+
+        let waited = timed_acquire(&limiter).await;
+        assert!(waited >= Duration::from_millis(200), "{:?}", waited);
+    }
+}
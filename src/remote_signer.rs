@@ -0,0 +1,440 @@
+use anyhow::anyhow;
+use async_trait::async_trait;
+use ethers::{
+    signers::{LocalWallet, Signer, WalletError},
+    types::{
+        transaction::{eip2718::TypedTransaction, eip712::Eip712},
+        Address, Bytes, Signature,
+    },
+    utils::rlp::Rlp,
+};
+use reqwest::Url;
+use serde_json::{json, Value};
+use std::{net::IpAddr, sync::Arc};
+use thiserror::Error;
+
+use crate::{
+    rpc_config::EndpointConfig,
+    rpc_pool::{RpcPoolError, Transport},
+};
+
+#[derive(Debug, Error)]
+pub enum RemoteSignerError {
+    #[error(transparent)]
+    Rpc(#[from] RpcPoolError),
+    #[error("无法解析签名服务返回的数据 / Cannot decode signer response: {0}")]
+    Decode(String),
+    #[error("签名服务返回的交易与请求不符 / Signer returned a transaction that differs from the request")]
+    Mismatch,
+    #[error("签名来自 {0:?} 而不是 {1:?} / Signature recovers to {0:?} instead of {1:?}")]
+    WrongSigner(Address, Address),
+    #[error(
+        "签名服务不支持 EIP-712 签名 / Typed data signing is not supported by the remote signer"
+    )]
+    Unsupported,
+}
+
+// 把 eth_signTransaction 委托给外部签名服务（Clef、web3signer 等），
+// 挖矿主机上不保存私钥
+#[derive(Debug, Clone)]
+pub struct RemoteSigner {
+    url: String,
+    client: Arc<Transport>,
+    address: Address,
+    chain_id: u64,
+}
+
+impl RemoteSigner {
+    // 连接签名服务；只接受本机 HTTP(S) 或 Unix 套接字，其他主机的 HTTPS 需 allow_remote，
+    // 未指定地址时使用服务唯一的账户
+    pub async fn connect(
+        url: &str,
+        address: Option<Address>,
+        chain_id: u64,
+        allow_remote: bool,
+    ) -> anyhow::Result<Self> {
+        check_url(url, allow_remote)?;
+
+        let client = Transport::connect(&EndpointConfig::from_url(url))
+            .await
+            .map_err(|e| anyhow!("无法连接签名服务 / Cannot connect to signer {}: {}", url, e))?;
+
+        let address = match address {
+            Some(address) => address,
+            None => {
+                let accounts: Vec<Address> = client
+                    .request("eth_accounts", &json!([]))
+                    .await
+                    .map_err(|e| {
+                        anyhow!(
+                            "无法获取签名服务账户 / Cannot list signer accounts {}: {}",
+                            url,
+                            e
+                        )
+                    })?;
+                match accounts.as_slice() {
+                    [address] => *address,
+                    [] => {
+                        return Err(anyhow!(
+                            "签名服务没有可用账户 / Signer has no accounts: {}",
+                            url
+                        ))
+                    }
+                    _ => {
+                        return Err(anyhow!(
+                            "签名服务有多个账户，请用 --signer-address 指定 / Signer has several accounts, choose one with --signer-address"
+                        ))
+                    }
+                }
+            }
+        };
+
+        Ok(RemoteSigner {
+            url: url.to_string(),
+            client: Arc::new(client),
+            address,
+            chain_id,
+        })
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+#[async_trait]
+impl Signer for RemoteSigner {
+    type Error = RemoteSignerError;
+
+    async fn sign_message<S: Send + Sync + AsRef<[u8]>>(
+        &self,
+        message: S,
+    ) -> Result<Signature, Self::Error> {
+        let data = Bytes::from(message.as_ref().to_vec());
+        let signature: Bytes = self
+            .client
+            .request("eth_sign", &json!([self.address, data]))
+            .await?;
+        Signature::try_from(signature.as_ref())
+            .map_err(|e| RemoteSignerError::Decode(e.to_string()))
+    }
+
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature, Self::Error> {
+        let mut tx = tx.clone();
+        if tx.chain_id().is_none() {
+            tx.set_chain_id(self.chain_id);
+        }
+
+        // Clef 返回 {raw, tx}，web3signer 直接返回原始交易
+        let response: Value = self
+            .client
+            .request(
+                "eth_signTransaction",
+                &json!([sign_request(&tx, self.address)]),
+            )
+            .await?;
+        let raw = match &response {
+            Value::String(raw) => raw.as_str(),
+            other => other
+                .get("raw")
+                .and_then(Value::as_str)
+                .ok_or_else(|| RemoteSignerError::Decode(other.to_string()))?,
+        };
+        let raw: Bytes = raw.parse().map_err(|e: ethers::types::ParseBytesError| {
+            RemoteSignerError::Decode(e.to_string())
+        })?;
+        let (signed, signature) = TypedTransaction::decode_signed(&Rlp::new(&raw))
+            .map_err(|e| RemoteSignerError::Decode(e.to_string()))?;
+
+        // 签名服务只能签名，不能改动交易内容或换用其他账户
+        let sighash = tx.sighash();
+        if signed.sighash() != sighash {
+            return Err(RemoteSignerError::Mismatch);
+        }
+        let signer = signature
+            .recover(sighash)
+            .map_err(|e| RemoteSignerError::Decode(e.to_string()))?;
+        if signer != self.address {
+            return Err(RemoteSignerError::WrongSigner(signer, self.address));
+        }
+        Ok(signature)
+    }
+
+    async fn sign_typed_data<T: Eip712 + Send + Sync>(
+        &self,
+        _payload: &T,
+    ) -> Result<Signature, Self::Error> {
+        Err(RemoteSignerError::Unsupported)
+    }
+
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    fn with_chain_id<T: Into<u64>>(mut self, chain_id: T) -> Self {
+        self.chain_id = chain_id.into();
+        self
+    }
+}
+
+// 签名服务能替我们签任意交易，默认只允许本机；其他主机需使用 HTTPS 并显式允许
+fn check_url(url: &str, allow_remote: bool) -> anyhow::Result<()> {
+    let https = url.starts_with("https://");
+    if !https && !url.starts_with("http://") {
+        if url.contains("://") {
+            return Err(anyhow!(
+                "签名服务只支持 HTTP(S) 或 Unix 套接字 / Signer must be reached over HTTP(S) or a Unix socket: {}",
+                url
+            ));
+        }
+        return Ok(());
+    }
+
+    let parsed = Url::parse(url)?;
+    let local = match parsed.host_str() {
+        Some("localhost") => true,
+        Some(host) => host
+            .trim_matches(|c| c == '[' || c == ']')
+            .parse::<IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback()),
+        None => false,
+    };
+    if local || (https && allow_remote) {
+        return Ok(());
+    }
+    if https {
+        return Err(anyhow!(
+            "签名服务不在本机，确认可信后使用 --allow-remote-signer / Signer is not on localhost, use --allow-remote-signer if you trust it: {}",
+            url
+        ));
+    }
+    Err(anyhow!(
+        "其他主机上的签名服务必须使用 HTTPS / A signer on another host must use HTTPS: {}",
+        url
+    ))
+}
+
+// eth_signTransaction 的参数，省略未设置的字段
+fn sign_request(tx: &TypedTransaction, from: Address) -> Value {
+    let mut request = json!({
+        "from": from,
+        "to": tx.to_addr(),
+        "gas": tx.gas(),
+        "value": tx.value().copied().unwrap_or_default(),
+        "data": tx.data().cloned().unwrap_or_default(),
+        "nonce": tx.nonce(),
+        "chainId": tx.chain_id(),
+    });
+    match tx {
+        TypedTransaction::Legacy(tx) => {
+            request["gasPrice"] = json!(tx.gas_price);
+        }
+        TypedTransaction::Eip2930(tx) => {
+            request["gasPrice"] = json!(tx.tx.gas_price);
+            request["accessList"] = json!(tx.access_list);
+        }
+        TypedTransaction::Eip1559(tx) => {
+            request["maxFeePerGas"] = json!(tx.max_fee_per_gas);
+            request["maxPriorityFeePerGas"] = json!(tx.max_priority_fee_per_gas);
+            request["accessList"] = json!(tx.access_list);
+        }
+    }
+    if let Value::Object(fields) = &mut request {
+        fields.retain(|_, value| !value.is_null());
+    }
+    request
+}
+
+// 挖矿使用的签名者：本地私钥或远程签名服务
+#[derive(Debug, Clone)]
+pub enum MinerSigner {
+    Local(LocalWallet),
+    Remote(RemoteSigner),
+}
+
+#[derive(Debug, Error)]
+pub enum MinerSignerError {
+    #[error(transparent)]
+    Local(#[from] WalletError),
+    #[error(transparent)]
+    Remote(#[from] RemoteSignerError),
+}
+
+#[async_trait]
+impl Signer for MinerSigner {
+    type Error = MinerSignerError;
+
+    async fn sign_message<S: Send + Sync + AsRef<[u8]>>(
+        &self,
+        message: S,
+    ) -> Result<Signature, Self::Error> {
+        Ok(match self {
+            MinerSigner::Local(wallet) => wallet.sign_message(message).await?,
+            MinerSigner::Remote(signer) => signer.sign_message(message).await?,
+        })
+    }
+
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature, Self::Error> {
+        Ok(match self {
+            MinerSigner::Local(wallet) => wallet.sign_transaction(tx).await?,
+            MinerSigner::Remote(signer) => signer.sign_transaction(tx).await?,
+        })
+    }
+
+    async fn sign_typed_data<T: Eip712 + Send + Sync>(
+        &self,
+        payload: &T,
+    ) -> Result<Signature, Self::Error> {
+        Ok(match self {
+            MinerSigner::Local(wallet) => wallet.sign_typed_data(payload).await?,
+            MinerSigner::Remote(signer) => signer.sign_typed_data(payload).await?,
+        })
+    }
+
+    fn address(&self) -> Address {
+        match self {
+            MinerSigner::Local(wallet) => wallet.address(),
+            MinerSigner::Remote(signer) => signer.address(),
+        }
+    }
+
+    fn chain_id(&self) -> u64 {
+        match self {
+            MinerSigner::Local(wallet) => wallet.chain_id(),
+            MinerSigner::Remote(signer) => signer.chain_id(),
+        }
+    }
+
+    fn with_chain_id<T: Into<u64>>(self, chain_id: T) -> Self {
+        match self {
+            MinerSigner::Local(wallet) => MinerSigner::Local(wallet.with_chain_id(chain_id)),
+            MinerSigner::Remote(signer) => MinerSigner::Remote(signer.with_chain_id(chain_id)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_rpc::{MockRpc, Reply};
+    use ethers::types::{TransactionRequest, U256};
+
+    const CHAIN_ID: u64 = 114514;
+
+    fn wallet(byte: u8) -> LocalWallet {
+        LocalWallet::from_bytes(&[byte; 32])
+            .unwrap()
+            .with_chain_id(CHAIN_ID)
+    }
+
+    // 用 key 签名的签名服务，tamper 可在签名前改动交易；clef 为 true 时按 Clef 的 {raw, tx} 返回
+    async fn signer_service(
+        key: LocalWallet,
+        tamper: fn(&mut TransactionRequest),
+        clef: bool,
+    ) -> MockRpc {
+        MockRpc::start(move |method, params| match method {
+            "eth_accounts" => Reply::Result(json!([key.address()])),
+            "eth_signTransaction" => {
+                let mut request: TransactionRequest =
+                    serde_json::from_value(params[0].clone()).unwrap();
+                tamper(&mut request);
+                let tx = TypedTransaction::Legacy(request);
+                let signature = key.sign_transaction_sync(&tx).unwrap();
+                let raw = tx.rlp_signed(&signature);
+                if clef {
+                    Reply::Result(json!({ "raw": raw, "tx": {} }))
+                } else {
+                    Reply::Result(json!(raw))
+                }
+            }
+            _ => Reply::Error(-32601, "method not found".to_string()),
+        })
+        .await
+    }
+
+    fn tx() -> TypedTransaction {
+        TransactionRequest::new()
+            .to(Address::repeat_byte(0x51))
+            .value(U256::zero())
+            .data(vec![1, 2, 3, 4])
+            .gas(100_000)
+            .gas_price(1_000_000_000u64)
+            .nonce(7)
+            .into()
+    }
+
+    async fn remote(service: &MockRpc, address: Option<Address>) -> RemoteSigner {
+        RemoteSigner::connect(&service.url, address, CHAIN_ID, false)
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn only_local_or_opted_in_https_signers_are_accepted() {
+        assert!(check_url("http://127.0.0.1:8550", false).is_ok());
+        assert!(check_url("http://localhost:8550", false).is_ok());
+        assert!(check_url("http://[::1]:8550", false).is_ok());
+        assert!(check_url("https://127.0.0.1:8550", false).is_ok());
+        assert!(check_url("/run/clef/clef.ipc", false).is_ok());
+
+        assert!(check_url("http://10.0.0.2:8550", false).is_err());
+        assert!(check_url("http://10.0.0.2:8550", true).is_err());
+        assert!(check_url("https://signer.example.com", false).is_err());
+        assert!(check_url("https://signer.example.com", true).is_ok());
+        assert!(check_url("ws://127.0.0.1:8550", false).is_err());
+    }
+
+    #[tokio::test]
+    async fn uses_the_only_account_and_accepts_its_signature() {
+        for clef in [false, true] {
+            let key = wallet(1);
+            let service = signer_service(key.clone(), |_| {}, clef).await;
+            let signer = remote(&service, None).await;
+            assert_eq!(signer.address(), key.address());
+
+            let signature = signer.sign_transaction(&tx()).await.unwrap();
+            let mut expected = tx();
+            expected.set_chain_id(CHAIN_ID);
+            assert_eq!(
+                signature.recover(expected.sighash()).unwrap(),
+                key.address()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_a_signature_from_another_account() {
+        let service = signer_service(wallet(2), |_| {}, false).await;
+        let signer = remote(&service, Some(wallet(1).address())).await;
+
+        match signer.sign_transaction(&tx()).await {
+            Err(RemoteSignerError::WrongSigner(signer, expected)) => {
+                assert_eq!(signer, wallet(2).address());
+                assert_eq!(expected, wallet(1).address());
+            }
+            other => panic!("expected WrongSigner, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_a_modified_transaction() {
+        let tampers: [fn(&mut TransactionRequest); 3] = [
+            |request| request.value = Some(U256::exp10(18)),
+            |request| request.to = Some(Address::repeat_byte(0xee).into()),
+            |request| request.nonce = Some(U256::from(8)),
+        ];
+        for tamper in tampers {
+            let service = signer_service(wallet(1), tamper, false).await;
+            let signer = remote(&service, None).await;
+            assert!(matches!(
+                signer.sign_transaction(&tx()).await,
+                Err(RemoteSignerError::Mismatch)
+            ));
+        }
+    }
+}
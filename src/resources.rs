@@ -0,0 +1,128 @@
+use sysinfo::{ComponentExt, CpuExt, CpuRefreshKind, Pid, PidExt, ProcessExt, System, SystemExt};
+
+// 没有临界温度时按此温度提示过热
+const HOT_CELSIUS: f32 = 80.0;
+// 距临界温度多少度以内视为过热
+const CRITICAL_MARGIN: f32 = 5.0;
+// 满载且频率低于峰值的该比例时视为降频
+const THROTTLE_RATIO: f64 = 0.8;
+const BUSY_PERCENT: f32 = 90.0;
+
+pub struct Temperature {
+    pub label: String,
+    pub celsius: f32,
+    pub critical: Option<f32>,
+}
+
+impl Temperature {
+    pub fn is_hot(&self) -> bool {
+        match self.critical {
+            Some(critical) if critical > 0.0 => self.celsius >= critical - CRITICAL_MARGIN,
+            _ => self.celsius >= HOT_CELSIUS,
+        }
+    }
+}
+
+// 一次采样得到的本机资源状况
+pub struct ResourceSnapshot {
+    // 各核心占用率（%）与频率（MHz）
+    pub cpu_usage: Vec<f32>,
+    pub cpu_frequency: Vec<u64>,
+    // 本进程常驻内存（字节）
+    pub rss_bytes: u64,
+    pub load_average: (f64, f64, f64),
+    // 平台不提供温度时为空
+    pub temperatures: Vec<Temperature>,
+    // 有核心满载但频率明显低于运行以来的峰值
+    pub throttled: bool,
+}
+
+// 定期采样 CPU、内存、负载与温度；CPU 占用率按两次采样的间隔计算
+pub struct ResourceSampler {
+    system: System,
+    pid: Option<Pid>,
+    // 各核心运行以来的最高频率
+    peak_frequency: Vec<u64>,
+}
+
+impl Default for ResourceSampler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ResourceSampler {
+    pub fn new() -> Self {
+        Self::with_pid(sysinfo::get_current_pid().ok())
+    }
+
+    // 统计指定进程的内存，attach 时为挖矿进程
+    pub fn for_process(pid: u32) -> Self {
+        Self::with_pid(Some(Pid::from_u32(pid)))
+    }
+
+    fn with_pid(pid: Option<Pid>) -> Self {
+        let mut system = System::new();
+        system.refresh_cpu_specifics(CpuRefreshKind::everything());
+        system.refresh_components_list();
+        ResourceSampler {
+            system,
+            pid,
+            peak_frequency: Vec::new(),
+        }
+    }
+
+    pub fn sample(&mut self) -> ResourceSnapshot {
+        self.system
+            .refresh_cpu_specifics(CpuRefreshKind::everything());
+        self.system.refresh_components();
+        let rss_bytes = self
+            .pid
+            .filter(|pid| self.system.refresh_process(*pid))
+            .and_then(|pid| self.system.process(pid))
+            .map_or(0, |process| process.memory());
+
+        let cpus = self.system.cpus();
+        let cpu_usage: Vec<f32> = cpus.iter().map(|cpu| cpu.cpu_usage()).collect();
+        let cpu_frequency: Vec<u64> = cpus.iter().map(|cpu| cpu.frequency()).collect();
+
+        self.peak_frequency.resize(cpu_frequency.len(), 0);
+        let mut throttled = false;
+        for ((peak, &frequency), &usage) in self
+            .peak_frequency
+            .iter_mut()
+            .zip(&cpu_frequency)
+            .zip(&cpu_usage)
+        {
+            *peak = (*peak).max(frequency);
+            if usage >= BUSY_PERCENT
+                && frequency > 0
+                && (frequency as f64) < *peak as f64 * THROTTLE_RATIO
+            {
+                throttled = true;
+            }
+        }
+
+        let load = self.system.load_average();
+        let temperatures = self
+            .system
+            .components()
+            .iter()
+            .filter(|component| component.temperature().is_finite())
+            .map(|component| Temperature {
+                label: component.label().to_string(),
+                celsius: component.temperature(),
+                critical: component.critical(),
+            })
+            .collect();
+
+        ResourceSnapshot {
+            cpu_usage,
+            cpu_frequency,
+            rss_bytes,
+            load_average: (load.one, load.five, load.fifteen),
+            temperatures,
+            throttled,
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use ethers::{contract::ContractRevert, prelude::*, types::transaction::eip2718::TypedTransaction};
+use std::fmt;
+
+use crate::contract::MiningContractErrors;
+
+// Solidity 内置 Panic(uint256) 的选择器
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+// 明确表示调用方无权或参数永远无效的 Error(string) 原因（不区分大小写的子串），
+// 重发同样的交易只会再次回滚；其他原因多为任务已被解决、nonce 或状态竞争等暂时情况
+const PERMANENT_REASONS: &[&str] = &[
+    "caller is not the owner",
+    "unauthorized",
+    "not authorized",
+    "invalid signature",
+];
+
+// 失败后的重试策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryClass {
+    // 状态相关或原因未知，可以重新发送
+    Retry,
+    // 合约明确拒绝，重发同样的交易只会再次回滚
+    Abort,
+}
+
+// 解码后的回滚信息
+#[derive(Debug, Clone)]
+pub struct RevertInfo {
+    pub reason: String,
+    pub class: RetryClass,
+}
+
+impl RevertInfo {
+    pub fn should_abort(&self) -> bool {
+        self.class == RetryClass::Abort
+    }
+}
+
+impl fmt::Display for RevertInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.reason)
+    }
+}
+
+// 将回滚数据解码为合约自定义错误、Error(string) 或 Panic(uint256)
+pub fn decode_revert_data(data: &[u8]) -> RevertInfo {
+    if let Some(err) = MiningContractErrors::decode_with_selector(data) {
+        let (reason, class) = match err {
+            MiningContractErrors::OwnableInvalidOwner(e) => (
+                format!("OwnableInvalidOwner(owner: {:?})", e.owner),
+                RetryClass::Abort,
+            ),
+            MiningContractErrors::OwnableUnauthorizedAccount(e) => (
+                format!("OwnableUnauthorizedAccount(account: {:?})", e.account),
+                RetryClass::Abort,
+            ),
+            MiningContractErrors::RevertString(s) => {
+                let class = classify_reason(&s);
+                (format!("Error(\"{}\")", s), class)
+            }
+        };
+        return RevertInfo { reason, class };
+    }
+
+    if data.len() == 36 && data[..4] == PANIC_SELECTOR {
+        let code = U256::from_big_endian(&data[4..]);
+        return RevertInfo {
+            reason: format!("Panic(0x{:x})", code),
+            class: RetryClass::Abort,
+        };
+    }
+
+    if data.is_empty() {
+        RevertInfo {
+            reason: "无回滚数据 / no revert data".to_string(),
+            class: RetryClass::Retry,
+        }
+    } else {
+        RevertInfo {
+            reason: format!(
+                "未知回滚数据 / unknown revert data: 0x{}",
+                hex::encode(data)
+            ),
+            class: RetryClass::Retry,
+        }
+    }
+}
+
+// 只有已知的永久性原因才放弃，未知原因按暂时情况重试（重试次数仍受上限约束）
+fn classify_reason(reason: &str) -> RetryClass {
+    let reason = reason.to_lowercase();
+    if PERMANENT_REASONS
+        .iter()
+        .any(|permanent| reason.contains(permanent))
+    {
+        RetryClass::Abort
+    } else {
+        RetryClass::Retry
+    }
+}
+
+// 从中间件错误（例如 eth_estimateGas / eth_call 失败）中提取回滚原因
+pub fn revert_from_error<E: MiddlewareError>(err: &E) -> Option<RevertInfo> {
+    let data = err.as_error_response()?.as_revert_data()?;
+    Some(decode_revert_data(&data))
+}
+
+// 在失败交易所在区块的前一个区块上重新执行 eth_call，以获取回滚原因；
+// 交易所在区块之后的状态已包含这笔交易的影响，在其上重放得到的原因可能不对
+pub async fn diagnose_failed_tx<M: Middleware>(
+    client: &M,
+    tx: &TypedTransaction,
+    block: Option<U64>,
+) -> RevertInfo {
+    let block_id =
+        block.map(|b| BlockId::Number(BlockNumber::Number(b.saturating_sub(U64::one()))));
+
+    match client.call(tx, block_id).await {
+        Ok(_) => RevertInfo {
+            reason: "重新模拟成功，可能是状态变化或 gas 不足 / re-simulation succeeded, likely state change or out of gas".to_string(),
+            class: RetryClass::Retry,
+        },
+        Err(e) => revert_from_error(&e).unwrap_or_else(|| RevertInfo {
+            reason: format!("模拟调用失败 / Simulation failed: {}", e),
+            class: RetryClass::Retry,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_rpc::{MockRpc, Reply};
+    use ethers::{abi::Token, utils::id};
+    use serde_json::{json, Value};
+
+    // 选择器加 ABI 编码的参数
+    fn encode_call(signature: &str, args: &[Token]) -> Vec<u8> {
+        let mut data = id(signature).to_vec();
+        data.extend(ethers::abi::encode(args));
+        data
+    }
+
+    #[test]
+    fn custom_error_is_decoded_and_aborts() {
+        let account = Address::repeat_byte(0x42);
+        let data = encode_call(
+            "OwnableUnauthorizedAccount(address)",
+            &[Token::Address(account)],
+        );
+        let info = decode_revert_data(&data);
+        assert_eq!(
+            info.reason,
+            format!("OwnableUnauthorizedAccount(account: {:?})", account)
+        );
+        assert!(info.should_abort());
+    }
+
+    #[test]
+    fn error_string_is_decoded_and_classified_by_reason() {
+        let data = encode_call(
+            "Error(string)",
+            &[Token::String("Task already completed".to_string())],
+        );
+        let info = decode_revert_data(&data);
+        assert_eq!(info.reason, "Error(\"Task already completed\")");
+        assert_eq!(info.class, RetryClass::Retry);
+
+        let data = encode_call(
+            "Error(string)",
+            &[Token::String(
+                "Ownable: caller is not the owner".to_string(),
+            )],
+        );
+        assert!(decode_revert_data(&data).should_abort());
+    }
+
+    #[test]
+    fn panic_code_is_decoded() {
+        let data = encode_call("Panic(uint256)", &[Token::Uint(U256::from(0x11))]);
+        assert_eq!(data[..4], PANIC_SELECTOR);
+        let info = decode_revert_data(&data);
+        assert_eq!(info.reason, "Panic(0x11)");
+        assert!(info.should_abort());
+    }
+
+    #[test]
+    fn empty_data_is_retried() {
+        let info = decode_revert_data(&[]);
+        assert_eq!(info.class, RetryClass::Retry);
+        assert!(info.reason.contains("no revert data"));
+    }
+
+    #[test]
+    fn truncated_data_is_reported_as_unknown() {
+        let error = encode_call("Error(string)", &[Token::String("nonce race".to_string())]);
+        let panic = encode_call("Panic(uint256)", &[Token::Uint(U256::one())]);
+        for data in [&error[..4], &error[..40], &panic[..20], &[0x4e, 0x48][..]] {
+            let info = decode_revert_data(data);
+            assert_eq!(info.class, RetryClass::Retry);
+            assert_eq!(
+                info.reason,
+                format!(
+                    "未知回滚数据 / unknown revert data: 0x{}",
+                    hex::encode(data)
+                )
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn failed_tx_is_replayed_before_its_block() {
+        let node = MockRpc::start(|method, _: &Value| match method {
+            "eth_call" => Reply::Error(3, "execution reverted".to_string()),
+            _ => Reply::Result(Value::Null),
+        })
+        .await;
+        let provider = Provider::<Http>::try_from(node.url.as_str()).unwrap();
+        let tx: TypedTransaction = TransactionRequest::new().to(Address::zero()).into();
+
+        let info = diagnose_failed_tx(&provider, &tx, Some(U64::from(100))).await;
+        assert_eq!(info.class, RetryClass::Retry);
+
+        let calls = node.calls("eth_call");
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0][1], json!("0x63"));
+    }
+}
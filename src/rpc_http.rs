@@ -0,0 +1,135 @@
+use ethers::providers::{HttpClientError, JsonRpcError};
+use reqwest::{header::RETRY_AFTER, Client, StatusCode, Url};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use crate::rpc_pool::RpcPoolError;
+
+#[derive(Debug, Deserialize)]
+struct Response {
+    id: u64,
+    #[serde(default)]
+    result: Value,
+    error: Option<JsonRpcError>,
+}
+
+impl Response {
+    fn into_result(self) -> Result<Value, RpcPoolError> {
+        match self.error {
+            Some(error) => Err(HttpClientError::JsonRpcError(error).into()),
+            None => Ok(self.result),
+        }
+    }
+}
+
+// HTTP JSON-RPC 传输：与 ethers 的 Http 相比，能识别 429/Retry-After 并支持批量请求
+#[derive(Debug, Clone)]
+pub struct HttpTransport {
+    client: Client,
+    url: Url,
+    next_id: Arc<AtomicU64>,
+}
+
+impl HttpTransport {
+    pub fn new(url: Url, client: Client) -> Self {
+        HttpTransport {
+            client,
+            url,
+            next_id: Arc::new(AtomicU64::new(1)),
+        }
+    }
+
+    pub async fn request<R: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: &Value,
+    ) -> Result<R, RpcPoolError> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let body = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+
+        let text = self.post(&body).await?;
+        let response: Response = parse(&text)?;
+        Ok(serde_json::from_value(response.into_result()?)?)
+    }
+
+    // 在一个 HTTP 请求中发送多个调用，按调用顺序返回各自的结果
+    pub async fn batch(
+        &self,
+        calls: &[(&str, Value)],
+    ) -> Result<Vec<Result<Value, RpcPoolError>>, RpcPoolError> {
+        let first_id = self
+            .next_id
+            .fetch_add(calls.len() as u64, Ordering::Relaxed);
+        let body: Vec<Value> = calls
+            .iter()
+            .enumerate()
+            .map(|(i, (method, params))| {
+                json!({ "jsonrpc": "2.0", "id": first_id + i as u64, "method": method, "params": params })
+            })
+            .collect();
+
+        let text = self.post(&Value::Array(body)).await?;
+        let responses: Vec<Response> = parse(&text)?;
+
+        // 节点返回的顺序不一定与请求一致，按 id 对应
+        let mut results: Vec<Option<Result<Value, RpcPoolError>>> =
+            calls.iter().map(|_| None).collect();
+        for response in responses {
+            let index = response.id.wrapping_sub(first_id) as usize;
+            if let Some(slot) = results.get_mut(index) {
+                *slot = Some(response.into_result());
+            }
+        }
+
+        Ok(results
+            .into_iter()
+            .map(|result| {
+                result.unwrap_or_else(|| {
+                    Err(RpcPoolError::AllFailed(
+                        "批量请求缺少响应 / Missing response in batch".to_string(),
+                    ))
+                })
+            })
+            .collect())
+    }
+
+    async fn post(&self, body: &Value) -> Result<String, RpcPoolError> {
+        let response = self
+            .client
+            .post(self.url.clone())
+            .json(body)
+            .send()
+            .await
+            .map_err(HttpClientError::from)?;
+
+        if response.status() == StatusCode::TOO_MANY_REQUESTS {
+            // 只处理秒数格式的 Retry-After
+            let retry_after = response
+                .headers()
+                .get(RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.trim().parse::<u64>().ok())
+                .map(Duration::from_secs);
+            return Err(RpcPoolError::RateLimited(retry_after));
+        }
+
+        Ok(response.text().await.map_err(HttpClientError::from)?)
+    }
+}
+
+fn parse<T: DeserializeOwned>(text: &str) -> Result<T, RpcPoolError> {
+    serde_json::from_str(text).map_err(|err| {
+        HttpClientError::SerdeJson {
+            err,
+            text: text.to_string(),
+        }
+        .into()
+    })
+}
@@ -0,0 +1,1420 @@
+use async_trait::async_trait;
+use colored::*;
+use ethers::{
+    providers::{
+        HttpClientError, Ipc, IpcError, JsonRpcClient, JsonRpcError, ProviderError, RpcError, Ws,
+        WsClientError,
+    },
+    types::{Address, Bytes, TransactionReceipt, H256, U64},
+    utils::keccak256,
+};
+use futures::{future::join_all, stream::FuturesUnordered, StreamExt};
+use reqwest::Url;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    fmt::Debug,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+
+use crate::rate_limit::RateLimiter;
+use crate::rpc_config::EndpointConfig;
+use crate::rpc_http::HttpTransport;
+use crate::ws_relay;
+
+// 连续失败多少次后熔断
+const FAILURE_THRESHOLD: u32 = 3;
+// 熔断后的冷却时间
+const CIRCUIT_COOLDOWN: Duration = Duration::from_secs(30);
+// 落后最高区块超过该值的节点降级
+const MAX_BLOCK_LAG: u64 = 3;
+// 延迟的指数移动平均权重
+const LATENCY_EWMA_WEIGHT: f64 = 0.3;
+// WebSocket 断线后的自动重连次数
+const WS_RECONNECTS: usize = 10;
+// 仲裁读取中结果不一致或数据过旧的节点的隔离时间
+const QUARANTINE_DURATION: Duration = Duration::from_secs(300);
+// 需要仲裁读取的方法
+const QUORUM_METHODS: [&str; 2] = ["eth_call", "eth_getBalance"];
+// 节点返回 429 但没有 Retry-After 时的退避时间
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(5);
+// 因限流全部失败时的最大重试轮数
+const RATE_LIMIT_RETRIES: usize = 2;
+
+#[derive(Debug, Error)]
+pub enum RpcPoolError {
+    #[error(transparent)]
+    Http(#[from] HttpClientError),
+    // 体积较大，装箱以免所有 Result 都被撑大
+    #[error(transparent)]
+    Ws(Box<WsClientError>),
+    #[error(transparent)]
+    Ipc(Box<IpcError>),
+    #[error(transparent)]
+    Serde(#[from] serde_json::Error),
+    #[error("所有 RPC 节点均请求失败 / All RPC endpoints failed: {0}")]
+    AllFailed(String),
+    #[error("仲裁读取失败 / Quorum read failed: {0}")]
+    Quorum(String),
+    #[error("节点限流 / Rate limited by endpoint (retry after {0:?})")]
+    RateLimited(Option<Duration>),
+}
+
+impl RpcError for RpcPoolError {
+    fn as_error_response(&self) -> Option<&JsonRpcError> {
+        match self {
+            RpcPoolError::Http(e) => e.as_error_response(),
+            RpcPoolError::Ws(e) => e.as_error_response(),
+            RpcPoolError::Ipc(e) => e.as_error_response(),
+            _ => None,
+        }
+    }
+
+    fn as_serde_error(&self) -> Option<&serde_json::Error> {
+        match self {
+            RpcPoolError::Http(e) => e.as_serde_error(),
+            RpcPoolError::Ws(e) => e.as_serde_error(),
+            RpcPoolError::Ipc(e) => e.as_serde_error(),
+            RpcPoolError::Serde(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<WsClientError> for RpcPoolError {
+    fn from(src: WsClientError) -> Self {
+        RpcPoolError::Ws(Box::new(src))
+    }
+}
+
+impl From<IpcError> for RpcPoolError {
+    fn from(src: IpcError) -> Self {
+        RpcPoolError::Ipc(Box::new(src))
+    }
+}
+
+impl From<RpcPoolError> for ProviderError {
+    fn from(src: RpcPoolError) -> Self {
+        ProviderError::JsonRpcClientError(Box::new(src))
+    }
+}
+
+// 单个节点的健康状态
+#[derive(Debug, Default)]
+struct EndpointHealth {
+    latency_ms: Option<f64>,
+    block_number: Option<u64>,
+    consecutive_failures: u32,
+    circuit_open_until: Option<Instant>,
+    quarantined_until: Option<Instant>,
+    requests: u64,
+    failures: u64,
+    last_error: Option<String>,
+    // 用户在监控界面中停用
+    disabled: bool,
+    trust: Trust,
+}
+
+// 节点是否已确认连接的是正确的链与合约
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum Trust {
+    #[default]
+    Unchecked,
+    Verified,
+    // 链ID或合约代码不符，不再使用
+    Rejected,
+}
+
+// 启动校验确定的链ID与合约代码哈希；设置后只使用核对一致的节点，
+// 启动时未响应的节点恢复后先核对再使用。None 表示该项不核对
+#[derive(Debug, Clone, Copy)]
+pub struct ChainIdentity {
+    pub chain_id: Option<u64>,
+    pub contract: Address,
+    pub code_hash: Option<H256>,
+}
+
+impl ChainIdentity {
+    // 与链身份不一致时返回原因
+    pub fn mismatch(&self, chain_id: u64, code: &Bytes) -> Option<String> {
+        if let Some(expected) = self.chain_id.filter(|expected| *expected != chain_id) {
+            return Some(format!(
+                "链ID {} 与设置的 {} 不符 / chain ID {} does not match configured {}",
+                chain_id, expected, chain_id, expected
+            ));
+        }
+        let code_hash = H256::from(keccak256(code));
+        if let Some(expected) = self.code_hash.filter(|expected| *expected != code_hash) {
+            return Some(format!(
+                "合约代码哈希 {:?} 与期望的 {:?} 不符 / contract code hash {:?} does not match expected {:?}",
+                code_hash, expected, code_hash, expected
+            ));
+        }
+        None
+    }
+}
+
+impl EndpointHealth {
+    fn circuit_open(&self, now: Instant) -> bool {
+        self.circuit_open_until.is_some_and(|until| now < until)
+    }
+
+    fn quarantined(&self, now: Instant) -> bool {
+        self.quarantined_until.is_some_and(|until| now < until)
+    }
+
+    // 熔断或隔离中的节点不参与请求
+    fn unavailable(&self, now: Instant) -> bool {
+        self.circuit_open(now) || self.quarantined(now)
+    }
+
+    // 未停用，且未被拒绝；已设置链身份时还需核对通过
+    fn usable(&self, checking: bool) -> bool {
+        !self.disabled
+            && match self.trust {
+                Trust::Verified => true,
+                Trust::Unchecked => !checking,
+                Trust::Rejected => false,
+            }
+    }
+}
+
+// 仲裁读取配置：向 size 个节点查询，至少 threshold 个节点在同一区块上结果一致才采信
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quorum {
+    pub threshold: usize,
+    pub size: usize,
+}
+
+// 格式为 M/N，例如 2/3
+impl FromStr for Quorum {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (threshold, size) = s
+            .split_once('/')
+            .ok_or_else(|| "格式应为 M/N，例如 2/3 / expected M/N, e.g. 2/3".to_string())?;
+        let threshold: usize = threshold.trim().parse().map_err(|e| format!("{}", e))?;
+        let size: usize = size.trim().parse().map_err(|e| format!("{}", e))?;
+        if threshold == 0 || threshold > size {
+            return Err("需要 0 < M <= N / expected 0 < M <= N".to_string());
+        }
+        Ok(Quorum { threshold, size })
+    }
+}
+
+// 节点的传输方式，由地址前缀决定：
+// http(s):// → HTTP，ws(s):// → WebSocket，ipc:// 或本地路径 → IPC
+#[derive(Debug, Clone)]
+pub enum Transport {
+    Http(HttpTransport),
+    Ws(Ws),
+    Ipc(Ipc),
+}
+
+impl Transport {
+    pub async fn connect(config: &EndpointConfig) -> anyhow::Result<Self> {
+        let url = config.url.as_str();
+        let transport = if url.starts_with("http://") || url.starts_with("https://") {
+            Transport::Http(HttpTransport::new(Url::parse(url)?, config.http_client()?))
+        } else if url.starts_with("ws://") || url.starts_with("wss://") {
+            // 需要认证、代理或自定义 CA 时经本地中继连接
+            let url = if config.is_customized() {
+                ws_relay::spawn(config.clone()).await?
+            } else {
+                url.to_string()
+            };
+            Transport::Ws(Ws::connect_with_reconnects(url, WS_RECONNECTS).await?)
+        } else {
+            let path = url.strip_prefix("ipc://").unwrap_or(url);
+            Transport::Ipc(Ipc::connect(path).await?)
+        };
+        Ok(transport)
+    }
+
+    pub async fn request<R: DeserializeOwned + Send>(
+        &self,
+        method: &str,
+        params: &Value,
+    ) -> Result<R, RpcPoolError> {
+        Ok(match self {
+            Transport::Http(client) => client.request(method, params).await?,
+            Transport::Ws(client) => client.request(method, params).await?,
+            Transport::Ipc(client) => client.request(method, params).await?,
+        })
+    }
+
+    pub fn supports_subscriptions(&self) -> bool {
+        !matches!(self, Transport::Http(_))
+    }
+}
+
+#[derive(Debug)]
+struct Endpoint {
+    url: String,
+    client: Transport,
+    limiter: RateLimiter,
+    health: Mutex<EndpointHealth>,
+}
+
+impl Endpoint {
+    // 经限速器发送请求；节点返回 429 时按 Retry-After 暂停该节点
+    async fn call<R: DeserializeOwned + Send>(
+        &self,
+        method: &str,
+        params: &Value,
+    ) -> Result<R, RpcPoolError> {
+        self.limiter.acquire().await;
+        let result = self.client.request(method, params).await;
+        if let Err(RpcPoolError::RateLimited(retry_after)) = &result {
+            self.throttled(*retry_after);
+        }
+        result
+    }
+
+    fn throttled(&self, retry_after: Option<Duration>) {
+        let pause = retry_after.unwrap_or(DEFAULT_RETRY_AFTER);
+        self.limiter.pause(pause);
+        console_eprintln!(
+            warn: "{}",
+            format!(
+                "RPC 节点 {} 限流，暂停 {} 秒 / RPC endpoint {} rate limited, pausing for {}s",
+                self.url,
+                pause.as_secs(),
+                self.url,
+                pause.as_secs()
+            )
+            .yellow()
+        );
+    }
+
+    // 查询节点的链ID与合约代码
+    async fn identify(&self, contract: Address) -> Result<(u64, Bytes), RpcPoolError> {
+        let chain_id: U64 = self.call("eth_chainId", &Value::Array(vec![])).await?;
+        let params = Value::Array(vec![serde_json::to_value(contract)?, "latest".into()]);
+        let code: Bytes = self.call("eth_getCode", &params).await?;
+        Ok((chain_id.as_u64(), code))
+    }
+
+    fn set_trust(&self, trust: Trust, reason: Option<String>) {
+        let mut health = self.health.lock().unwrap();
+        health.trust = trust;
+        if reason.is_some() {
+            health.last_error = reason.clone();
+        }
+        drop(health);
+
+        if let Some(reason) = reason {
+            console_eprintln!(
+                error: "{}",
+                format!(
+                    "RPC 节点 {} 已隔离，不再使用 / RPC endpoint {} quarantined permanently: {}",
+                    self.url, self.url, reason
+                )
+                .red()
+            );
+        }
+    }
+
+    // 记录节点错误；限流不计入熔断
+    fn record_error(&self, error: &RpcPoolError) {
+        if matches!(error, RpcPoolError::RateLimited(_)) {
+            return;
+        }
+        if self.record_failure(error.to_string()) {
+            log_circuit_opened(&self.url, &error.to_string());
+        }
+    }
+
+    // 记录成功请求，返回熔断器是否由此恢复
+    fn record_success(&self) -> bool {
+        let mut health = self.health.lock().unwrap();
+        health.requests += 1;
+        health.consecutive_failures = 0;
+        health.last_error = None;
+        health.circuit_open_until.take().is_some()
+    }
+
+    // 隔离返回异常数据的节点，隔离期间健康检查成功也不会恢复
+    fn quarantine(&self, reason: String) {
+        let mut health = self.health.lock().unwrap();
+        health.quarantined_until = Some(Instant::now() + QUARANTINE_DURATION);
+        health.last_error = Some(reason.clone());
+        drop(health);
+
+        console_eprintln!(
+            error: "{}",
+            format!(
+                "RPC 节点 {} 已隔离 {} 秒 / RPC endpoint {} quarantined for {}s: {}",
+                self.url,
+                QUARANTINE_DURATION.as_secs(),
+                self.url,
+                QUARANTINE_DURATION.as_secs(),
+                reason
+            )
+            .red()
+        );
+    }
+
+    // 记录失败请求，返回熔断器是否由此打开
+    fn record_failure(&self, error: String) -> bool {
+        let now = Instant::now();
+        let mut health = self.health.lock().unwrap();
+        health.requests += 1;
+        health.failures += 1;
+        health.consecutive_failures += 1;
+        health.last_error = Some(error);
+
+        if health.consecutive_failures >= FAILURE_THRESHOLD && !health.circuit_open(now) {
+            health.circuit_open_until = Some(now + CIRCUIT_COOLDOWN);
+            return true;
+        }
+        false
+    }
+}
+
+// 供日志和 TUI 展示的节点状态快照
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EndpointStatus {
+    pub url: String,
+    pub latency_ms: Option<f64>,
+    pub block_number: Option<u64>,
+    pub lag: Option<u64>,
+    pub circuit_open: bool,
+    pub quarantined: bool,
+    pub requests: u64,
+    pub failures: u64,
+    pub last_error: Option<String>,
+    pub disabled: bool,
+    pub preferred: bool,
+    // 链ID或合约代码不符
+    #[serde(default)]
+    pub rejected: bool,
+    // 尚未核对链ID与合约代码，核对前不使用
+    #[serde(default)]
+    pub unverified: bool,
+}
+
+impl EndpointStatus {
+    pub fn is_healthy(&self) -> bool {
+        !self.disabled
+            && !self.rejected
+            && !self.unverified
+            && !self.circuit_open
+            && !self.quarantined
+            && self.lag.is_some_and(|lag| lag <= MAX_BLOCK_LAG)
+    }
+}
+
+// 广播结果：交易哈希以及接受了该交易的节点
+#[derive(Debug, Clone)]
+pub struct BroadcastResult {
+    pub tx_hash: H256,
+    pub accepted: Vec<String>,
+}
+
+#[derive(Debug)]
+struct PoolInner {
+    endpoints: Vec<Endpoint>,
+    // 用户指定优先使用的节点，健康时排在最前
+    preferred: Mutex<Option<String>>,
+    identity: Mutex<Option<ChainIdentity>>,
+}
+
+// RPC 节点池：按健康状况为每个请求选择节点，失败时自动切换
+#[derive(Debug, Clone)]
+pub struct RpcPool {
+    inner: Arc<PoolInner>,
+    quorum: Option<Quorum>,
+}
+
+impl RpcPool {
+    // 连接所有节点；WebSocket/IPC 连接失败的节点会被跳过，只要至少一个可用即可
+    pub async fn connect(configs: &[EndpointConfig]) -> anyhow::Result<Self> {
+        let mut endpoints = Vec::with_capacity(configs.len());
+        for config in configs {
+            let url = config.url.as_str();
+            match Transport::connect(config).await {
+                Ok(client) => endpoints.push(Endpoint {
+                    url: url.to_string(),
+                    client,
+                    limiter: RateLimiter::new(config.rate_limit.unwrap_or(0.0)),
+                    health: Mutex::new(EndpointHealth::default()),
+                }),
+                Err(e) => console_eprintln!(
+                    warn: "{}",
+                    format!(
+                        "无法连接 RPC 节点 / Cannot connect to RPC endpoint {}: {}",
+                        url, e
+                    )
+                    .yellow()
+                ),
+            }
+        }
+
+        if endpoints.is_empty() {
+            return Err(anyhow::anyhow!(
+                "没有可用的 RPC 节点 / No usable RPC endpoints configured"
+            ));
+        }
+
+        Ok(RpcPool {
+            inner: Arc::new(PoolInner {
+                endpoints,
+                preferred: Mutex::new(None),
+                identity: Mutex::new(None),
+            }),
+            quorum: None,
+        })
+    }
+
+    // 启用仲裁读取：余额与合约只读调用需要多个节点在同一区块上结果一致
+    pub fn with_quorum(mut self, quorum: Quorum) -> Self {
+        self.quorum = Some(quorum);
+        self
+    }
+
+    // 返回最健康的支持订阅（WebSocket/IPC）的节点
+    pub fn pubsub_client(&self) -> Option<(String, Transport)> {
+        let now = Instant::now();
+        self.ranked()
+            .into_iter()
+            .filter(|ep| ep.client.supports_subscriptions())
+            .find(|ep| !ep.health.lock().unwrap().unavailable(now))
+            .map(|ep| (ep.url.clone(), ep.client.clone()))
+    }
+
+    fn checking_identity(&self) -> bool {
+        self.inner.identity.lock().unwrap().is_some()
+    }
+
+    // 被拒绝或未核对的节点报告的区块高度不可信，不参与计算
+    fn max_block(&self) -> Option<u64> {
+        let checking = self.checking_identity();
+        self.inner
+            .endpoints
+            .iter()
+            .filter_map(|ep| {
+                let health = ep.health.lock().unwrap();
+                health.usable(checking).then_some(health.block_number)?
+            })
+            .max()
+    }
+
+    // 逐个节点查询链ID与合约代码，供启动校验使用
+    pub async fn identify(
+        &self,
+        contract: Address,
+    ) -> Vec<(String, Result<(u64, Bytes), RpcPoolError>)> {
+        let queries = self.inner.endpoints.iter().map(|ep| async move {
+            let result = ep.identify(contract).await;
+            if let Err(e) = &result {
+                ep.record_error(e);
+            }
+            (ep.url.clone(), result)
+        });
+        join_all(queries).await
+    }
+
+    // 标记节点已核对一致，或与链身份不符而不再使用
+    pub fn set_verified(&self, url: &str, mismatch: Option<String>) {
+        if let Some(ep) = self.inner.endpoints.iter().find(|ep| ep.url == url) {
+            match mismatch {
+                None => ep.set_trust(Trust::Verified, None),
+                Some(reason) => ep.set_trust(Trust::Rejected, Some(reason)),
+            }
+        }
+    }
+
+    // 设置链身份，之后只使用核对一致的节点
+    pub fn set_identity(&self, identity: ChainIdentity) {
+        *self.inner.identity.lock().unwrap() = Some(identity);
+    }
+
+    // 核对尚未核对的节点，健康检查时调用
+    async fn verify_unchecked(&self) {
+        let Some(identity) = *self.inner.identity.lock().unwrap() else {
+            return;
+        };
+        let checks = self
+            .inner
+            .endpoints
+            .iter()
+            .filter(|ep| ep.health.lock().unwrap().trust == Trust::Unchecked)
+            .map(|ep| async move { (ep, ep.identify(identity.contract).await) });
+
+        for (ep, result) in join_all(checks).await {
+            match result {
+                Ok((chain_id, code)) => match identity.mismatch(chain_id, &code) {
+                    None => {
+                        ep.set_trust(Trust::Verified, None);
+                        console_println!(
+                            "{}",
+                            format!(
+                                "RPC 节点 {} 已通过链与合约核对 / RPC endpoint {} verified",
+                                ep.url, ep.url
+                            )
+                            .green()
+                        );
+                    }
+                    Some(reason) => ep.set_trust(Trust::Rejected, Some(reason)),
+                },
+                Err(e) => ep.record_error(&e),
+            }
+        }
+    }
+
+    // 按 熔断状态 → 限流 → 区块落后 → 首选 → 延迟 排序节点；
+    // 已停用、被拒绝、未核对或因仲裁分歧被隔离的节点不参与，全部节点都不可用时也不会退回到它们
+    fn ranked(&self) -> Vec<&Endpoint> {
+        let now = Instant::now();
+        let max_block = self.max_block();
+        let preferred = self.inner.preferred.lock().unwrap().clone();
+        let checking = self.checking_identity();
+
+        // 熔断、限流、区块落后、非首选、延迟
+        type RankKey = (bool, bool, bool, bool, u64);
+        let mut ranked: Vec<(&Endpoint, RankKey)> = self
+            .inner
+            .endpoints
+            .iter()
+            .filter(|ep| {
+                let health = ep.health.lock().unwrap();
+                health.usable(checking) && !health.quarantined(now)
+            })
+            .map(|ep| {
+                let health = ep.health.lock().unwrap();
+                let lagging = match (max_block, health.block_number) {
+                    (Some(max), Some(block)) => max.saturating_sub(block) > MAX_BLOCK_LAG,
+                    (Some(_), None) => true,
+                    _ => false,
+                };
+                let latency = health.latency_ms.map_or(u64::MAX, |l| l as u64);
+                let paused = ep.limiter.is_paused();
+                let other = preferred.as_deref() != Some(ep.url.as_str());
+                (
+                    ep,
+                    (health.circuit_open(now), paused, lagging, other, latency),
+                )
+            })
+            .collect();
+
+        ranked.sort_by_key(|(_, key)| *key);
+        ranked.into_iter().map(|(ep, _)| ep).collect()
+    }
+
+    // 并发探测所有节点的区块高度和延迟
+    pub async fn check_health(&self) {
+        let probes = self.inner.endpoints.iter().map(|ep| async move {
+            let start = Instant::now();
+            let result = ep
+                .call::<U64>("eth_blockNumber", &Value::Array(vec![]))
+                .await;
+            (ep, result, start.elapsed())
+        });
+
+        for (ep, result, elapsed) in join_all(probes).await {
+            match result {
+                Ok(block) => {
+                    {
+                        let mut health = ep.health.lock().unwrap();
+                        let sample = elapsed.as_secs_f64() * 1000.0;
+                        health.latency_ms = Some(match health.latency_ms {
+                            Some(avg) => {
+                                avg * (1.0 - LATENCY_EWMA_WEIGHT) + sample * LATENCY_EWMA_WEIGHT
+                            }
+                            None => sample,
+                        });
+                        health.block_number = Some(block.as_u64());
+                    }
+                    if ep.record_success() {
+                        log_circuit_closed(&ep.url);
+                    }
+                }
+                Err(e) => {
+                    ep.record_error(&e);
+                }
+            }
+        }
+
+        self.verify_unchecked().await;
+    }
+
+    // 未熔断且未落后的节点；全部不健康时退回 ranked()，隔离中的节点仍被排除
+    fn healthy(&self) -> Vec<&Endpoint> {
+        let now = Instant::now();
+        let max_block = self.max_block();
+        let checking = self.checking_identity();
+        let healthy: Vec<&Endpoint> = self
+            .inner
+            .endpoints
+            .iter()
+            .filter(|ep| {
+                let health = ep.health.lock().unwrap();
+                let lag = health
+                    .block_number
+                    .zip(max_block)
+                    .map(|(block, max)| max.saturating_sub(block));
+                health.usable(checking)
+                    && !health.unavailable(now)
+                    && lag.map_or(true, |lag| lag <= MAX_BLOCK_LAG)
+            })
+            .collect();
+
+        if healthy.is_empty() {
+            self.ranked()
+        } else {
+            healthy
+        }
+    }
+
+    // 将已签名的交易同时提交到所有健康节点，只要有一个节点接受即视为成功
+    pub async fn broadcast_raw_transaction(
+        &self,
+        raw: &Bytes,
+    ) -> Result<BroadcastResult, RpcPoolError> {
+        let tx_hash = H256::from(keccak256(raw));
+        let params = serde_json::to_value([raw])?;
+
+        let sends = self.healthy().into_iter().map(|ep| {
+            let params = &params;
+            async move {
+                let result = ep.call::<H256>("eth_sendRawTransaction", params).await;
+                (ep, result)
+            }
+        });
+
+        let mut accepted = Vec::new();
+        let mut rejection = None;
+        let mut errors = Vec::new();
+        for (ep, result) in join_all(sends).await {
+            match result {
+                Ok(_) => {
+                    ep.record_success();
+                    accepted.push(ep.url.clone());
+                }
+                // 交易已经通过其他节点传播过来，同样算作接受
+                Err(e) if is_already_known(&e) => {
+                    ep.record_success();
+                    accepted.push(ep.url.clone());
+                }
+                Err(e) if is_endpoint_failure(&e) => {
+                    ep.record_error(&e);
+                    errors.push(format!("{}: {}", ep.url, e));
+                }
+                Err(e) => {
+                    ep.record_success();
+                    errors.push(format!("{}: {}", ep.url, e));
+                    rejection.get_or_insert(e);
+                }
+            }
+        }
+
+        if accepted.is_empty() {
+            // 优先返回节点的拒绝原因，以便上层解码回滚数据
+            return Err(rejection.unwrap_or(RpcPoolError::AllFailed(errors.join("; "))));
+        }
+        Ok(BroadcastResult { tx_hash, accepted })
+    }
+
+    // 并发向接受了交易的节点查询收据，返回最先报告交易已打包的节点及收据
+    pub async fn first_inclusion(
+        &self,
+        broadcast: &BroadcastResult,
+    ) -> Option<(String, TransactionReceipt)> {
+        let params = Value::Array(vec![serde_json::to_value(broadcast.tx_hash).ok()?]);
+        let mut queries: FuturesUnordered<_> = self
+            .inner
+            .endpoints
+            .iter()
+            .filter(|ep| broadcast.accepted.contains(&ep.url))
+            .map(|ep| {
+                let params = &params;
+                async move {
+                    let result = ep
+                        .call::<Option<TransactionReceipt>>("eth_getTransactionReceipt", params)
+                        .await;
+                    (ep, result)
+                }
+            })
+            .collect();
+
+        while let Some((ep, result)) = queries.next().await {
+            if let Ok(Some(receipt)) = result {
+                if receipt.block_number.is_some() {
+                    return Some((ep.url.clone(), receipt));
+                }
+            }
+        }
+        None
+    }
+
+    // 仲裁读取：先确定至少 M 个节点都已到达的区块，再在该区块上并发查询，
+    // 至少 M 个结果一致才返回；与多数结果不一致或落后太多的节点将被隔离
+    async fn quorum_request(
+        &self,
+        quorum: Quorum,
+        method: &str,
+        params: Value,
+    ) -> Result<Value, RpcPoolError> {
+        let candidates: Vec<&Endpoint> = self.healthy().into_iter().take(quorum.size).collect();
+        if candidates.len() < quorum.threshold {
+            return Err(RpcPoolError::Quorum(format!(
+                "只有 {} 个可用节点，需要 {} 个 / only {} endpoints available, {} required",
+                candidates.len(),
+                quorum.threshold,
+                candidates.len(),
+                quorum.threshold
+            )));
+        }
+
+        // 1. 确定查询区块
+        let probes = candidates.iter().map(|ep| async move {
+            let result = ep
+                .call::<U64>("eth_blockNumber", &Value::Array(vec![]))
+                .await;
+            (*ep, result)
+        });
+        let mut heads = Vec::new();
+        for (ep, result) in join_all(probes).await {
+            match result {
+                Ok(block) => heads.push((ep, block.as_u64())),
+                Err(e) => {
+                    ep.record_error(&e);
+                }
+            }
+        }
+        if heads.len() < quorum.threshold {
+            return Err(RpcPoolError::Quorum(format!(
+                "只有 {} 个节点返回区块高度 / only {} endpoints returned a block number",
+                heads.len(),
+                heads.len()
+            )));
+        }
+        let mut blocks: Vec<u64> = heads.iter().map(|(_, block)| *block).collect();
+        blocks.sort_unstable_by(|a, b| b.cmp(a));
+        let target = blocks[quorum.threshold - 1];
+
+        let mut voters = Vec::new();
+        for (ep, block) in heads {
+            if block >= target {
+                voters.push(ep);
+            } else if target - block > MAX_BLOCK_LAG {
+                ep.quarantine(format!(
+                    "区块 {} 落后于仲裁区块 {} / block {} behind quorum block {}",
+                    block, target, block, target
+                ));
+            }
+        }
+
+        // 2. 在同一区块上查询并统计结果
+        let params = pin_block(params, target);
+        let reads = voters.iter().map(|ep| {
+            let params = &params;
+            async move { (*ep, ep.call::<Value>(method, params).await) }
+        });
+
+        // JSON-RPC 错误响应（如回滚）同样参与投票
+        let mut groups: Vec<(Result<Value, RpcPoolError>, Vec<&Endpoint>)> = Vec::new();
+        for (ep, result) in join_all(reads).await {
+            if let Err(e) = &result {
+                if is_endpoint_failure(e) {
+                    ep.record_error(e);
+                    continue;
+                }
+            }
+            ep.record_success();
+
+            let existing = groups
+                .iter_mut()
+                .find(|(answer, _)| same_answer(answer, &result));
+            match existing {
+                Some((_, members)) => members.push(ep),
+                None => groups.push((result, vec![ep])),
+            }
+        }
+
+        groups.sort_by_key(|(_, members)| std::cmp::Reverse(members.len()));
+        let mut groups = groups.into_iter();
+        let Some((answer, members)) = groups.next() else {
+            return Err(RpcPoolError::Quorum(format!(
+                "区块 {} 上没有节点返回结果 / no endpoint answered at block {}",
+                target, target
+            )));
+        };
+        if members.len() < quorum.threshold {
+            return Err(RpcPoolError::Quorum(format!(
+                "区块 {} 上最多 {} 个节点结果一致，需要 {} 个 / at most {} endpoints agreed at block {}, {} required",
+                target,
+                members.len(),
+                quorum.threshold,
+                members.len(),
+                target,
+                quorum.threshold
+            )));
+        }
+
+        for (_, dissenters) in groups {
+            for ep in dissenters {
+                ep.quarantine(format!(
+                    "{} 在区块 {} 上的结果与多数节点不一致 / {} result at block {} diverged from quorum",
+                    method, target, method, target
+                ));
+            }
+        }
+        answer
+    }
+
+    // 批量发送相互独立的读取请求：HTTP 节点合并为一个请求，其他节点或批量失败时逐个并发发送。
+    // 启用仲裁读取时逐个走仲裁流程
+    pub async fn batch(&self, calls: &[(&str, Value)]) -> Vec<Result<Value, RpcPoolError>> {
+        if self.quorum.is_none() {
+            for ep in self.ranked() {
+                let Transport::Http(http) = &ep.client else {
+                    break;
+                };
+                ep.limiter.acquire().await;
+                match http.batch(calls).await {
+                    Ok(results) => {
+                        if ep.record_success() {
+                            log_circuit_closed(&ep.url);
+                        }
+                        return results;
+                    }
+                    Err(e) => {
+                        if let RpcPoolError::RateLimited(retry_after) = &e {
+                            ep.throttled(*retry_after);
+                        }
+                        ep.record_error(&e);
+                    }
+                }
+            }
+        }
+
+        let requests = calls
+            .iter()
+            .map(|(method, params)| self.request::<_, Value>(method, params));
+        join_all(requests).await
+    }
+
+    pub fn status(&self) -> Vec<EndpointStatus> {
+        let now = Instant::now();
+        let max_block = self.max_block();
+        let preferred = self.inner.preferred.lock().unwrap().clone();
+        let checking = self.checking_identity();
+
+        self.inner
+            .endpoints
+            .iter()
+            .map(|ep| {
+                let health = ep.health.lock().unwrap();
+                EndpointStatus {
+                    url: ep.url.clone(),
+                    latency_ms: health.latency_ms,
+                    block_number: health.block_number,
+                    lag: health
+                        .block_number
+                        .zip(max_block)
+                        .map(|(block, max)| max.saturating_sub(block)),
+                    circuit_open: health.circuit_open(now),
+                    quarantined: health.quarantined(now),
+                    requests: health.requests,
+                    failures: health.failures,
+                    last_error: health.last_error.clone(),
+                    disabled: health.disabled,
+                    preferred: preferred.as_deref() == Some(ep.url.as_str()),
+                    rejected: health.trust == Trust::Rejected,
+                    unverified: checking && health.trust == Trust::Unchecked,
+                }
+            })
+            .collect()
+    }
+
+    // 优先使用指定节点；传入 None 恢复按健康状况自动选择
+    pub fn prefer(&self, url: Option<&str>) {
+        *self.inner.preferred.lock().unwrap() = url.map(str::to_string);
+    }
+
+    // 停用或重新启用节点；不允许停用最后一个启用的节点，返回是否生效
+    pub fn set_disabled(&self, url: &str, disabled: bool) -> bool {
+        let Some(target) = self.inner.endpoints.iter().find(|ep| ep.url == url) else {
+            return false;
+        };
+        if disabled {
+            let enabled = self
+                .inner
+                .endpoints
+                .iter()
+                .filter(|ep| !ep.health.lock().unwrap().disabled)
+                .count();
+            if enabled <= 1 && !target.health.lock().unwrap().disabled {
+                return false;
+            }
+        }
+        target.health.lock().unwrap().disabled = disabled;
+        true
+    }
+}
+
+// 节点本身出错（连接失败、响应无法解析）时才切换节点；
+// JSON-RPC 错误响应（如回滚、nonce 过低）是请求本身的结果，直接返回
+fn is_endpoint_failure(error: &RpcPoolError) -> bool {
+    error.as_error_response().is_none()
+}
+
+// 将参数中的区块标签 "latest" 替换为具体区块号，保证各节点在同一状态上回答
+fn pin_block(params: Value, block: u64) -> Value {
+    let mut params = match params {
+        Value::Array(params) => params,
+        other => return other,
+    };
+    let pinned = Value::String(format!("{:#x}", block));
+    match params.get_mut(1) {
+        Some(tag) if tag.as_str() == Some("latest") => *tag = pinned,
+        Some(_) => {}
+        None => params.push(pinned),
+    }
+    Value::Array(params)
+}
+
+fn same_answer(a: &Result<Value, RpcPoolError>, b: &Result<Value, RpcPoolError>) -> bool {
+    match (a, b) {
+        (Ok(a), Ok(b)) => a == b,
+        (Err(a), Err(b)) => match (a.as_error_response(), b.as_error_response()) {
+            (Some(a), Some(b)) => a.code == b.code && a.message == b.message && a.data == b.data,
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+// 不同客户端对重复交易的报错不同：geth "already known"，其他实现 "known transaction" 等
+fn is_already_known(error: &RpcPoolError) -> bool {
+    error.as_error_response().is_some_and(|e| {
+        let message = e.message.to_lowercase();
+        message.contains("already known")
+            || message.contains("known transaction")
+            || message.contains("already imported")
+    })
+}
+
+#[async_trait]
+impl JsonRpcClient for RpcPool {
+    type Error = RpcPoolError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        let params = serde_json::to_value(params)?;
+        if let Some(quorum) = self.quorum {
+            if QUORUM_METHODS.contains(&method) {
+                let value = self.quorum_request(quorum, method, params).await?;
+                return Ok(serde_json::from_value(value)?);
+            }
+        }
+        let mut errors = Vec::new();
+
+        // 所有节点都失败且其中有节点限流时，等待限流结束后再试
+        for _ in 0..=RATE_LIMIT_RETRIES {
+            errors.clear();
+            let mut rate_limited = false;
+
+            for ep in self.ranked() {
+                match ep.call::<Value>(method, &params).await {
+                    Ok(value) => {
+                        if ep.record_success() {
+                            log_circuit_closed(&ep.url);
+                        }
+                        return Ok(serde_json::from_value(value)?);
+                    }
+                    Err(e) if is_endpoint_failure(&e) => {
+                        rate_limited |= matches!(e, RpcPoolError::RateLimited(_));
+                        ep.record_error(&e);
+                        errors.push(format!("{}: {}", ep.url, e));
+                    }
+                    Err(e) => {
+                        if ep.record_success() {
+                            log_circuit_closed(&ep.url);
+                        }
+                        return Err(e);
+                    }
+                }
+            }
+
+            if !rate_limited {
+                break;
+            }
+        }
+
+        Err(RpcPoolError::AllFailed(errors.join("; ")))
+    }
+}
+
+fn log_circuit_opened(url: &str, error: &str) {
+    console_eprintln!(
+        warn: "{}",
+        format!(
+            "RPC 节点 {} 连续失败，暂停使用 {} 秒 / RPC endpoint {} failing, circuit opened for {}s: {}",
+            url,
+            CIRCUIT_COOLDOWN.as_secs(),
+            url,
+            CIRCUIT_COOLDOWN.as_secs(),
+            error
+        )
+        .yellow()
+    );
+}
+
+fn log_circuit_closed(url: &str) {
+    console_println!(
+        "{}",
+        format!("RPC 节点 {} 已恢复 / RPC endpoint {} recovered", url, url).green()
+    );
+}
+
+// 打印所有节点的健康状态
+pub fn print_status(status: &[EndpointStatus]) {
+    console_println!("{}", "RPC 节点状态 / RPC endpoint health:".bold());
+    for ep in status {
+        let line = format!(
+            "  {} - 延迟 / latency: {}, 区块 / block: {}, 落后 / lag: {}, 请求 / requests: {}, 失败 / failures: {}{}{}",
+            ep.url,
+            ep.latency_ms
+                .map_or("-".to_string(), |l| format!("{:.0}ms", l)),
+            ep.block_number.map_or("-".to_string(), |b| b.to_string()),
+            ep.lag.map_or("-".to_string(), |l| l.to_string()),
+            ep.requests,
+            ep.failures,
+            if ep.circuit_open { " [熔断 / circuit open]" } else { "" },
+            if ep.quarantined { " [隔离 / quarantined]" } else { "" }
+        );
+        let line = if ep.rejected {
+            format!("{} [链或合约不符 / wrong chain or contract]", line)
+        } else if ep.unverified {
+            format!("{} [未核对 / unverified]", line)
+        } else {
+            line
+        };
+        let line = if ep.disabled {
+            format!("{} [已停用 / disabled]", line)
+        } else {
+            line
+        };
+        if ep.is_healthy() {
+            console_println!("{}", line.green());
+        } else {
+            console_println!(warn: "{}", line.yellow());
+            if let Some(error) = &ep.last_error {
+                console_println!(
+                    warn: "{}",
+                    format!("    最近错误 / Last error: {}", error).yellow()
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_rpc::{pool, MockRpc, Reply};
+    use serde_json::json;
+
+    // 区块高度为 head，eth_getBalance 返回 balance 的节点
+    async fn node(head: u64, balance: &'static str) -> MockRpc {
+        MockRpc::start(move |method, _| match method {
+            "eth_blockNumber" => Reply::Result(json!(format!("{:#x}", head))),
+            "eth_getBalance" => Reply::Result(json!(balance)),
+            "eth_call" => Reply::Error(3, "execution reverted".to_string()),
+            _ => Reply::Error(-32601, "method not found".to_string()),
+        })
+        .await
+    }
+
+    async fn quorum_pool(nodes: &[&MockRpc], quorum: &str) -> RpcPool {
+        pool(nodes).await.with_quorum(quorum.parse().unwrap())
+    }
+
+    async fn balance(pool: &RpcPool) -> Result<Value, RpcPoolError> {
+        pool.request(
+            "eth_getBalance",
+            json!(["0x0000000000000000000000000000000000000001", "latest"]),
+        )
+        .await
+    }
+
+    fn quarantined(pool: &RpcPool, node: &MockRpc) -> bool {
+        pool.status()
+            .iter()
+            .find(|ep| ep.url == node.url)
+            .unwrap()
+            .quarantined
+    }
+
+    #[test]
+    fn parse_quorum() {
+        assert_eq!(
+            "2/3".parse::<Quorum>(),
+            Ok(Quorum {
+                threshold: 2,
+                size: 3
+            })
+        );
+        assert!("0/3".parse::<Quorum>().is_err());
+        assert!("4/3".parse::<Quorum>().is_err());
+        assert!("2".parse::<Quorum>().is_err());
+    }
+
+    #[test]
+    fn pin_block_replaces_latest_only() {
+        assert_eq!(
+            pin_block(json!(["0xabc", "latest"]), 16),
+            json!(["0xabc", "0x10"])
+        );
+        assert_eq!(
+            pin_block(json!([{ "to": "0xabc" }]), 16),
+            json!([{ "to": "0xabc" }, "0x10"])
+        );
+        assert_eq!(
+            pin_block(json!(["0xabc", "0x5"]), 16),
+            json!(["0xabc", "0x5"])
+        );
+    }
+
+    #[tokio::test]
+    async fn majority_answer_wins_and_dissenter_is_quarantined() {
+        let a = node(100, "0x10").await;
+        let b = node(100, "0x10").await;
+        let c = node(100, "0x99").await;
+        let pool = quorum_pool(&[&a, &b, &c], "2/3").await;
+
+        assert_eq!(balance(&pool).await.unwrap(), json!("0x10"));
+        assert!(!quarantined(&pool, &a));
+        assert!(!quarantined(&pool, &b));
+        assert!(quarantined(&pool, &c));
+    }
+
+    #[tokio::test]
+    async fn quarantined_endpoint_is_not_a_fallback() {
+        let a = node(100, "0x10").await;
+        let b = node(100, "0x10").await;
+        let c = node(100, "0x99").await;
+        let pool = quorum_pool(&[&a, &b, &c], "2/3").await;
+        balance(&pool).await.unwrap();
+        assert!(quarantined(&pool, &c));
+
+        // 只剩隔离中的节点时，普通请求与交易广播都不会发往它
+        assert!(pool.set_disabled(&a.url, true));
+        assert!(pool.set_disabled(&b.url, true));
+        let before = c.calls("eth_blockNumber").len();
+        assert!(pool
+            .request::<_, Value>("eth_blockNumber", json!([]))
+            .await
+            .is_err());
+        assert!(pool
+            .broadcast_raw_transaction(&Bytes::from(vec![0x01]))
+            .await
+            .is_err());
+        assert_eq!(c.calls("eth_blockNumber").len(), before);
+        assert!(c.calls("eth_sendRawTransaction").is_empty());
+    }
+
+    #[tokio::test]
+    async fn no_agreement_fails() {
+        let a = node(100, "0x1").await;
+        let b = node(100, "0x2").await;
+        let c = node(100, "0x3").await;
+        let pool = quorum_pool(&[&a, &b, &c], "2/3").await;
+
+        assert!(matches!(balance(&pool).await, Err(RpcPoolError::Quorum(_))));
+    }
+
+    #[tokio::test]
+    async fn reads_are_pinned_to_the_quorum_block() {
+        let a = node(0x105, "0x10").await;
+        let b = node(0x100, "0x10").await;
+        let c = node(0x90, "0x10").await;
+        let pool = quorum_pool(&[&a, &b, &c], "2/3").await;
+
+        assert_eq!(balance(&pool).await.unwrap(), json!("0x10"));
+        // 第二高的区块是至少两个节点都已到达的区块
+        for node in [&a, &b] {
+            assert_eq!(node.calls("eth_getBalance")[0][1], json!("0x100"));
+        }
+        // 落后太多的节点不参与投票并被隔离
+        assert!(c.calls("eth_getBalance").is_empty());
+        assert!(quarantined(&pool, &c));
+    }
+
+    #[tokio::test]
+    async fn too_few_endpoints_fails() {
+        let a = node(100, "0x10").await;
+        let pool = quorum_pool(&[&a], "2/3").await;
+
+        assert!(matches!(balance(&pool).await, Err(RpcPoolError::Quorum(_))));
+    }
+
+    #[tokio::test]
+    async fn agreeing_errors_are_returned() {
+        let a = node(100, "0x10").await;
+        let b = node(100, "0x10").await;
+        let pool = quorum_pool(&[&a, &b], "2/2").await;
+
+        let result: Result<Value, _> = pool
+            .request(
+                "eth_call",
+                json!([{ "to": "0x0000000000000000000000000000000000000001" }, "latest"]),
+            )
+            .await;
+        let error = result.unwrap_err();
+        assert_eq!(
+            error.as_error_response().unwrap().message,
+            "execution reverted"
+        );
+    }
+
+    fn limiter_paused(pool: &RpcPool, node: &MockRpc) -> bool {
+        pool.inner
+            .endpoints
+            .iter()
+            .find(|ep| ep.url == node.url)
+            .unwrap()
+            .limiter
+            .is_paused()
+    }
+
+    #[tokio::test]
+    async fn rate_limited_endpoint_is_paused_and_skipped() {
+        let limited = MockRpc::start(|_, _| Reply::RateLimited(Some(30))).await;
+        let healthy = node(100, "0x10").await;
+        let pool = pool(&[&limited, &healthy]).await;
+        pool.prefer(Some(&limited.url));
+
+        assert_eq!(balance(&pool).await.unwrap(), json!("0x10"));
+        assert!(limiter_paused(&pool, &limited));
+        assert!(!limiter_paused(&pool, &healthy));
+
+        // 暂停期间排在后面，不再收到请求
+        balance(&pool).await.unwrap();
+        assert_eq!(limited.calls("eth_getBalance").len(), 1);
+        assert_eq!(healthy.calls("eth_getBalance").len(), 2);
+    }
+
+    #[tokio::test]
+    async fn first_inclusion_goes_through_the_rate_limiter() {
+        let receipt = serde_json::to_value(TransactionReceipt {
+            block_number: Some(7.into()),
+            ..Default::default()
+        })
+        .unwrap();
+        let limited = MockRpc::start(|_, _| Reply::RateLimited(None)).await;
+        let included = MockRpc::start(move |_, _| Reply::Result(receipt.clone())).await;
+        let pool = pool(&[&limited, &included]).await;
+
+        let broadcast = BroadcastResult {
+            tx_hash: H256::repeat_byte(1),
+            accepted: vec![limited.url.clone(), included.url.clone()],
+        };
+        let (url, receipt) = pool.first_inclusion(&broadcast).await.unwrap();
+        assert_eq!(url, included.url);
+        assert_eq!(receipt.block_number, Some(7.into()));
+        assert!(limiter_paused(&pool, &limited));
+    }
+
+    // eth_sendRawTransaction 返回 reply 的节点
+    async fn sender(reply: Result<(), &'static str>) -> MockRpc {
+        MockRpc::start(move |method, _| match (method, reply) {
+            ("eth_sendRawTransaction", Ok(())) => {
+                Reply::Result(json!(H256::from(keccak256(raw_tx()))))
+            }
+            ("eth_sendRawTransaction", Err(message)) => Reply::Error(-32000, message.to_string()),
+            _ => Reply::Error(-32601, "method not found".to_string()),
+        })
+        .await
+    }
+
+    fn raw_tx() -> Bytes {
+        Bytes::from(vec![0xf8, 0x6b, 0x01, 0x02])
+    }
+
+    #[tokio::test]
+    async fn broadcast_accepted_by_every_endpoint() {
+        let a = sender(Ok(())).await;
+        let b = sender(Ok(())).await;
+        let pool = pool(&[&a, &b]).await;
+
+        let result = pool.broadcast_raw_transaction(&raw_tx()).await.unwrap();
+        assert_eq!(result.tx_hash, H256::from(keccak256(raw_tx())));
+        assert_eq!(result.accepted, vec![a.url.clone(), b.url.clone()]);
+        assert_eq!(a.calls("eth_sendRawTransaction").len(), 1);
+        assert_eq!(b.calls("eth_sendRawTransaction").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn broadcast_counts_already_known_as_accepted() {
+        let accepting = sender(Ok(())).await;
+        let known = sender(Err("already known")).await;
+        let replaced = sender(Err("nonce too low")).await;
+        let pool = pool(&[&replaced, &known, &accepting]).await;
+
+        let result = pool.broadcast_raw_transaction(&raw_tx()).await.unwrap();
+        assert_eq!(
+            result.accepted,
+            vec![known.url.clone(), accepting.url.clone()]
+        );
+    }
+
+    #[tokio::test]
+    async fn broadcast_rejected_everywhere_returns_the_first_error() {
+        let a = sender(Err("insufficient funds for gas * price + value")).await;
+        let b = sender(Err("nonce too low")).await;
+        let pool = pool(&[&a, &b]).await;
+
+        let error = pool.broadcast_raw_transaction(&raw_tx()).await.unwrap_err();
+        assert_eq!(
+            error.as_error_response().unwrap().message,
+            "insufficient funds for gas * price + value"
+        );
+    }
+
+    #[tokio::test]
+    async fn first_endpoint_to_report_inclusion_is_recorded() {
+        let receipt = serde_json::to_value(TransactionReceipt {
+            block_number: Some(7.into()),
+            ..Default::default()
+        })
+        .unwrap();
+        let pending = MockRpc::start(|_, _| Reply::Result(Value::Null)).await;
+        // 接受连接但从不回答的节点
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let silent = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let mut held = Vec::new();
+            while let Ok((stream, _)) = listener.accept().await {
+                held.push(stream);
+            }
+        });
+        let included = MockRpc::start(move |_, _| Reply::Result(receipt.clone())).await;
+        let unused = MockRpc::start(|_, _| Reply::Result(Value::Null)).await;
+        let configs: Vec<EndpointConfig> = [&pending.url, &silent, &included.url, &unused.url]
+            .into_iter()
+            .map(|url| EndpointConfig::from_url(url))
+            .collect();
+        let pool = RpcPool::connect(&configs).await.unwrap();
+
+        let broadcast = BroadcastResult {
+            tx_hash: H256::repeat_byte(1),
+            accepted: vec![pending.url.clone(), silent, included.url.clone()],
+        };
+        let (url, receipt) = pool.first_inclusion(&broadcast).await.unwrap();
+        assert_eq!(url, included.url);
+        assert_eq!(receipt.block_number, Some(7.into()));
+        // 未接受交易的节点不会被查询
+        assert!(unused.calls("eth_getTransactionReceipt").is_empty());
+    }
+}
@@ -0,0 +1,544 @@
+use anyhow::{anyhow, Result};
+use colored::*;
+use ethers::{
+    contract::{parse_log, EthEvent},
+    prelude::*,
+};
+use indicatif::{ProgressBar, ProgressStyle};
+use std::{collections::HashMap, time::Duration};
+use tokio::time::sleep;
+
+use crate::contract::{MiningContractEvents, MiningRewardFilter, NewMiningTaskFilter};
+use crate::ledger::{receipt_fee, RewardLedger, RewardRecord, TaskRecord};
+
+// 每次 eth_getLogs 查询的初始/最大区块跨度
+const INITIAL_BLOCK_RANGE: u64 = 5_000;
+const MAX_BLOCK_RANGE: u64 = 50_000;
+// 单个区块仍然失败时的最大重试次数
+const MAX_SINGLE_BLOCK_RETRIES: usize = 3;
+
+// 节点因区块跨度或结果数量超限而拒绝查询时的错误信息片段（小写）；
+// 只匹配明确的说法，服务商的额度与限流错误拆分范围只会发出更多请求
+const RANGE_LIMIT_ERRORS: &[&str] = &[
+    "block range",
+    "range too large",
+    "range is too large",
+    "query returned more than",
+    "response size exceeded",
+];
+
+// 是否为跨度或结果数量超限的错误；认证、连接等其他错误拆分范围也无济于事
+fn is_range_limit_error(message: &str) -> bool {
+    let message = message.to_lowercase();
+    !message.contains("rate limit")
+        && RANGE_LIMIT_ERRORS
+            .iter()
+            .any(|pattern| message.contains(pattern))
+}
+
+// 待查询的区块范围：超限时拆半重试，成功后前进并逐步放大跨度
+struct BlockRanges {
+    // 下一段的起始区块，全部查完后为 None；结束区块可能是 u64::MAX，不能用 start > to_block 判断
+    start: Option<u64>,
+    to_block: u64,
+    range: u64,
+}
+
+impl BlockRanges {
+    fn new(from_block: u64, to_block: u64) -> Self {
+        BlockRanges {
+            start: (from_block <= to_block).then_some(from_block),
+            to_block,
+            range: INITIAL_BLOCK_RANGE,
+        }
+    }
+
+    // 下一段查询的起止区块，全部查完时返回 None
+    fn next(&self) -> Option<(u64, u64)> {
+        let start = self.start?;
+        let end = start.saturating_add(self.range - 1).min(self.to_block);
+        Some((start, end))
+    }
+
+    // 查询失败后拆分范围；已经只剩单个区块时返回 false
+    fn split(&mut self) -> bool {
+        let Some((start, end)) = self.next() else {
+            return false;
+        };
+        if end == start {
+            return false;
+        }
+        self.range = (end - start + 1) / 2;
+        true
+    }
+
+    fn advance(&mut self) {
+        if let Some((_, end)) = self.next() {
+            self.start = if end < self.to_block {
+                Some(end + 1)
+            } else {
+                None
+            };
+            self.range = (self.range * 2).min(MAX_BLOCK_RANGE);
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ScanStats {
+    pub rewards_found: usize,
+    pub rewards_imported: usize,
+    pub tasks_found: usize,
+    pub tasks_imported: usize,
+}
+
+// 从链上日志回填奖励账本，范围过大或结果过多时自动拆分区块范围，其他错误直接返回；
+// 调用前应先用 chain_guard::verify 确认节点所在的链与合约
+pub async fn scan_history<M: Middleware>(
+    provider: &M,
+    ledger: &RewardLedger,
+    contract: Address,
+    wallets: &[Address],
+    from_block: u64,
+    to_block: Option<u64>,
+) -> Result<ScanStats> {
+    let to_block = match to_block {
+        Some(block) => block,
+        None => provider
+            .get_block_number()
+            .await
+            .map_err(|e| anyhow!("无法获取最新区块 / Could not get latest block: {}", e))?
+            .as_u64(),
+    };
+    if from_block > to_block {
+        return Err(anyhow!(
+            "起始区块大于结束区块 / Start block {} is after end block {}",
+            from_block,
+            to_block
+        ));
+    }
+
+    let pb = ProgressBar::new((to_block - from_block).saturating_add(1));
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template(
+                "扫描区块 / Scanning: [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} {msg}",
+            )
+            .unwrap()
+            .progress_chars("#>-"),
+    );
+
+    let users: Vec<Option<H256>> = wallets.iter().map(|w| Some(H256::from(*w))).collect();
+    let base_filter = Filter::new()
+        .address(contract)
+        .topic0(ValueOrArray::Array(vec![
+            Some(MiningRewardFilter::signature()),
+            Some(NewMiningTaskFilter::signature()),
+        ]))
+        .topic1(ValueOrArray::Array(users));
+
+    let mut stats = ScanStats::default();
+    let mut timestamps: HashMap<u64, i64> = HashMap::new();
+    let mut ranges = BlockRanges::new(from_block, to_block);
+    let mut single_block_failures = 0;
+
+    while let Some((start, end)) = ranges.next() {
+        let filter = base_filter.clone().from_block(start).to_block(end);
+
+        let logs = match provider.get_logs(&filter).await {
+            Ok(logs) => logs,
+            Err(e) if !is_range_limit_error(&e.to_string()) => {
+                pb.abandon();
+                return Err(anyhow!(
+                    "区块 {}-{} 的日志查询失败 / Log query for blocks {}-{} failed: {}",
+                    start,
+                    end,
+                    start,
+                    end,
+                    e
+                ));
+            }
+            Err(e) => {
+                // 多数节点会限制区块跨度或结果数量，拆分后重试
+                if ranges.split() {
+                    pb.set_message(format!(
+                        "拆分范围 / Splitting range to {} blocks",
+                        ranges.range
+                    ));
+                    continue;
+                }
+                single_block_failures += 1;
+                if single_block_failures >= MAX_SINGLE_BLOCK_RETRIES {
+                    pb.abandon();
+                    return Err(anyhow!(
+                        "区块 {} 的日志查询失败 / Log query for block {} failed: {}",
+                        start,
+                        start,
+                        e
+                    ));
+                }
+                sleep(Duration::from_secs(2)).await;
+                continue;
+            }
+        };
+        single_block_failures = 0;
+
+        for log in logs {
+            import_log(provider, ledger, &mut timestamps, &mut stats, log).await?;
+        }
+
+        pb.inc(end - start + 1);
+        pb.set_message(format!(
+            "奖励 / rewards: {}, 任务 / tasks: {}",
+            stats.rewards_found, stats.tasks_found
+        ));
+        ranges.advance();
+    }
+
+    pb.finish_and_clear();
+    console_println!(
+        "{}",
+        format!(
+            "扫描完成：区块 {}-{}，奖励 {} 条（新增 {}），任务 {} 条（新增 {}） / Scan finished: blocks {}-{}, {} rewards ({} new), {} tasks ({} new)",
+            from_block,
+            to_block,
+            stats.rewards_found,
+            stats.rewards_imported,
+            stats.tasks_found,
+            stats.tasks_imported,
+            from_block,
+            to_block,
+            stats.rewards_found,
+            stats.rewards_imported,
+            stats.tasks_found,
+            stats.tasks_imported
+        )
+        .green()
+    );
+
+    Ok(stats)
+}
+
+async fn import_log<M: Middleware>(
+    provider: &M,
+    ledger: &RewardLedger,
+    timestamps: &mut HashMap<u64, i64>,
+    stats: &mut ScanStats,
+    log: Log,
+) -> Result<()> {
+    let (Some(tx_hash), Some(block_number)) = (log.transaction_hash, log.block_number) else {
+        // 未确认的日志没有交易哈希和区块号
+        return Ok(());
+    };
+    let block_number = block_number.as_u64();
+    let log_index = log.log_index.unwrap_or_default().as_u64();
+    let timestamp = block_timestamp(provider, timestamps, block_number).await?;
+
+    match parse_log::<MiningContractEvents>(log)? {
+        MiningContractEvents::MiningRewardFilter(event) => {
+            stats.rewards_found += 1;
+
+            // 只能取得提交交易本身的手续费，挖矿时记录的整个任务手续费优先
+            let receipt = provider
+                .get_transaction_receipt(tx_hash)
+                .await
+                .map_err(|e| {
+                    anyhow!(
+                        "无法获取交易收据 / Could not get receipt {:?}: {}",
+                        tx_hash,
+                        e
+                    )
+                })?;
+            let record = RewardRecord {
+                tx_hash,
+                log_index,
+                wallet: event.user,
+                block_number,
+                timestamp,
+                reward: event.reward,
+                gas_used: receipt.as_ref().and_then(|r| r.gas_used),
+                effective_gas_price: receipt.as_ref().and_then(|r| r.effective_gas_price),
+                fee: receipt.as_ref().map(receipt_fee),
+            };
+            if ledger.import(&record)? {
+                stats.rewards_imported += 1;
+            }
+        }
+        MiningContractEvents::NewMiningTaskFilter(event) => {
+            stats.tasks_found += 1;
+
+            let record = TaskRecord {
+                tx_hash,
+                log_index,
+                wallet: event.user,
+                block_number,
+                timestamp,
+                difficulty: event.difficulty,
+            };
+            if ledger.record_task(&record)? {
+                stats.tasks_imported += 1;
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+async fn block_timestamp<M: Middleware>(
+    provider: &M,
+    cache: &mut HashMap<u64, i64>,
+    block_number: u64,
+) -> Result<i64> {
+    if let Some(timestamp) = cache.get(&block_number) {
+        return Ok(*timestamp);
+    }
+
+    let block = provider
+        .get_block(block_number)
+        .await
+        .map_err(|e| anyhow!("无法获取区块 / Could not get block {}: {}", block_number, e))?
+        .ok_or_else(|| anyhow!("区块不存在 / Block {} not found", block_number))?;
+    let timestamp = block.timestamp.as_u64() as i64;
+    cache.insert(block_number, timestamp);
+    Ok(timestamp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_rpc::{MockRpc, Reply};
+    use ethers::abi::{encode, Token};
+    use serde_json::{json, Value};
+    use std::path::Path;
+
+    // 节点允许的最大查询跨度
+    const NODE_MAX_RANGE: u64 = 100;
+
+    fn wallet() -> Address {
+        Address::repeat_byte(0x77)
+    }
+
+    fn contract() -> Address {
+        Address::repeat_byte(0x51)
+    }
+
+    fn event_log(signature: H256, block: u64, value: u64) -> Log {
+        Log {
+            address: contract(),
+            topics: vec![signature, H256::from(wallet())],
+            data: encode(&[Token::Uint(U256::from(value))]).into(),
+            block_number: Some(block.into()),
+            transaction_hash: Some(H256::from_low_u64_be(block)),
+            log_index: Some(U256::zero()),
+            ..Default::default()
+        }
+    }
+
+    fn hex_block(value: &Value) -> u64 {
+        u64::from_str_radix(value.as_str().unwrap().trim_start_matches("0x"), 16).unwrap()
+    }
+
+    // 链上有三条奖励与一条任务日志，跨度超过 NODE_MAX_RANGE 的查询被拒绝
+    async fn history_node() -> MockRpc {
+        let logs = [
+            event_log(NewMiningTaskFilter::signature(), 3, 10),
+            event_log(MiningRewardFilter::signature(), 5, 1_000),
+            event_log(MiningRewardFilter::signature(), 250, 2_000),
+            event_log(MiningRewardFilter::signature(), 900, 3_000),
+        ];
+        MockRpc::start(move |method, params: &Value| match method {
+            "eth_getLogs" => {
+                let from = hex_block(&params[0]["fromBlock"]);
+                let to = hex_block(&params[0]["toBlock"]);
+                if to - from + 1 > NODE_MAX_RANGE {
+                    return Reply::Error(-32005, "query returned more than 10000 results".into());
+                }
+                let matching: Vec<&Log> = logs
+                    .iter()
+                    .filter(|log| (from..=to).contains(&log.block_number.unwrap().as_u64()))
+                    .collect();
+                Reply::Result(json!(matching))
+            }
+            "eth_getBlockByNumber" => {
+                let number = hex_block(&params[0]);
+                Reply::Result(json!(Block::<H256> {
+                    number: Some(number.into()),
+                    timestamp: U256::from(1_700_000_000 + number * 2),
+                    ..Default::default()
+                }))
+            }
+            "eth_getTransactionReceipt" => Reply::Result(json!(TransactionReceipt {
+                transaction_hash: serde_json::from_value(params[0].clone()).unwrap(),
+                gas_used: Some(U256::from(21_000)),
+                effective_gas_price: Some(U256::from(7)),
+                ..Default::default()
+            })),
+            _ => Reply::Error(-32601, "method not found".to_string()),
+        })
+        .await
+    }
+
+    #[test]
+    fn only_limit_errors_split_the_range() {
+        assert!(is_range_limit_error(
+            "(code: -32005, message: query returned more than 10000 results, data: None)"
+        ));
+        assert!(is_range_limit_error("eth_getLogs block range is too large"));
+        assert!(is_range_limit_error("Log response size exceeded."));
+        assert!(is_range_limit_error("exceed maximum block range: 5000"));
+        assert!(!is_range_limit_error("401 Unauthorized"));
+        assert!(!is_range_limit_error(
+            "Your app has exceeded its compute units per second capacity"
+        ));
+        assert!(!is_range_limit_error("project ID request rate exceeded"));
+        assert!(!is_range_limit_error("too many requests"));
+        assert!(!is_range_limit_error(
+            "daily request count exceeded, request rate limited"
+        ));
+        assert!(!is_range_limit_error(
+            "error sending request: connection refused"
+        ));
+        assert!(!is_range_limit_error("rate limit exceeded"));
+    }
+
+    #[tokio::test]
+    async fn rescanning_the_same_range_is_idempotent() {
+        let node = history_node().await;
+        let provider = Provider::<Http>::try_from(node.url.as_str()).unwrap();
+        let ledger = RewardLedger::open(Path::new(":memory:")).unwrap();
+
+        let first = scan_history(&provider, &ledger, contract(), &[wallet()], 0, Some(1_000))
+            .await
+            .unwrap();
+        assert_eq!(first.rewards_found, 3);
+        assert_eq!(first.rewards_imported, 3);
+        assert_eq!(first.tasks_imported, 1);
+        let rows = format!("{:?}", ledger.records(None).unwrap());
+
+        let second = scan_history(&provider, &ledger, contract(), &[wallet()], 0, Some(1_000))
+            .await
+            .unwrap();
+        assert_eq!(second.rewards_found, 3);
+        assert_eq!(second.rewards_imported, 0);
+        assert_eq!(second.tasks_imported, 0);
+        assert_eq!(format!("{:?}", ledger.records(None).unwrap()), rows);
+
+        let records = ledger.records(None).unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[1].block_number, 250);
+        assert_eq!(records[1].timestamp, 1_700_000_500);
+        assert_eq!(records[1].fee, Some(U256::from(147_000)));
+    }
+
+    #[tokio::test]
+    async fn other_errors_are_returned_without_splitting() {
+        let node = MockRpc::start(|_, _| Reply::Error(-32000, "invalid API key".to_string())).await;
+        let provider = Provider::<Http>::try_from(node.url.as_str()).unwrap();
+        let ledger = RewardLedger::open(Path::new(":memory:")).unwrap();
+
+        let error = scan_history(&provider, &ledger, contract(), &[wallet()], 0, Some(1_000))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("invalid API key"));
+        assert_eq!(node.calls("eth_getLogs").len(), 1);
+    }
+
+    #[test]
+    fn split_halves_until_single_block() {
+        let mut ranges = BlockRanges::new(10, 1_000_000);
+        assert_eq!(ranges.next(), Some((10, 10 + INITIAL_BLOCK_RANGE - 1)));
+
+        let mut spans = Vec::new();
+        while ranges.split() {
+            let (start, end) = ranges.next().unwrap();
+            assert_eq!(start, 10);
+            spans.push(end - start + 1);
+        }
+        assert_eq!(spans.first(), Some(&(INITIAL_BLOCK_RANGE / 2)));
+        assert!(spans.windows(2).all(|w| w[1] < w[0]));
+        assert_eq!(ranges.next(), Some((10, 10)));
+    }
+
+    #[test]
+    fn split_keeps_lower_half_of_odd_spans() {
+        let mut ranges = BlockRanges::new(0, 2);
+        assert_eq!(ranges.next(), Some((0, 2)));
+        assert!(ranges.split());
+        assert_eq!(ranges.next(), Some((0, 0)));
+    }
+
+    #[test]
+    fn advance_grows_range_up_to_max() {
+        let mut ranges = BlockRanges::new(0, u64::MAX / 2);
+        let mut last = 0;
+        for _ in 0..10 {
+            let (start, end) = ranges.next().unwrap();
+            last = end - start + 1;
+            ranges.advance();
+        }
+        assert_eq!(last, MAX_BLOCK_RANGE);
+    }
+
+    #[test]
+    fn ranges_cover_every_block_once() {
+        let mut ranges = BlockRanges::new(5, 123_456);
+        let mut expected = 5;
+        let mut round = 0;
+        while let Some((start, end)) = ranges.next() {
+            assert_eq!(start, expected);
+            assert!(end <= 123_456);
+            // 模拟节点时常拒绝过大的范围
+            if round % 3 == 0 && ranges.split() {
+                round += 1;
+                continue;
+            }
+            round += 1;
+            expected = end + 1;
+            ranges.advance();
+        }
+        assert_eq!(expected, 123_457);
+    }
+
+    #[test]
+    fn ranges_end_at_the_last_possible_block() {
+        let mut ranges = BlockRanges::new(u64::MAX - 3, u64::MAX);
+        assert_eq!(ranges.next(), Some((u64::MAX - 3, u64::MAX)));
+        ranges.advance();
+        assert_eq!(ranges.next(), None);
+
+        let ranges = BlockRanges::new(0, u64::MAX);
+        assert_eq!(ranges.next(), Some((0, INITIAL_BLOCK_RANGE - 1)));
+    }
+
+    #[tokio::test]
+    async fn scan_up_to_the_last_possible_block_finishes() {
+        let node = history_node().await;
+        let provider = Provider::<Http>::try_from(node.url.as_str()).unwrap();
+        let ledger = RewardLedger::open(Path::new(":memory:")).unwrap();
+
+        let stats = tokio::time::timeout(
+            Duration::from_secs(5),
+            scan_history(
+                &provider,
+                &ledger,
+                contract(),
+                &[wallet()],
+                u64::MAX - 5,
+                Some(u64::MAX),
+            ),
+        )
+        .await
+        .expect("scan did not finish")
+        .unwrap();
+        assert_eq!(stats.rewards_found, 0);
+        assert_eq!(node.calls("eth_getLogs").len(), 1);
+    }
+
+    #[test]
+    fn empty_when_start_after_end() {
+        let mut ranges = BlockRanges::new(10, 9);
+        assert_eq!(ranges.next(), None);
+        assert!(!ranges.split());
+    }
+}
@@ -0,0 +1,210 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{net::SocketAddr, sync::Arc};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::{
+    http_util::{bearer_matches, read_head, respond, spawn_server},
+    miner_control::{ControlState, Controller, MinerControl},
+    rpc_config::redact_urls,
+    tui_monitor::{LogLine, MonitorData, MonitorSnapshot},
+};
+
+// 状态中附带的最近日志行数
+const STATUS_LOG_LINES: usize = 50;
+
+// GET /status 返回的挖矿进程状态，fleet 汇总多台机器时读取
+#[derive(Serialize, Deserialize)]
+pub struct MinerStatus {
+    pub state: ControlState,
+    pub data: MonitorSnapshot,
+    pub logs: Vec<LogLine>,
+}
+
+impl MinerStatus {
+    pub fn collect(data: &MonitorData, control: &MinerControl) -> Self {
+        let mut logs = data.logs_after(None);
+        logs.drain(..logs.len().saturating_sub(STATUS_LOG_LINES));
+        MinerStatus {
+            state: control.state(),
+            data: data.snapshot(),
+            logs,
+        }
+    }
+
+    // 对外输出的 JSON：节点地址、错误与日志中的 URL 去掉用户名、密码与查询参数
+    pub fn to_json(&self) -> serde_json::Result<Vec<u8>> {
+        redacted_json(self)
+    }
+}
+
+// 序列化后去掉所有字符串中 URL 的用户名、密码与查询参数
+pub fn redacted_json<T: Serialize + ?Sized>(value: &T) -> serde_json::Result<Vec<u8>> {
+    let mut value = serde_json::to_value(value)?;
+    redact_strings(&mut value);
+    serde_json::to_vec(&value)
+}
+
+fn redact_strings(value: &mut Value) {
+    match value {
+        Value::String(text) => *text = redact_urls(text),
+        Value::Array(items) => items.iter_mut().for_each(redact_strings),
+        Value::Object(fields) => fields.values_mut().for_each(redact_strings),
+        _ => {}
+    }
+}
+
+// 只读的 HTTP 状态接口，只提供 GET /status，不能操作挖矿；
+// 设置 token 时请求需带 `Authorization: Bearer <令牌>`，只有设置了令牌才能监听非本机地址
+pub async fn spawn(
+    addr: SocketAddr,
+    token: Option<String>,
+    data: Arc<MonitorData>,
+    control: Arc<MinerControl>,
+) -> Result<SocketAddr> {
+    if !addr.ip().is_loopback() && token.is_none() {
+        return Err(anyhow!(
+            "状态接口监听非本机地址时需设置 --status-token / The status endpoint needs --status-token to listen on a non-loopback address: {}",
+            addr
+        ));
+    }
+    let listener = TcpListener::bind(addr).await.map_err(|e| {
+        anyhow!(
+            "无法监听状态接口地址 / Cannot listen on status address {}: {}",
+            addr,
+            e
+        )
+    })?;
+    let local = listener.local_addr()?;
+
+    let token = Arc::new(token);
+    spawn_server(
+        listener,
+        "状态接口请求失败 / Status request failed",
+        move |client| {
+            let token = token.clone();
+            let data = data.clone();
+            let control = control.clone();
+            async move { serve(client, token.as_deref(), &data, &control).await }
+        },
+    );
+
+    Ok(local)
+}
+
+async fn serve(
+    mut client: TcpStream,
+    token: Option<&str>,
+    data: &MonitorData,
+    control: &MinerControl,
+) -> Result<()> {
+    let head = read_head(&mut client).await?;
+    let request_line = head.lines().next().unwrap_or_default();
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = (parts.next(), parts.next());
+
+    if let Some(token) = token {
+        if !bearer_matches(&head, token) {
+            return respond(
+                &mut client,
+                "401 Unauthorized",
+                br#"{"error":"invalid or missing token"}"#,
+            )
+            .await;
+        }
+    }
+
+    let (status, body) = match (method, path) {
+        (Some("GET"), Some("/status")) => {
+            ("200 OK", MinerStatus::collect(data, control).to_json()?)
+        }
+        (Some("GET"), _) => ("404 Not Found", br#"{"error":"not found"}"#.to_vec()),
+        _ => (
+            "405 Method Not Allowed",
+            br#"{"error":"method not allowed"}"#.to_vec(),
+        ),
+    };
+    respond(&mut client, status, &body).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        mock_rpc::{idle_control, MockRpc, Reply},
+        rpc_config::EndpointConfig,
+        rpc_pool::RpcPool,
+        tui_monitor::LogLevel,
+    };
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    // 发送一个请求，返回完整响应
+    async fn get(addr: SocketAddr, headers: &str) -> String {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(format!("GET /status HTTP/1.1\r\n{}\r\n", headers).as_bytes())
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[tokio::test]
+    async fn public_address_needs_a_token() {
+        let data = Arc::new(MonitorData::new());
+        let control = idle_control().await;
+
+        let public: SocketAddr = "0.0.0.0:0".parse().unwrap();
+        assert!(spawn(public, None, data.clone(), control.clone())
+            .await
+            .is_err());
+        assert!(spawn(public, Some("token".to_string()), data, control)
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn token_is_required_when_set() {
+        let addr = spawn(
+            "127.0.0.1:0".parse().unwrap(),
+            Some("secret-token".to_string()),
+            Arc::new(MonitorData::new()),
+            idle_control().await,
+        )
+        .await
+        .unwrap();
+
+        assert!(get(addr, "").await.starts_with("HTTP/1.1 401"));
+        assert!(get(addr, "Authorization: Bearer wrong-token!\r\n")
+            .await
+            .starts_with("HTTP/1.1 401"));
+        assert!(get(addr, "Authorization: Bearer secret-token\r\n")
+            .await
+            .starts_with("HTTP/1.1 200"));
+    }
+
+    #[tokio::test]
+    async fn status_hides_url_credentials() {
+        let node = MockRpc::start(|_, _| Reply::Result(Value::Null)).await;
+        let url = node.url.replace("http://", "http://miner:hunter2@") + "/?apikey=hunter2";
+        let data = Arc::new(MonitorData::new());
+        let control = idle_control().await;
+        data.update_rpc_status(
+            RpcPool::connect(&[EndpointConfig::from_url(&url)])
+                .await
+                .unwrap()
+                .status(),
+        );
+        data.push_log(format!("RPC 节点 {} 失败", url), LogLevel::Warn);
+        let addr = spawn("127.0.0.1:0".parse().unwrap(), None, data, control)
+            .await
+            .unwrap();
+
+        let response = get(addr, "").await;
+        assert!(response.starts_with("HTTP/1.1 200"));
+        assert!(response.contains("http://***@127.0.0.1"));
+        assert!(!response.contains("hunter2"));
+    }
+}
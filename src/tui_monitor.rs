@@ -1,14 +1,22 @@
+use crate::{
+    miner_control::{Controller, MinerCommand},
+    resources::{ResourceSampler, ResourceSnapshot},
+    rpc_pool::EndpointStatus,
+};
 use chrono::{DateTime, Local};
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyModifiers,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use std::collections::VecDeque;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
 use std::{
-    io,
+    io::{self, Write},
     sync::{
-        atomic::{AtomicUsize, Ordering},
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
         Arc, Mutex,
     },
     time::{Duration, Instant},
@@ -17,36 +25,225 @@ use tui::{
     backend::{Backend, CrosstermBackend},
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
-    widgets::{Block, Borders, List, ListItem, Paragraph},
+    symbols,
+    text::Span,
+    widgets::{
+        Axis, BarChart, Block, Borders, Chart, Clear, Dataset, Gauge, GraphType, List, ListItem,
+        ListState, Paragraph, Row, Sparkline, Table, TableState, Wrap,
+    },
     Frame, Terminal,
 };
 
+// 保留的日志行数
+const MAX_LOG_LINES: usize = 2000;
+
+// 算力曲线保留的秒数
+const HASHRATE_SAMPLES: usize = 300;
+
+// 图表默认显示的历史时长（分钟）
+pub const DEFAULT_CHART_MINUTES: u64 = 30;
+
+// 默认保留的已结束任务数，更早的任务归档为汇总
+pub const DEFAULT_TASK_HISTORY: usize = 200;
+
+// 每个任务保留的时间线条目数
+const MAX_TIMELINE_EVENTS: usize = 50;
+
+// 翻页时移动的行数
+const PAGE_ROWS: isize = 10;
+
+// 帮助窗口中列出的快捷键
+const HELP_LINES: [(&str, &str); 14] = [
+    ("p", "暂停/继续请求新任务 / Pause or resume new tasks"),
+    ("+ / -", "增减哈希线程 / More or fewer hashing threads"),
+    ("] / [", "增减并行任务 / More or fewer in-flight tasks"),
+    (
+        "Tab",
+        "切换任务列表/RPC节点/日志 / Switch between tasks, RPC endpoints and log",
+    ),
+    ("↑↓ PgUp PgDn", "选择与翻页 / Move selection and scroll"),
+    ("x", "取消选中的任务 / Cancel the selected task"),
+    ("s", "优先使用选中的节点 / Prefer the selected endpoint"),
+    (
+        "d",
+        "停用/启用选中的节点 / Disable or enable the selected endpoint",
+    ),
+    ("b", "立即刷新余额 / Refresh balances now"),
+    (
+        "1 / 2 / 3",
+        "显示/隐藏 信息/警告/错误 日志 / Toggle info, warn, error log lines",
+    ),
+    (
+        "/",
+        "搜索日志，Enter 确认，Esc 清除 / Search the log, Enter applies, Esc clears",
+    ),
+    (
+        "空格 / Space",
+        "暂停/恢复日志滚动 / Pause or resume log scrolling",
+    ),
+    ("w", "把日志缓冲写入文件 / Dump the log buffer to a file"),
+    ("h / ?", "显示/关闭帮助 / Toggle this help"),
+];
+
 // 监控数据结构
 pub struct MonitorData {
     pub online_tasks: AtomicUsize,
     pub processing_tasks: AtomicUsize,
     pub completed_tasks: AtomicUsize,
     pub wallet_balance: Mutex<f64>,
+    pub contract_balance: Mutex<f64>,
     pub balance_history: Mutex<VecDeque<(DateTime<Local>, f64)>>,
+    // 本次运行的累计奖励（MAG）
+    pub reward_history: Mutex<VecDeque<(DateTime<Local>, f64)>>,
+    pub total_rewards: Mutex<f64>,
+    // 最近一次获得奖励的时间，不随图表窗口清理
+    pub last_reward: Mutex<Option<DateTime<Local>>>,
+    // 本次运行开始时的钱包余额，用于计算余额变化
+    pub initial_balance: Mutex<Option<f64>>,
+    // 余额与奖励图表的时间窗口
+    pub chart_window: Mutex<chrono::Duration>,
+    // 每秒一次的总算力采样（次/秒）
+    pub hashrate_history: Mutex<VecDeque<u64>>,
     pub task_progresses: Mutex<Vec<TaskProgress>>,
+    // 最多保留的已结束任务数
+    pub task_history: AtomicUsize,
+    // 超出保留数被移出列表的任务汇总
+    pub archive: Mutex<TaskArchive>,
+    pub rpc_status: Mutex<Vec<EndpointStatus>>,
+    // 各任务当前的哈希速率（次/秒）
+    pub hashrates: Mutex<HashMap<usize, f64>>,
+    pub logs: Mutex<VecDeque<LogLine>>,
+    next_log_seq: AtomicU64,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LogLine {
+    // 递增序号，日志暂停滚动时用来固定位置
+    pub seq: u64,
+    pub time: DateTime<Local>,
+    pub level: LogLevel,
+    pub text: String,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum LogLevel {
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            LogLevel::Info => "INFO",
+            LogLevel::Warn => "WARN",
+            LogLevel::Error => "ERROR",
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            LogLevel::Info => Color::Gray,
+            LogLevel::Warn => Color::Yellow,
+            LogLevel::Error => Color::Red,
+        }
+    }
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct TaskProgress {
     pub id: usize,
+    pub wallet: String,
     pub progress: f64,
     pub status: TaskStatus,
+    pub difficulty: Option<String>,
     pub start_time: DateTime<Local>,
     pub end_time: Option<DateTime<Local>>,
+    pub last_error: Option<String>,
+    // 已计算与预计需要的哈希次数
+    pub hashes: u64,
+    pub expected_hashes: f64,
+    pub tx_hashes: Vec<String>,
+    pub reward: Option<f64>,
+    // 任务经历的各个步骤
+    pub timeline: VecDeque<(DateTime<Local>, String)>,
 }
 
-#[derive(PartialEq)]
+impl TaskProgress {
+    pub fn is_finished(&self) -> bool {
+        matches!(self.status, TaskStatus::Completed | TaskStatus::Failed)
+    }
+
+    pub fn elapsed(&self) -> chrono::Duration {
+        self.end_time.unwrap_or_else(Local::now) - self.start_time
+    }
+
+    fn push_event(&mut self, text: String) {
+        if self.timeline.len() >= MAX_TIMELINE_EVENTS {
+            self.timeline.pop_front();
+        }
+        self.timeline.push_back((Local::now(), text));
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum TaskStatus {
-    Waiting,
-    Processing,
+    // 发送请求任务交易并等待确认
+    Requesting,
+    Hashing,
+    // 提交结果交易并等待确认
+    Submitting,
     Completed,
     Failed,
 }
 
+impl TaskStatus {
+    pub fn label(self) -> (&'static str, Color) {
+        match self {
+            TaskStatus::Requesting => ("请求任务", Color::Yellow),
+            TaskStatus::Hashing => ("计算中", Color::Blue),
+            TaskStatus::Submitting => ("提交中", Color::Magenta),
+            TaskStatus::Completed => ("已完成", Color::Green),
+            TaskStatus::Failed => ("失败", Color::Red),
+        }
+    }
+}
+
+// 已归档任务的汇总
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct TaskArchive {
+    pub completed: usize,
+    pub failed: usize,
+    pub rewards: f64,
+}
+
+// 监控数据的完整副本（日志除外），经监控套接字发送给 attach 的界面
+#[derive(Serialize, Deserialize)]
+pub struct MonitorSnapshot {
+    pub online_tasks: usize,
+    pub processing_tasks: usize,
+    pub completed_tasks: usize,
+    pub wallet_balance: f64,
+    pub contract_balance: f64,
+    pub balance_history: VecDeque<(DateTime<Local>, f64)>,
+    pub reward_history: VecDeque<(DateTime<Local>, f64)>,
+    pub total_rewards: f64,
+    pub last_reward: Option<DateTime<Local>>,
+    pub initial_balance: Option<f64>,
+    pub chart_minutes: i64,
+    pub hashrate_history: VecDeque<u64>,
+    pub task_progresses: Vec<TaskProgress>,
+    pub task_history: usize,
+    pub archive: TaskArchive,
+    pub rpc_status: Vec<EndpointStatus>,
+    // JSON 对象的键是字符串，按列表传输
+    pub hashrates: Vec<(usize, f64)>,
+}
+
 impl Default for MonitorData {
     fn default() -> Self {
         MonitorData {
@@ -54,8 +251,21 @@ impl Default for MonitorData {
             processing_tasks: AtomicUsize::new(0),
             completed_tasks: AtomicUsize::new(0),
             wallet_balance: Mutex::new(0.0),
-            balance_history: Mutex::new(VecDeque::with_capacity(10)),
+            contract_balance: Mutex::new(0.0),
+            balance_history: Mutex::new(VecDeque::new()),
+            reward_history: Mutex::new(VecDeque::new()),
+            total_rewards: Mutex::new(0.0),
+            last_reward: Mutex::new(None),
+            initial_balance: Mutex::new(None),
+            chart_window: Mutex::new(chrono::Duration::minutes(DEFAULT_CHART_MINUTES as i64)),
+            hashrate_history: Mutex::new(VecDeque::with_capacity(HASHRATE_SAMPLES)),
             task_progresses: Mutex::new(Vec::new()),
+            task_history: AtomicUsize::new(DEFAULT_TASK_HISTORY),
+            archive: Mutex::new(TaskArchive::default()),
+            rpc_status: Mutex::new(Vec::new()),
+            hashrates: Mutex::new(HashMap::new()),
+            logs: Mutex::new(VecDeque::with_capacity(MAX_LOG_LINES)),
+            next_log_seq: AtomicU64::new(0),
         }
     }
 }
@@ -65,67 +275,371 @@ impl MonitorData {
         Self::default()
     }
 
+    pub fn set_chart_minutes(&self, minutes: u64) {
+        *self.chart_window.lock().unwrap() = chrono::Duration::minutes(minutes.max(1) as i64);
+    }
+
     pub fn update_balance(&self, balance: f64) {
         let mut wallet_balance = self.wallet_balance.lock().unwrap();
         *wallet_balance = balance;
+        self.initial_balance.lock().unwrap().get_or_insert(balance);
 
         let mut history = self.balance_history.lock().unwrap();
         history.push_back((Local::now(), balance));
-        if history.len() > 10 {
+        self.prune(&mut history);
+    }
+
+    pub fn add_reward(&self, id: usize, reward: f64) {
+        self.with_task(id, |task| {
+            task.reward = Some(reward);
+            task.push_event(format!("获得奖励 {:.6} MAG", reward));
+        });
+
+        let mut total = self.total_rewards.lock().unwrap();
+        *total += reward;
+        *self.last_reward.lock().unwrap() = Some(Local::now());
+
+        let mut history = self.reward_history.lock().unwrap();
+        history.push_back((Local::now(), *total));
+        self.prune(&mut history);
+    }
+
+    // 丢弃图表窗口之外的样本，保留窗口前的最后一个作为曲线起点
+    fn prune(&self, history: &mut VecDeque<(DateTime<Local>, f64)>) {
+        let cutoff = Local::now() - *self.chart_window.lock().unwrap();
+        while history.len() > 1 && history[1].0 < cutoff {
             history.pop_front();
         }
     }
 
-    pub fn add_task(&self, id: usize) {
+    // 记录一次当前总算力
+    pub fn sample_hashrate(&self) {
+        let rate = self.total_hashrate() as u64;
+        let mut history = self.hashrate_history.lock().unwrap();
+        if history.len() >= HASHRATE_SAMPLES {
+            history.pop_front();
+        }
+        history.push_back(rate);
+    }
+
+    pub fn update_contract_balance(&self, balance: f64) {
+        *self.contract_balance.lock().unwrap() = balance;
+    }
+
+    pub fn update_hashrate(&self, id: usize, hashes_per_second: f64) {
+        self.hashrates.lock().unwrap().insert(id, hashes_per_second);
+    }
+
+    // 所有任务的哈希速率之和
+    pub fn total_hashrate(&self) -> f64 {
+        self.hashrates
+            .lock()
+            .unwrap()
+            .values()
+            .fold(0.0, |total, rate| total + rate)
+    }
+
+    pub fn push_log(&self, text: String, level: LogLevel) {
+        let mut logs = self.logs.lock().unwrap();
+        if logs.len() >= MAX_LOG_LINES {
+            logs.pop_front();
+        }
+        logs.push_back(LogLine {
+            seq: self.next_log_seq.fetch_add(1, Ordering::SeqCst),
+            time: Local::now(),
+            level,
+            text,
+        });
+    }
+
+    // 序号大于 after 的日志，None 时返回全部
+    pub fn logs_after(&self, after: Option<u64>) -> Vec<LogLine> {
+        self.logs
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|line| after.map_or(true, |seq| line.seq > seq))
+            .cloned()
+            .collect()
+    }
+
+    // 追加来自其他进程的日志，保留原时间与级别，按本地顺序重新编号
+    pub fn append_logs(&self, lines: Vec<LogLine>) {
+        let mut logs = self.logs.lock().unwrap();
+        for mut line in lines {
+            if logs.len() >= MAX_LOG_LINES {
+                logs.pop_front();
+            }
+            line.seq = self.next_log_seq.fetch_add(1, Ordering::SeqCst);
+            logs.push_back(line);
+        }
+    }
+
+    pub fn snapshot(&self) -> MonitorSnapshot {
+        MonitorSnapshot {
+            online_tasks: self.online_tasks.load(Ordering::SeqCst),
+            processing_tasks: self.processing_tasks.load(Ordering::SeqCst),
+            completed_tasks: self.completed_tasks.load(Ordering::SeqCst),
+            wallet_balance: *self.wallet_balance.lock().unwrap(),
+            contract_balance: *self.contract_balance.lock().unwrap(),
+            balance_history: self.balance_history.lock().unwrap().clone(),
+            reward_history: self.reward_history.lock().unwrap().clone(),
+            total_rewards: *self.total_rewards.lock().unwrap(),
+            last_reward: *self.last_reward.lock().unwrap(),
+            initial_balance: *self.initial_balance.lock().unwrap(),
+            chart_minutes: self.chart_window.lock().unwrap().num_minutes(),
+            hashrate_history: self.hashrate_history.lock().unwrap().clone(),
+            task_progresses: self.task_progresses.lock().unwrap().clone(),
+            task_history: self.task_history.load(Ordering::SeqCst),
+            archive: self.archive.lock().unwrap().clone(),
+            rpc_status: self.rpc_status.lock().unwrap().clone(),
+            hashrates: self
+                .hashrates
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(&id, &rate)| (id, rate))
+                .collect(),
+        }
+    }
+
+    // 用挖矿进程发来的副本替换本地数据
+    pub fn restore(&self, snapshot: MonitorSnapshot) {
+        self.online_tasks
+            .store(snapshot.online_tasks, Ordering::SeqCst);
+        self.processing_tasks
+            .store(snapshot.processing_tasks, Ordering::SeqCst);
+        self.completed_tasks
+            .store(snapshot.completed_tasks, Ordering::SeqCst);
+        *self.wallet_balance.lock().unwrap() = snapshot.wallet_balance;
+        *self.contract_balance.lock().unwrap() = snapshot.contract_balance;
+        *self.balance_history.lock().unwrap() = snapshot.balance_history;
+        *self.reward_history.lock().unwrap() = snapshot.reward_history;
+        *self.total_rewards.lock().unwrap() = snapshot.total_rewards;
+        *self.last_reward.lock().unwrap() = snapshot.last_reward;
+        *self.initial_balance.lock().unwrap() = snapshot.initial_balance;
+        *self.chart_window.lock().unwrap() = chrono::Duration::minutes(snapshot.chart_minutes);
+        *self.hashrate_history.lock().unwrap() = snapshot.hashrate_history;
+        *self.task_progresses.lock().unwrap() = snapshot.task_progresses;
+        self.task_history
+            .store(snapshot.task_history, Ordering::SeqCst);
+        *self.archive.lock().unwrap() = snapshot.archive;
+        *self.rpc_status.lock().unwrap() = snapshot.rpc_status;
+        *self.hashrates.lock().unwrap() = snapshot.hashrates.into_iter().collect();
+    }
+
+    // 把日志缓冲完整写入文件
+    pub fn dump_logs(&self, path: &std::path::Path) -> io::Result<usize> {
+        let logs = self.logs.lock().unwrap();
+        let mut file = io::BufWriter::new(std::fs::File::create(path)?);
+        for line in logs.iter() {
+            writeln!(
+                file,
+                "{} {:<5} {}",
+                line.time.format("%Y-%m-%d %H:%M:%S"),
+                line.level.label(),
+                line.text
+            )?;
+        }
+        file.flush()?;
+        Ok(logs.len())
+    }
+
+    pub fn update_rpc_status(&self, status: Vec<EndpointStatus>) {
+        *self.rpc_status.lock().unwrap() = status;
+    }
+
+    pub fn set_task_history(&self, limit: usize) {
+        self.task_history.store(limit, Ordering::SeqCst);
+    }
+
+    fn with_task(&self, id: usize, update: impl FnOnce(&mut TaskProgress)) {
         let mut tasks = self.task_progresses.lock().unwrap();
-        tasks.push(TaskProgress {
+        if let Some(task) = tasks.iter_mut().find(|t| t.id == id) {
+            update(task);
+        }
+    }
+
+    pub fn add_task(&self, id: usize, wallet: String) {
+        let mut task = TaskProgress {
             id,
+            wallet,
             progress: 0.0,
-            status: TaskStatus::Waiting,
+            status: TaskStatus::Requesting,
+            difficulty: None,
             start_time: Local::now(),
             end_time: None,
-        });
+            last_error: None,
+            hashes: 0,
+            expected_hashes: 0.0,
+            tx_hashes: Vec::new(),
+            reward: None,
+            timeline: VecDeque::new(),
+        };
+        task.push_event("开始".to_string());
+        self.task_progresses.lock().unwrap().push(task);
         self.online_tasks.fetch_add(1, Ordering::SeqCst);
     }
 
+    pub fn task_event(&self, id: usize, text: String) {
+        self.with_task(id, |task| task.push_event(text));
+    }
+
+    pub fn set_task_difficulty(&self, id: usize, difficulty: String) {
+        self.with_task(id, |task| task.difficulty = Some(difficulty));
+    }
+
+    pub fn add_task_tx(&self, id: usize, tx_hash: String) {
+        self.with_task(id, |task| task.tx_hashes.push(tx_hash));
+    }
+
+    // 找到解后进入提交阶段
+    pub fn start_submitting(&self, id: usize) {
+        self.with_task(id, |task| {
+            if task.status == TaskStatus::Hashing {
+                task.status = TaskStatus::Submitting;
+            }
+        });
+    }
+
     pub fn update_task_progress(&self, id: usize, progress: f64) {
-        let mut tasks = self.task_progresses.lock().unwrap();
-        if let Some(task) = tasks.iter_mut().find(|t| t.id == id) {
+        self.with_task(id, |task| {
             task.progress = progress;
-            if task.status == TaskStatus::Waiting {
-                task.status = TaskStatus::Processing;
+            if task.status == TaskStatus::Requesting {
+                task.status = TaskStatus::Hashing;
                 self.processing_tasks.fetch_add(1, Ordering::SeqCst);
             }
-        }
+        });
+    }
+
+    pub fn update_task_hashes(&self, id: usize, hashes: u64, expected_hashes: f64) {
+        self.with_task(id, |task| {
+            task.hashes = hashes;
+            task.expected_hashes = expected_hashes;
+        });
+    }
+
+    pub fn set_task_error(&self, id: usize, error: String) {
+        self.with_task(id, |task| {
+            task.push_event(format!("错误: {}", error));
+            task.last_error = Some(error);
+        });
     }
 
     pub fn complete_task(&self, id: usize, success: bool) {
+        self.hashrates.lock().unwrap().remove(&id);
         let mut tasks = self.task_progresses.lock().unwrap();
         if let Some(task) = tasks.iter_mut().find(|t| t.id == id) {
+            if matches!(task.status, TaskStatus::Hashing | TaskStatus::Submitting) {
+                self.processing_tasks.fetch_sub(1, Ordering::SeqCst);
+            }
+            if !task.is_finished() {
+                self.online_tasks.fetch_sub(1, Ordering::SeqCst);
+            }
             task.status = if success {
                 TaskStatus::Completed
             } else {
                 TaskStatus::Failed
             };
             task.end_time = Some(Local::now());
+            task.push_event(if success { "完成" } else { "失败" }.to_string());
             if success {
                 self.completed_tasks.fetch_add(1, Ordering::SeqCst);
             }
-            self.processing_tasks.fetch_sub(1, Ordering::SeqCst);
         }
+        self.archive_finished(&mut tasks);
+    }
+
+    // 本次运行失败的任务数，包括已归档的任务
+    pub fn failed_tasks(&self) -> usize {
+        let tasks = self.task_progresses.lock().unwrap();
+        let failed = tasks
+            .iter()
+            .filter(|task| task.status == TaskStatus::Failed)
+            .count();
+        failed + self.archive.lock().unwrap().failed
+    }
+
+    // 已结束的任务超出保留数时，把最早开始的已结束任务移出列表并计入汇总
+    fn archive_finished(&self, tasks: &mut Vec<TaskProgress>) {
+        let limit = self.task_history.load(Ordering::SeqCst);
+        let finished = tasks.iter().filter(|t| t.is_finished()).count();
+        let mut excess = finished.saturating_sub(limit);
+        if excess == 0 {
+            return;
+        }
+
+        let mut archive = self.archive.lock().unwrap();
+        tasks.retain(|task| {
+            if excess == 0 || !task.is_finished() {
+                return true;
+            }
+            excess -= 1;
+            if task.status == TaskStatus::Completed {
+                archive.completed += 1;
+            } else {
+                archive.failed += 1;
+            }
+            archive.rewards += task.reward.unwrap_or(0.0);
+            false
+        });
     }
 }
 
+// 方向键操作的区域
+#[derive(Clone, Copy, PartialEq)]
+enum Focus {
+    Tasks,
+    Rpc,
+    Logs,
+}
+
 // TUI应用程序
 pub struct TuiApp {
     data: Arc<MonitorData>,
+    // 与挖矿循环共享的停止标志：界面退出时停止挖矿，挖矿结束时关闭界面
+    stop: Arc<AtomicBool>,
+    control: Arc<dyn Controller>,
+    focus: Focus,
+    // 按任务编号记录选中的任务，任务被归档后列表位置变化也不会选错
+    selected_task: Option<usize>,
+    task_table: TableState,
+    rpc_state: ListState,
+    // 按级别显示日志：信息、警告、错误
+    log_levels: [bool; 3],
+    // 正在输入的搜索词与已生效的搜索词
+    search_input: Option<String>,
+    search: Option<String>,
+    // 暂停滚动时最后一行可见日志的序号
+    log_anchor: Option<u64>,
+    resources: ResourceSampler,
+    resource_snapshot: Option<ResourceSnapshot>,
+    show_help: bool,
     should_quit: bool,
 }
 
 impl TuiApp {
-    pub fn new(data: Arc<MonitorData>) -> Self {
+    pub fn new(
+        data: Arc<MonitorData>,
+        stop: Arc<AtomicBool>,
+        control: Arc<dyn Controller>,
+        resources: ResourceSampler,
+    ) -> Self {
         TuiApp {
             data,
+            stop,
+            control,
+            focus: Focus::Tasks,
+            selected_task: None,
+            task_table: TableState::default(),
+            rpc_state: ListState::default(),
+            log_levels: [true; 3],
+            search_input: None,
+            search: None,
+            log_anchor: None,
+            resources,
+            resource_snapshot: None,
+            show_help: false,
             should_quit: false,
         }
     }
@@ -139,6 +653,7 @@ impl TuiApp {
 
         let tick_rate = Duration::from_millis(500); // 降低刷新频率
         let mut last_tick = Instant::now();
+        let mut last_sample = Instant::now();
 
         loop {
             terminal.draw(|f| self.ui(f))?;
@@ -149,21 +664,23 @@ impl TuiApp {
 
             if event::poll(timeout)? {
                 if let Event::Key(key) = event::read()? {
-                    match key.code {
-                        KeyCode::Char('q') | KeyCode::Esc => self.should_quit = true,
-                        _ => {}
-                    }
+                    self.handle_key(key);
                 }
             }
 
             if last_tick.elapsed() >= tick_rate {
                 last_tick = Instant::now();
             }
+            if last_sample.elapsed() >= Duration::from_secs(1) {
+                last_sample = Instant::now();
+                self.resource_snapshot = Some(self.resources.sample());
+            }
 
-            if self.should_quit {
+            if self.should_quit || self.stop.load(Ordering::SeqCst) {
                 break;
             }
         }
+        self.stop.store(true, Ordering::SeqCst);
 
         disable_raw_mode()?;
         execute!(
@@ -176,34 +693,498 @@ impl TuiApp {
         Ok(())
     }
 
-    fn ui<B: Backend>(&self, f: &mut Frame<B>) {
+    fn handle_key(&mut self, key: KeyEvent) {
+        // 帮助窗口打开时任意键关闭
+        if self.show_help {
+            self.show_help = false;
+            return;
+        }
+        if self.search_input.is_some() {
+            self.handle_search_key(key);
+            return;
+        }
+
+        match key.code {
+            // 原始模式下 Ctrl+C 不会产生信号
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.should_quit = true
+            }
+            // 有搜索条件时 Esc 先清除搜索
+            KeyCode::Esc if self.search.is_some() => self.search = None,
+            KeyCode::Char('q') | KeyCode::Esc => self.should_quit = true,
+            KeyCode::Char('/') => self.search_input = Some(String::new()),
+            KeyCode::Char(c @ '1'..='3') => {
+                let index = c as usize - '1' as usize;
+                self.log_levels[index] = !self.log_levels[index];
+            }
+            KeyCode::Char(' ') => {
+                self.log_anchor = match self.log_anchor {
+                    Some(_) => None,
+                    None => self.data.logs.lock().unwrap().back().map(|line| line.seq),
+                };
+            }
+            KeyCode::Char('w') => self.dump_logs(),
+            KeyCode::Char('h') | KeyCode::Char('?') => self.show_help = true,
+            KeyCode::Char('p') => self.send(MinerCommand::TogglePause),
+            KeyCode::Char('+') | KeyCode::Char('=') => {
+                self.send(MinerCommand::AdjustHashThreads { delta: 1 })
+            }
+            KeyCode::Char('-') => self.send(MinerCommand::AdjustHashThreads { delta: -1 }),
+            KeyCode::Char(']') => self.send(MinerCommand::AdjustParallelTasks { delta: 1 }),
+            KeyCode::Char('[') => self.send(MinerCommand::AdjustParallelTasks { delta: -1 }),
+            KeyCode::Char('b') => self.send(MinerCommand::RefreshBalance),
+            KeyCode::Tab => {
+                self.focus = match self.focus {
+                    Focus::Tasks => Focus::Rpc,
+                    Focus::Rpc => Focus::Logs,
+                    Focus::Logs => Focus::Tasks,
+                };
+            }
+            KeyCode::Up => self.move_selection(-1),
+            KeyCode::Down => self.move_selection(1),
+            KeyCode::PageUp => self.move_selection(-PAGE_ROWS),
+            KeyCode::PageDown => self.move_selection(PAGE_ROWS),
+            KeyCode::Home => self.move_selection(isize::MIN),
+            KeyCode::End => self.move_selection(isize::MAX),
+            KeyCode::Char('x') => self.cancel_selected_task(),
+            KeyCode::Char('s') => self.prefer_selected_rpc(),
+            KeyCode::Char('d') => self.toggle_selected_rpc(),
+            _ => {}
+        }
+    }
+
+    fn send(&self, command: MinerCommand) {
+        if self.control.can_control() {
+            self.control.send(command, &self.data);
+        } else {
+            console_eprintln!(
+                "只读连接，不能操作挖矿进程；使用 attach --control 连接 / Attached read-only; use attach --control to operate the miner"
+            );
+        }
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        match self.focus {
+            Focus::Tasks => {
+                let tasks = self.data.task_progresses.lock().unwrap();
+                let current = self
+                    .selected_task
+                    .and_then(|id| tasks.iter().position(|t| t.id == id));
+                self.selected_task = step(current, delta, tasks.len()).map(|index| tasks[index].id);
+            }
+            Focus::Rpc => {
+                let len = self.data.rpc_status.lock().unwrap().len();
+                let selected = step(self.rpc_state.selected(), delta, len);
+                self.rpc_state.select(selected);
+            }
+            Focus::Logs => self.scroll_logs(delta),
+        }
+    }
+
+    fn handle_search_key(&mut self, key: KeyEvent) {
+        let Some(input) = self.search_input.as_mut() else {
+            return;
+        };
+        match key.code {
+            KeyCode::Enter => {
+                let query = input.trim().to_string();
+                self.search = (!query.is_empty()).then_some(query);
+                self.search_input = None;
+            }
+            KeyCode::Esc => {
+                self.search = None;
+                self.search_input = None;
+            }
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Char(c) => input.push(c),
+            _ => {}
+        }
+    }
+
+    // 当前过滤条件下可见的日志
+    fn log_visible(&self, line: &LogLine) -> bool {
+        self.log_levels[line.level.index()]
+            && self.search.as_ref().map_or(true, |query| {
+                line.text.to_lowercase().contains(&query.to_lowercase())
+            })
+    }
+
+    // 在过滤后的日志中移动；向上滚动即暂停，回到底部恢复跟随
+    fn scroll_logs(&mut self, delta: isize) {
+        let logs = self.data.logs.lock().unwrap();
+        let visible: Vec<u64> = logs
+            .iter()
+            .filter(|line| self.log_visible(line))
+            .map(|line| line.seq)
+            .collect();
+        drop(logs);
+        let Some(&last) = visible.last() else {
+            return;
+        };
+
+        let current = match self.log_anchor {
+            Some(anchor) => visible
+                .partition_point(|&seq| seq <= anchor)
+                .saturating_sub(1),
+            None => visible.len() - 1,
+        };
+        let target = current.saturating_add_signed(delta).min(visible.len() - 1);
+        self.log_anchor = (visible[target] != last).then_some(visible[target]);
+    }
+
+    fn dump_logs(&self) {
+        let path = std::path::PathBuf::from(format!(
+            "pow-client-{}.log",
+            Local::now().format("%Y%m%d-%H%M%S")
+        ));
+        match self.data.dump_logs(&path) {
+            Ok(count) => console_println!(
+                "已将 {} 行日志写入 {} / Wrote {} log lines to {}",
+                count,
+                path.display(),
+                count,
+                path.display()
+            ),
+            Err(e) => console_eprintln!(
+                "无法写入日志文件 / Cannot write log file {}: {}",
+                path.display(),
+                e
+            ),
+        }
+    }
+
+    fn cancel_selected_task(&self) {
+        let Some(id) = self.selected_task else {
+            return;
+        };
+        let tasks = self.data.task_progresses.lock().unwrap();
+        if !tasks.iter().any(|t| t.id == id && !t.is_finished()) {
+            return;
+        }
+        drop(tasks);
+        self.send(MinerCommand::CancelTask { task_id: id });
+    }
+
+    fn selected_rpc(&self) -> Option<EndpointStatus> {
+        let index = self.rpc_state.selected()?;
+        self.data.rpc_status.lock().unwrap().get(index).cloned()
+    }
+
+    fn prefer_selected_rpc(&self) {
+        let Some(endpoint) = self.selected_rpc() else {
+            return;
+        };
+        let url = (!endpoint.preferred).then_some(endpoint.url);
+        self.send(MinerCommand::PreferEndpoint { url });
+    }
+
+    fn toggle_selected_rpc(&self) {
+        let Some(endpoint) = self.selected_rpc() else {
+            return;
+        };
+        self.send(MinerCommand::SetEndpointDisabled {
+            url: endpoint.url,
+            disabled: !endpoint.disabled,
+        });
+    }
+
+    fn ui<B: Backend>(&mut self, f: &mut Frame<B>) {
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .margin(1)
             .constraints(
                 [
-                    Constraint::Length(3), // 标题
-                    Constraint::Min(12),   // 任务区域
-                    Constraint::Length(7), // 钱包信息
+                    Constraint::Length(3),  // 标题
+                    Constraint::Min(10),    // 任务区域
+                    Constraint::Length(8),  // RPC节点与系统资源
+                    Constraint::Length(9),  // 钱包信息与图表
+                    Constraint::Length(10), // 日志
                 ]
                 .as_ref(),
             )
             .split(f.size());
 
         // 标题
-        let title = Paragraph::new("Magnet POW 挖矿监控 (按 'q' 退出)")
-            .style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))
-            .block(Block::default().borders(Borders::ALL));
+        let state = self.control.state();
+        let title = Paragraph::new(format!(
+            "Magnet POW 挖矿监控{}{}  哈希线程: {}  并行任务: {}  (按 'h' 查看快捷键, 'q' 退出)",
+            if state.paused { " [已暂停]" } else { "" },
+            if self.control.can_control() {
+                ""
+            } else {
+                " [只读]"
+            },
+            state.hash_threads,
+            state.parallel_tasks
+        ))
+        .style(
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        )
+        .block(Block::default().borders(Borders::ALL));
         f.render_widget(title, chunks[0]);
 
         // 任务显示
         self.render_tasks(f, chunks[1]);
 
+        // RPC节点与系统资源
+        let middle = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(
+                [
+                    Constraint::Percentage(50),
+                    Constraint::Percentage(25),
+                    Constraint::Percentage(25),
+                ]
+                .as_ref(),
+            )
+            .split(chunks[2]);
+        self.render_rpc_status(f, middle[0]);
+        self.render_resources(f, middle[1]);
+        self.render_cpu_cores(f, middle[2]);
+
         // 钱包信息
-        self.render_wallet_info(f, chunks[2]);
+        self.render_wallet_info(f, chunks[3]);
+
+        // 日志
+        self.render_logs(f, chunks[4]);
+
+        if self.show_help {
+            self.render_help(f);
+        }
+    }
+
+    fn render_help<B: Backend>(&self, f: &mut Frame<B>) {
+        let size = f.size();
+        let width = size.width.min(100);
+        let height = size.height.min(HELP_LINES.len() as u16 + 4);
+        let area = Rect::new(
+            (size.width - width) / 2,
+            (size.height - height) / 2,
+            width,
+            height,
+        );
+
+        let mut text: Vec<String> = HELP_LINES
+            .iter()
+            .map(|(key, action)| format!("{:<14}{}", key, action))
+            .collect();
+        text.push("按任意键关闭 / Press any key to close".to_string());
+
+        let help = Paragraph::new(text.join("\n"))
+            .block(Block::default().title("快捷键").borders(Borders::ALL))
+            .style(Style::default().fg(Color::White));
+        f.render_widget(Clear, area);
+        f.render_widget(help, area);
+    }
+
+    // 当前操作的列表边框高亮
+    fn list_block(&self, title: &'static str, focus: Focus) -> Block<'static> {
+        self.list_block_owned(title.to_string(), focus)
+    }
+
+    fn list_block_owned(&self, title: String, focus: Focus) -> Block<'static> {
+        let block = Block::default().title(title).borders(Borders::ALL);
+        if self.focus == focus {
+            block.border_style(Style::default().fg(Color::Cyan))
+        } else {
+            block
+        }
+    }
+
+    fn render_logs<B: Backend>(&self, f: &mut Frame<B>, area: Rect) {
+        let logs = self.data.logs.lock().unwrap();
+        let lines: Vec<&LogLine> = logs
+            .iter()
+            .filter(|line| self.log_visible(line))
+            .filter(|line| self.log_anchor.map_or(true, |anchor| line.seq <= anchor))
+            .collect();
+
+        // 只显示能放下的最新几行（暂停时为固定位置之前的几行）
+        let visible = area.height.saturating_sub(2) as usize;
+        let items: Vec<ListItem> = lines
+            .iter()
+            .skip(lines.len().saturating_sub(visible))
+            .map(|line| {
+                ListItem::new(format!("{} {}", line.time.format("%H:%M:%S"), line.text))
+                    .style(Style::default().fg(line.level.color()))
+            })
+            .collect();
+
+        let mut title = String::from("日志");
+        let hidden: Vec<&str> = [LogLevel::Info, LogLevel::Warn, LogLevel::Error]
+            .iter()
+            .filter(|level| !self.log_levels[level.index()])
+            .map(|level| level.label())
+            .collect();
+        if !hidden.is_empty() {
+            title.push_str(&format!(" [隐藏: {}]", hidden.join(",")));
+        }
+        match (&self.search_input, &self.search) {
+            (Some(input), _) => title.push_str(&format!(" 搜索: {}_", input)),
+            (None, Some(query)) => title.push_str(&format!(" [搜索: {}]", query)),
+            _ => {}
+        }
+        if self.log_anchor.is_some() {
+            title.push_str(" [已暂停滚动，空格恢复]");
+        }
+
+        let list = List::new(items).block(self.list_block_owned(title, Focus::Logs));
+        f.render_widget(list, area);
+    }
+
+    fn render_resources<B: Backend>(&self, f: &mut Frame<B>, area: Rect) {
+        let block = Block::default().title("系统资源").borders(Borders::ALL);
+        let Some(snapshot) = &self.resource_snapshot else {
+            f.render_widget(Paragraph::new("采样中...").block(block), area);
+            return;
+        };
+
+        // 每个计算中的任务使用 hash_threads 个线程；没有逐线程计数，显示的是总算力的平均值
+        let hashing = self.data.hashrates.lock().unwrap().len();
+        let threads = hashing * self.control.state().hash_threads;
+        let average = if threads > 0 {
+            self.data.total_hashrate() / threads as f64
+        } else {
+            0.0
+        };
+
+        let mut lines = vec![
+            format!(
+                "平均每线程算力: {:.1}K/s ({} 线程)",
+                average / 1_000.0,
+                threads
+            ),
+            format!(
+                "内存: {:.1} MB",
+                snapshot.rss_bytes as f64 / 1024.0 / 1024.0
+            ),
+            format!(
+                "负载: {:.2} {:.2} {:.2}",
+                snapshot.load_average.0, snapshot.load_average.1, snapshot.load_average.2
+            ),
+        ];
+        let hottest = snapshot
+            .temperatures
+            .iter()
+            .max_by(|a, b| a.celsius.total_cmp(&b.celsius));
+        lines.push(match hottest {
+            Some(temp) => format!("温度: {:.0}°C ({})", temp.celsius, temp.label),
+            None => "温度: 无数据".to_string(),
+        });
+
+        let hot = snapshot.temperatures.iter().any(|temp| temp.is_hot());
+        let (style, block) = if snapshot.throttled || hot {
+            lines.push(
+                if snapshot.throttled {
+                    "CPU 可能正在降频"
+                } else {
+                    "CPU 温度过高"
+                }
+                .to_string(),
+            );
+            (
+                Style::default().fg(Color::Red),
+                block.border_style(Style::default().fg(Color::Red)),
+            )
+        } else {
+            (Style::default().fg(Color::White), block)
+        };
+
+        let paragraph = Paragraph::new(lines.join("\n")).block(block).style(style);
+        f.render_widget(paragraph, area);
+    }
+
+    // 各核心占用率柱状图
+    fn render_cpu_cores<B: Backend>(&self, f: &mut Frame<B>, area: Rect) {
+        let Some(snapshot) = &self.resource_snapshot else {
+            f.render_widget(
+                Block::default().title("CPU 核心").borders(Borders::ALL),
+                area,
+            );
+            return;
+        };
+
+        let labels: Vec<String> = (0..snapshot.cpu_usage.len())
+            .map(|i| i.to_string())
+            .collect();
+        let data: Vec<(&str, u64)> = labels
+            .iter()
+            .zip(&snapshot.cpu_usage)
+            .map(|(label, usage)| (label.as_str(), usage.round() as u64))
+            .collect();
+
+        // 按核心数分配柱宽，核心很多时柱宽为 1
+        let cores = data.len().max(1) as u16;
+        let bar_width = (area.width.saturating_sub(2) / cores)
+            .saturating_sub(1)
+            .clamp(1, 5);
+        let average = snapshot.cpu_usage.iter().sum::<f32>() / cores as f32;
+        let frequency = snapshot.cpu_frequency.iter().max().copied().unwrap_or(0);
+
+        let chart = BarChart::default()
+            .block(
+                Block::default()
+                    .title(format!("CPU {:.0}% {}MHz", average, frequency))
+                    .borders(Borders::ALL),
+            )
+            .data(&data)
+            .max(100)
+            .bar_width(bar_width)
+            .bar_gap(1)
+            .bar_style(Style::default().fg(Color::Cyan))
+            .value_style(Style::default().fg(Color::Black).bg(Color::Cyan));
+        f.render_widget(chart, area);
+    }
+
+    fn render_rpc_status<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect) {
+        let status = self.data.rpc_status.lock().unwrap();
+        let items: Vec<ListItem> = status
+            .iter()
+            .map(|ep| {
+                let color = if ep.disabled {
+                    Color::DarkGray
+                } else if ep.circuit_open || ep.quarantined || ep.rejected {
+                    Color::Red
+                } else if ep.is_healthy() {
+                    Color::Green
+                } else {
+                    Color::Yellow
+                };
+                ListItem::new(format!(
+                    "{} 延迟: {} 区块: {} 落后: {} 失败: {}/{}{}{}{}{}{}{}",
+                    ep.url,
+                    ep.latency_ms
+                        .map_or("-".to_string(), |l| format!("{:.0}ms", l)),
+                    ep.block_number.map_or("-".to_string(), |b| b.to_string()),
+                    ep.lag.map_or("-".to_string(), |l| l.to_string()),
+                    ep.failures,
+                    ep.requests,
+                    if ep.circuit_open { " [熔断]" } else { "" },
+                    if ep.quarantined { " [隔离]" } else { "" },
+                    if ep.rejected {
+                        " [链或合约不符]"
+                    } else {
+                        ""
+                    },
+                    if ep.unverified { " [未核对]" } else { "" },
+                    if ep.preferred { " [首选]" } else { "" },
+                    if ep.disabled { " [已停用]" } else { "" }
+                ))
+                .style(Style::default().fg(color))
+            })
+            .collect();
+
+        let list = List::new(items)
+            .block(self.list_block("RPC节点", Focus::Rpc))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        f.render_stateful_widget(list, area, &mut self.rpc_state);
     }
 
-    fn render_tasks<B: Backend>(&self, f: &mut Frame<B>, area: Rect) {
+    fn render_tasks<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect) {
         let tasks_area = Layout::default()
             .direction(Direction::Vertical)
             .constraints([Constraint::Length(3), Constraint::Min(5)].as_ref())
@@ -215,92 +1196,572 @@ impl TuiApp {
         let completed = self.data.completed_tasks.load(Ordering::Relaxed);
 
         let stats = Paragraph::new(format!(
-            "在线任务: {}  处理中: {}  已完成: {}",
-            online, processing, completed
+            "在线任务: {}  处理中: {}  已完成: {}  算力: {:.2}M 哈希/秒",
+            online,
+            processing,
+            completed,
+            self.data.total_hashrate() / 1_000_000.0
         ))
         .block(Block::default().title("任务统计").borders(Borders::ALL))
         .style(Style::default().fg(Color::Green));
         f.render_widget(stats, tasks_area[0]);
 
-        // 任务详情
+        let panes = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(62), Constraint::Percentage(38)].as_ref())
+            .split(tasks_area[1]);
+
         let tasks = self.data.task_progresses.lock().unwrap();
-        if tasks.is_empty() {
-            let no_tasks = Paragraph::new("暂无任务")
-                .block(Block::default().title("任务详情").borders(Borders::ALL));
-            f.render_widget(no_tasks, tasks_area[1]);
-            return;
+        let selected = self
+            .selected_task
+            .and_then(|id| tasks.iter().position(|t| t.id == id));
+        // 选中的任务已被归档
+        if selected.is_none() {
+            self.selected_task = None;
         }
+        self.task_table.select(selected);
 
-        // 使用更高效的列表渲染
-        let items: Vec<ListItem> = tasks
+        let rows: Vec<Row> = tasks
             .iter()
             .map(|task| {
-                let status = match task.status {
-                    TaskStatus::Waiting => ("等待中", Color::Yellow),
-                    TaskStatus::Processing => ("处理中", Color::Blue),
-                    TaskStatus::Completed => ("已完成", Color::Green),
-                    TaskStatus::Failed => ("失败", Color::Red),
-                };
-                
-                ListItem::new(format!(
-                    "任务#{}: {} {:.1}% 开始于: {}",
-                    task.id,
-                    status.0,
-                    task.progress * 100.0,
-                    task.start_time.format("%H:%M:%S")
-                )).style(Style::default().fg(status.1))
+                let (stage, color) = task.status.label();
+                Row::new(vec![
+                    task.id.to_string(),
+                    short_hex(&task.wallet),
+                    stage.to_string(),
+                    task.difficulty
+                        .as_deref()
+                        .and_then(|d| d.parse::<f64>().ok())
+                        .map_or("-".to_string(), |d| format!("{:.1e}", d)),
+                    format_count(task.hashes),
+                    format_elapsed(task.elapsed()),
+                    match task.tx_hashes.as_slice() {
+                        [] => "-".to_string(),
+                        [tx] => short_hex(tx),
+                        [.., last] => format!("{}+{}", short_hex(last), task.tx_hashes.len() - 1),
+                    },
+                    task.reward.map_or("-".to_string(), |r| format!("{:.4}", r)),
+                ])
+                .style(Style::default().fg(color))
             })
             .collect();
 
-        let list = List::new(items)
-            .block(Block::default().title("任务列表").borders(Borders::ALL))
-            .highlight_style(Style::default().add_modifier(Modifier::BOLD));
-        f.render_widget(list, tasks_area[1]);
+        let archive = self.data.archive.lock().unwrap();
+        let title = if archive.completed + archive.failed > 0 {
+            format!(
+                "任务列表 (已归档: 完成 {} 失败 {} 奖励 {:.4})",
+                archive.completed, archive.failed, archive.rewards
+            )
+        } else {
+            "任务列表".to_string()
+        };
+        drop(archive);
+
+        let header = Row::new(vec![
+            "ID",
+            "钱包",
+            "阶段",
+            "难度",
+            "已算哈希",
+            "用时",
+            "交易",
+            "奖励",
+        ])
+        .style(
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        );
+        let widths = [
+            Constraint::Length(5),
+            Constraint::Length(13),
+            Constraint::Length(8),
+            Constraint::Length(8),
+            Constraint::Length(8),
+            Constraint::Length(8),
+            Constraint::Length(16),
+            Constraint::Length(8),
+        ];
+        let table = Table::new(rows)
+            .header(header)
+            .block(self.list_block_owned(title, Focus::Tasks))
+            .widths(&widths)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        f.render_stateful_widget(table, panes[0], &mut self.task_table);
+
+        let detail = selected.map(|index| task_detail(&tasks[index], panes[1]));
+        drop(tasks);
+        let detail = Paragraph::new(
+            detail.unwrap_or_else(|| "按 Tab 切换到任务列表，↑/↓ 选择任务".to_string()),
+        )
+        .block(Block::default().title("任务详情").borders(Borders::ALL))
+        .wrap(Wrap { trim: false });
+        f.render_widget(detail, panes[1]);
     }
 
     fn render_wallet_info<B: Backend>(&self, f: &mut Frame<B>, area: Rect) {
         let wallet_areas = Layout::default()
             .direction(Direction::Horizontal)
-            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+            .constraints(
+                [
+                    Constraint::Percentage(30),
+                    Constraint::Percentage(30),
+                    Constraint::Percentage(40),
+                ]
+                .as_ref(),
+            )
             .split(area);
+        let left = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(5), Constraint::Min(3)].as_ref())
+            .split(wallet_areas[0]);
 
         // 当前余额
         let balance = *self.data.wallet_balance.lock().unwrap();
-        let balance_info = Paragraph::new(format!("当前余额: {:.6} MAG", balance))
-            .block(Block::default().title("钱包信息").borders(Borders::ALL))
-            .style(Style::default().fg(Color::White));
-        f.render_widget(balance_info, wallet_areas[0]);
+        let contract_balance = *self.data.contract_balance.lock().unwrap();
+        let rewards = *self.data.total_rewards.lock().unwrap();
+        let balance_info = Paragraph::new(format!(
+            "当前余额: {:.6} MAG\n矿池余额: {:.6} MAG\n本次奖励: {:.6} MAG",
+            balance, contract_balance, rewards
+        ))
+        .block(Block::default().title("钱包信息").borders(Borders::ALL))
+        .style(Style::default().fg(Color::White));
+        f.render_widget(balance_info, left[0]);
+
+        self.render_task_gauge(f, left[1]);
+        self.render_hashrate(f, wallet_areas[1]);
+        self.render_balance_chart(f, wallet_areas[2]);
+    }
 
-        // 历史余额
-        let history = self.data.balance_history.lock().unwrap();
-        let history_items: Vec<ListItem> = history
+    // 选中任务（未选中时为第一个计算中的任务）已计算哈希数与预计哈希数之比
+    fn render_task_gauge<B: Backend>(&self, f: &mut Frame<B>, area: Rect) {
+        let tasks = self.data.task_progresses.lock().unwrap();
+        let task = self
+            .selected_task
+            .and_then(|id| tasks.iter().find(|task| task.id == id))
+            .filter(|task| task.expected_hashes > 0.0)
+            .or_else(|| {
+                tasks
+                    .iter()
+                    .find(|task| task.status == TaskStatus::Hashing && task.expected_hashes > 0.0)
+            });
+
+        let Some(task) = task else {
+            let idle = Paragraph::new("暂无计算中的任务")
+                .block(Block::default().title("当前任务").borders(Borders::ALL));
+            f.render_widget(idle, area);
+            return;
+        };
+
+        // 超过预计哈希数说明运气不佳，显示为黄色
+        let ratio = task.hashes as f64 / task.expected_hashes;
+        let color = if ratio > 1.0 {
+            Color::Yellow
+        } else {
+            Color::Cyan
+        };
+        let gauge = Gauge::default()
+            .block(
+                Block::default()
+                    .title(format!("任务#{} 实际/预计哈希", task.id))
+                    .borders(Borders::ALL),
+            )
+            .gauge_style(Style::default().fg(color))
+            .ratio(ratio.clamp(0.0, 1.0))
+            .label(format!(
+                "{:.2}M / {:.2}M ({:.0}%)",
+                task.hashes as f64 / 1_000_000.0,
+                task.expected_hashes / 1_000_000.0,
+                ratio * 100.0
+            ));
+        f.render_widget(gauge, area);
+    }
+
+    fn render_hashrate<B: Backend>(&self, f: &mut Frame<B>, area: Rect) {
+        let history = self.data.hashrate_history.lock().unwrap();
+        // 只显示能放下的最近若干秒
+        let width = area.width.saturating_sub(2) as usize;
+        let data: Vec<u64> = history
             .iter()
-            .map(|(time, balance)| {
-                ListItem::new(format!("{}: {:.6} MAG", time.format("%H:%M:%S"), balance))
-            })
+            .skip(history.len().saturating_sub(width))
+            .copied()
             .collect();
+        let peak = data.iter().copied().max().unwrap_or(0);
 
-        let history_list = List::new(history_items)
-            .block(Block::default().title("余额历史").borders(Borders::ALL))
-            .style(Style::default().fg(Color::White));
-        f.render_widget(history_list, wallet_areas[1]);
+        let sparkline = Sparkline::default()
+            .block(
+                Block::default()
+                    .title(format!(
+                        "算力 当前: {:.2}M/s 峰值: {:.2}M/s",
+                        data.last().copied().unwrap_or(0) as f64 / 1_000_000.0,
+                        peak as f64 / 1_000_000.0
+                    ))
+                    .borders(Borders::ALL),
+            )
+            .data(&data)
+            .style(Style::default().fg(Color::Green));
+        f.render_widget(sparkline, area);
+    }
+
+    // 余额变化与累计奖励，横轴为距现在的分钟数
+    fn render_balance_chart<B: Backend>(&self, f: &mut Frame<B>, area: Rect) {
+        let now = Local::now();
+        let window = *self.data.chart_window.lock().unwrap();
+        let window_minutes = window.num_seconds() as f64 / 60.0;
+        let minutes_ago = |time: &DateTime<Local>| -((now - *time).num_seconds() as f64 / 60.0);
+
+        let initial = self.data.initial_balance.lock().unwrap().unwrap_or(0.0);
+        let mut balance: Vec<(f64, f64)> = self
+            .data
+            .balance_history
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(time, value)| (minutes_ago(time).max(-window_minutes), value - initial))
+            .collect();
+        let mut rewards: Vec<(f64, f64)> = self
+            .data
+            .reward_history
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(time, value)| (minutes_ago(time).max(-window_minutes), *value))
+            .collect();
+        // 把最后的值延伸到当前时刻
+        for series in [&mut balance, &mut rewards] {
+            if let Some(&(_, last)) = series.last() {
+                series.push((0.0, last));
+            }
+        }
+
+        let (low, high) = balance
+            .iter()
+            .chain(rewards.iter())
+            .fold((0.0f64, 0.0f64), |(low, high), &(_, y)| {
+                (low.min(y), high.max(y))
+            });
+        let high = if high <= low { low + 1.0 } else { high };
+
+        let datasets = vec![
+            Dataset::default()
+                .name("余额变化")
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(Color::Cyan))
+                .data(&balance),
+            Dataset::default()
+                .name("累计奖励")
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(Color::Green))
+                .data(&rewards),
+        ];
+        let chart = Chart::new(datasets)
+            .block(
+                Block::default()
+                    .title(format!("本次收益 MAG (最近{}分钟)", window.num_minutes()))
+                    .borders(Borders::ALL),
+            )
+            .x_axis(
+                Axis::default()
+                    .bounds([-window_minutes, 0.0])
+                    .labels(vec![
+                        Span::raw(format!("-{}m", window.num_minutes())),
+                        Span::raw("现在"),
+                    ])
+                    .style(Style::default().fg(Color::Gray)),
+            )
+            .y_axis(
+                Axis::default()
+                    .bounds([low, high])
+                    .labels(vec![
+                        Span::raw(format!("{:.2}", low)),
+                        Span::raw(format!("{:.2}", high)),
+                    ])
+                    .style(Style::default().fg(Color::Gray)),
+            );
+        f.render_widget(chart, area);
     }
 }
 
-pub fn start_tui(data: Arc<MonitorData>) -> io::Result<()> {
-    let mut app = TuiApp::new(data);
-    app.run()
+// 在长度为 len 的列表中移动选择，未选中时从首行开始
+pub fn step(current: Option<usize>, delta: isize, len: usize) -> Option<usize> {
+    if len == 0 {
+        return None;
+    }
+    Some(current.map_or(0, |index| index.saturating_add_signed(delta).min(len - 1)))
+}
+
+// 0x1234…abcd
+pub fn short_hex(value: &str) -> String {
+    if value.len() <= 13 {
+        return value.to_string();
+    }
+    format!("{}…{}", &value[..6], &value[value.len() - 4..])
+}
+
+pub fn format_count(count: u64) -> String {
+    match count {
+        0..=9_999 => count.to_string(),
+        10_000..=9_999_999 => format!("{:.1}K", count as f64 / 1_000.0),
+        _ => format!("{:.2}M", count as f64 / 1_000_000.0),
+    }
+}
+
+pub fn format_elapsed(elapsed: chrono::Duration) -> String {
+    let seconds = elapsed.num_seconds().max(0);
+    if seconds >= 3600 {
+        format!(
+            "{}:{:02}:{:02}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        )
+    } else {
+        format!("{:02}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+// 任务详情：基本信息与时间线，时间线放不下时省略较早的条目
+fn task_detail(task: &TaskProgress, area: Rect) -> String {
+    let mut lines = vec![
+        format!("任务 #{}  {}", task.id, task.status.label().0),
+        format!("钱包: {}", task.wallet),
+        format!("难度: {}", task.difficulty.as_deref().unwrap_or("-")),
+        format!(
+            "哈希: {} / 预计 {} ({:.1}%)",
+            format_count(task.hashes),
+            format_count(task.expected_hashes as u64),
+            task.progress * 100.0
+        ),
+        format!(
+            "开始: {}  用时: {}",
+            task.start_time.format("%H:%M:%S"),
+            format_elapsed(task.elapsed())
+        ),
+    ];
+    for tx in &task.tx_hashes {
+        lines.push(format!("交易: {}", tx));
+    }
+    if let Some(reward) = task.reward {
+        lines.push(format!("奖励: {:.6} MAG", reward));
+    }
+    if let Some(error) = &task.last_error {
+        lines.push(format!("错误: {}", error));
+    }
+
+    lines.push("时间线:".to_string());
+
+    // 按折行后的行数从最新的条目往前取
+    let width = area.width.saturating_sub(2).max(1) as usize;
+    let rows = |line: &String| {
+        let cells: usize = line.chars().map(|c| if c.is_ascii() { 1 } else { 2 }).sum();
+        ((cells + width - 1) / width).max(1)
+    };
+    let mut room =
+        (area.height.saturating_sub(2) as usize).saturating_sub(lines.iter().map(rows).sum());
+    let mut timeline: Vec<String> = Vec::new();
+    for (time, text) in task.timeline.iter().rev() {
+        let line = format!("  {} {}", time.format("%H:%M:%S"), text);
+        if rows(&line) > room {
+            break;
+        }
+        room -= rows(&line);
+        timeline.push(line);
+    }
+    lines.extend(timeline.into_iter().rev());
+    lines.join("\n")
 }
 
-pub fn start_monitor() -> Arc<MonitorData> {
-    let data = Arc::new(MonitorData::new());
-    let data_clone = Arc::clone(&data);
+pub fn start_tui(
+    data: Arc<MonitorData>,
+    stop: Arc<AtomicBool>,
+    control: Arc<dyn Controller>,
+) -> io::Result<()> {
+    let mut app = TuiApp::new(data, stop, control, ResourceSampler::new());
+    app.run()
+}
 
+// 在独立线程中运行监控界面，界面运行期间控制台输出写入界面日志；
+// 界面退出后恢复控制台输出并通知挖矿循环停止
+pub fn start_monitor(
+    data: Arc<MonitorData>,
+    stop: Arc<AtomicBool>,
+    control: Arc<dyn Controller>,
+) -> std::thread::JoinHandle<()> {
+    crate::console::capture(data.clone());
     std::thread::spawn(move || {
-        if let Err(err) = start_tui(data_clone) {
+        let result = start_tui(data, stop.clone(), control);
+        crate::console::release();
+        stop.store(true, Ordering::SeqCst);
+        if let Err(err) = result {
             eprintln!("TUI错误: {:?}", err);
         }
-    });
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_rpc::idle_control;
+
+    // 保留 history 个已结束任务的监控数据
+    fn data(history: usize) -> Arc<MonitorData> {
+        let data = Arc::new(MonitorData::new());
+        data.set_task_history(history);
+        data
+    }
+
+    fn finish(data: &MonitorData, id: usize, success: bool, reward: Option<f64>) {
+        data.add_task(id, format!("0x{:040x}", id));
+        if let Some(reward) = reward {
+            data.add_reward(id, reward);
+        }
+        data.complete_task(id, success);
+    }
+
+    fn task_ids(data: &MonitorData) -> Vec<usize> {
+        data.task_progresses
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|task| task.id)
+            .collect()
+    }
+
+    async fn app(data: Arc<MonitorData>) -> TuiApp {
+        TuiApp::new(
+            data,
+            Arc::new(AtomicBool::new(false)),
+            idle_control().await,
+            ResourceSampler::new(),
+        )
+    }
+
+    #[test]
+    fn finished_tasks_beyond_history_are_archived() {
+        let data = data(2);
+        finish(&data, 1, true, Some(1.5));
+        finish(&data, 2, false, None);
+        finish(&data, 3, true, Some(2.0));
+        assert_eq!(task_ids(&data), vec![2, 3]);
+
+        finish(&data, 4, true, Some(0.5));
+        assert_eq!(task_ids(&data), vec![3, 4]);
+        let archive = data.archive.lock().unwrap().clone();
+        assert_eq!(archive.completed, 1);
+        assert_eq!(archive.failed, 1);
+        assert_eq!(archive.rewards, 1.5);
+    }
+
+    #[test]
+    fn unfinished_tasks_are_never_archived() {
+        let data = data(0);
+        data.add_task(1, "0x01".to_string());
+        data.update_task_progress(1, 0.5);
+        finish(&data, 2, true, None);
+        data.add_task(3, "0x03".to_string());
+
+        assert_eq!(task_ids(&data), vec![1, 3]);
+        assert_eq!(data.archive.lock().unwrap().completed, 1);
+        assert_eq!(data.online_tasks.load(Ordering::SeqCst), 2);
+        assert_eq!(data.processing_tasks.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn failed_tasks_include_archived_failures() {
+        let data = data(1);
+        finish(&data, 1, false, None);
+        finish(&data, 2, false, None);
+        finish(&data, 3, true, None);
+        assert_eq!(task_ids(&data), vec![3]);
+        assert_eq!(data.failed_tasks(), 2);
+
+        finish(&data, 4, false, None);
+        assert_eq!(data.failed_tasks(), 3);
+    }
+
+    #[tokio::test]
+    async fn selection_follows_the_task_across_archiving() {
+        let data = data(1);
+        data.add_task(1, "0x01".to_string());
+        data.add_task(2, "0x02".to_string());
+        data.add_task(3, "0x03".to_string());
+        let mut app = app(data.clone()).await;
+        app.move_selection(1);
+        app.move_selection(1);
+        assert_eq!(app.selected_task, Some(2));
+
+        // 任务 1 被归档后列表位置前移，选中的仍是任务 2
+        data.complete_task(1, true);
+        data.complete_task(3, true);
+        assert_eq!(task_ids(&data), vec![2, 3]);
+        assert_eq!(app.selected_task, Some(2));
+        app.move_selection(1);
+        assert_eq!(app.selected_task, Some(3));
+    }
+
+    // 依次写入 信息、警告、错误 各两行，序号 0..6
+    fn logged() -> Arc<MonitorData> {
+        let data = Arc::new(MonitorData::new());
+        for round in 0..2 {
+            data.push_log(format!("info {}", round), LogLevel::Info);
+            data.push_log(format!("warn {}", round), LogLevel::Warn);
+            data.push_log(format!("error {}", round), LogLevel::Error);
+        }
+        data
+    }
+
+    #[tokio::test]
+    async fn scrolling_up_pins_and_returning_to_the_end_follows() {
+        let mut app = app(logged()).await;
+        app.focus = Focus::Logs;
+
+        app.move_selection(-1);
+        assert_eq!(app.log_anchor, Some(4));
+        app.move_selection(-2);
+        assert_eq!(app.log_anchor, Some(2));
+        // 新日志不会移动固定的位置
+        app.data.push_log("new".to_string(), LogLevel::Info);
+        app.move_selection(0);
+        assert_eq!(app.log_anchor, Some(2));
+
+        app.move_selection(isize::MIN);
+        assert_eq!(app.log_anchor, Some(0));
+        // 回到最后一行可见日志即恢复跟随
+        app.move_selection(isize::MAX);
+        assert_eq!(app.log_anchor, None);
+        app.move_selection(1);
+        assert_eq!(app.log_anchor, None);
+    }
 
-    data
-}
\ No newline at end of file
+    #[tokio::test]
+    async fn scrolling_moves_only_between_visible_lines() {
+        let mut app = app(logged()).await;
+        app.focus = Focus::Logs;
+
+        // 隐藏信息日志：可见的是 1 2 4 5
+        app.handle_key(KeyEvent::from(KeyCode::Char('1')));
+        app.move_selection(-1);
+        assert_eq!(app.log_anchor, Some(4));
+        app.move_selection(-1);
+        assert_eq!(app.log_anchor, Some(2));
+        app.move_selection(2);
+        assert_eq!(app.log_anchor, None);
+
+        // 只显示包含 "0" 的警告与错误：可见的是 1 2
+        app.search = Some("0".to_string());
+        assert!(app.log_visible(&app.data.logs.lock().unwrap()[1]));
+        assert!(!app.log_visible(&app.data.logs.lock().unwrap()[0]));
+        assert!(!app.log_visible(&app.data.logs.lock().unwrap()[4]));
+        app.move_selection(-5);
+        assert_eq!(app.log_anchor, Some(1));
+        app.move_selection(1);
+        assert_eq!(app.log_anchor, None);
+
+        // 没有可见日志时不移动
+        app.search = Some("missing".to_string());
+        app.move_selection(-1);
+        assert_eq!(app.log_anchor, None);
+    }
+}
@@ -0,0 +1,373 @@
+use async_trait::async_trait;
+use ethers::{
+    signers::Signer,
+    types::{
+        transaction::{eip2718::TypedTransaction, eip712::Eip712},
+        Address, Selector, Signature, U256,
+    },
+    utils::format_ether,
+};
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+
+use crate::contract::MININGCONTRACT_ABI;
+
+// 统计窗口
+const HOUR: Duration = Duration::from_secs(3600);
+const DAY: Duration = Duration::from_secs(24 * 3600);
+
+// 不附带数据的转账，需通过 --policy-allow transfer 显式开启
+pub const TRANSFER_OPERATION: &str = "transfer";
+
+// 挖矿本身需要的合约调用，始终允许
+const MINING_FUNCTIONS: [&str; 2] = ["requestMiningTask", "submitMiningResult"];
+
+#[derive(Debug, Error)]
+pub enum PolicyViolation {
+    #[error("交易目标 {0:?} 不是挖矿合约 / Transaction target {0:?} is not the mining contract")]
+    Target(Option<Address>),
+    #[error("不允许调用 {0} / Call to {0} is not allowed, enable it with --policy-allow")]
+    Selector(String),
+    #[error("挖矿调用不能附带转账 / Mining calls must not transfer value ({0} MAG)")]
+    Value(String),
+    #[error("交易缺少 gas 或 gas 价格 / Transaction has no gas limit or gas price")]
+    Unpriced,
+    #[error("已达每小时 {0} 笔交易上限 / Hourly limit of {0} transactions reached")]
+    HourlyCount(u32),
+    #[error("超出每日 gas 花费上限 {0} MAG / Daily gas spend limit of {0} MAG would be exceeded")]
+    DailyGas(String),
+    #[error("签名策略不允许签名消息 / Message signing is not allowed by the transaction policy")]
+    Message,
+}
+
+// 签名策略：只允许调用挖矿合约的挖矿函数和显式开启的操作，并限制交易频率与 gas 花费
+#[derive(Debug)]
+pub struct TxPolicy {
+    contract: Address,
+    allowed: Vec<(Selector, String)>,
+    allow_transfer: bool,
+    max_tx_per_hour: u32,
+    max_daily_gas: U256,
+    // 已签名（或正在签名）交易的时间与最大手续费
+    signed: Mutex<VecDeque<Reservation>>,
+}
+
+// 通过检查的交易占用的频率与 gas 额度；签名失败时归还，只有签名成功的交易计入统计
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reservation {
+    at: Instant,
+    fee: U256,
+}
+
+impl TxPolicy {
+    // operations 为额外允许的合约函数名或 transfer；max_tx_per_hour 与 max_daily_gas 为 0 时不限制
+    pub fn new(
+        contract: Address,
+        operations: &[String],
+        max_tx_per_hour: u32,
+        max_daily_gas: U256,
+    ) -> anyhow::Result<Self> {
+        let mut allowed = Vec::new();
+        let mut allow_transfer = false;
+        for name in MINING_FUNCTIONS
+            .iter()
+            .copied()
+            .chain(operations.iter().map(String::as_str))
+        {
+            if name == TRANSFER_OPERATION {
+                allow_transfer = true;
+                continue;
+            }
+            let function = MININGCONTRACT_ABI.function(name).map_err(|_| {
+                anyhow::anyhow!(
+                    "合约中没有函数 {} / Contract has no function {}",
+                    name,
+                    name
+                )
+            })?;
+            allowed.push((function.short_signature(), function.name.clone()));
+        }
+
+        Ok(TxPolicy {
+            contract,
+            allowed,
+            allow_transfer,
+            max_tx_per_hour,
+            max_daily_gas,
+            signed: Mutex::new(VecDeque::new()),
+        })
+    }
+
+    // 检查交易，通过时占用频率与 gas 额度，签名失败时需调用 release 归还；
+    // 在同一把锁内检查并占用，并行签名也不会超出上限
+    pub fn check(&self, tx: &TypedTransaction) -> Result<Reservation, PolicyViolation> {
+        self.check_at(tx, Instant::now())
+    }
+
+    // 归还未签名成功的交易占用的额度
+    pub fn release(&self, reservation: Reservation) {
+        let mut signed = self.signed.lock().unwrap();
+        if let Some(index) = signed.iter().position(|r| *r == reservation) {
+            signed.remove(index);
+        }
+    }
+
+    fn check_at(
+        &self,
+        tx: &TypedTransaction,
+        now: Instant,
+    ) -> Result<Reservation, PolicyViolation> {
+        self.check_call(tx)?;
+
+        let fee = match (tx.gas(), tx.gas_price()) {
+            (Some(gas), Some(price)) => gas * price,
+            _ => return Err(PolicyViolation::Unpriced),
+        };
+
+        let mut signed = self.signed.lock().unwrap();
+        while signed
+            .front()
+            .is_some_and(|r| now.saturating_duration_since(r.at) >= DAY)
+        {
+            signed.pop_front();
+        }
+
+        if self.max_tx_per_hour > 0 {
+            let last_hour = signed
+                .iter()
+                .filter(|r| now.saturating_duration_since(r.at) < HOUR)
+                .count();
+            if last_hour >= self.max_tx_per_hour as usize {
+                return Err(PolicyViolation::HourlyCount(self.max_tx_per_hour));
+            }
+        }
+        if !self.max_daily_gas.is_zero() {
+            let spent = signed.iter().fold(U256::zero(), |total, r| total + r.fee);
+            if spent + fee > self.max_daily_gas {
+                return Err(PolicyViolation::DailyGas(format_ether(self.max_daily_gas)));
+            }
+        }
+
+        let reservation = Reservation { at: now, fee };
+        signed.push_back(reservation);
+        Ok(reservation)
+    }
+
+    fn check_call(&self, tx: &TypedTransaction) -> Result<(), PolicyViolation> {
+        let data = tx.data().map(|data| data.as_ref()).unwrap_or_default();
+        let value = tx.value().copied().unwrap_or_default();
+
+        if data.is_empty() && self.allow_transfer {
+            return Ok(());
+        }
+        if tx.to_addr() != Some(&self.contract) {
+            return Err(PolicyViolation::Target(tx.to_addr().copied()));
+        }
+
+        let selector = data.get(..4).unwrap_or_default();
+        let Some((_, name)) = self
+            .allowed
+            .iter()
+            .find(|(allowed, _)| allowed.as_slice() == selector)
+        else {
+            return Err(PolicyViolation::Selector(format!(
+                "0x{}",
+                hex::encode(selector)
+            )));
+        };
+        if MINING_FUNCTIONS.contains(&name.as_str()) && !value.is_zero() {
+            return Err(PolicyViolation::Value(format_ether(value)));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum PolicySignerError<E: std::error::Error> {
+    #[error("交易被签名策略拒绝 / Rejected by transaction policy: {0}")]
+    Denied(#[from] PolicyViolation),
+    #[error(transparent)]
+    Signer(E),
+}
+
+// 签名前先经过 TxPolicy 检查，直接签名与广播路径都无法绕过
+#[derive(Debug, Clone)]
+pub struct PolicySigner<S> {
+    inner: S,
+    policy: Arc<TxPolicy>,
+}
+
+impl<S> PolicySigner<S> {
+    pub fn new(inner: S, policy: Arc<TxPolicy>) -> Self {
+        PolicySigner { inner, policy }
+    }
+}
+
+#[async_trait]
+impl<S: Signer> Signer for PolicySigner<S> {
+    type Error = PolicySignerError<S::Error>;
+
+    async fn sign_message<T: Send + Sync + AsRef<[u8]>>(
+        &self,
+        _message: T,
+    ) -> Result<Signature, Self::Error> {
+        Err(PolicyViolation::Message.into())
+    }
+
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature, Self::Error> {
+        let reservation = self.policy.check(tx)?;
+        self.inner.sign_transaction(tx).await.map_err(|e| {
+            self.policy.release(reservation);
+            PolicySignerError::Signer(e)
+        })
+    }
+
+    async fn sign_typed_data<T: Eip712 + Send + Sync>(
+        &self,
+        _payload: &T,
+    ) -> Result<Signature, Self::Error> {
+        Err(PolicyViolation::Message.into())
+    }
+
+    fn address(&self) -> Address {
+        self.inner.address()
+    }
+
+    fn chain_id(&self) -> u64 {
+        self.inner.chain_id()
+    }
+
+    fn with_chain_id<T: Into<u64>>(self, chain_id: T) -> Self {
+        PolicySigner {
+            inner: self.inner.with_chain_id(chain_id),
+            policy: self.policy,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        mock_rpc::{MockRpc, Reply},
+        remote_signer::{MinerSigner, RemoteSigner},
+    };
+    use ethers::{signers::LocalWallet, types::TransactionRequest};
+
+    fn contract() -> Address {
+        Address::repeat_byte(0x51)
+    }
+
+    fn policy(max_tx_per_hour: u32, max_daily_gas: u64) -> TxPolicy {
+        TxPolicy::new(contract(), &[], max_tx_per_hour, U256::from(max_daily_gas)).unwrap()
+    }
+
+    // 手续费为 fee 的挖矿调用
+    fn mining_tx(fee: u64) -> TypedTransaction {
+        let selector = MININGCONTRACT_ABI
+            .function("requestMiningTask")
+            .unwrap()
+            .short_signature();
+        TransactionRequest::new()
+            .to(contract())
+            .data(selector.to_vec())
+            .gas(fee)
+            .gas_price(1)
+            .into()
+    }
+
+    #[test]
+    fn only_mining_calls_to_the_contract_pass() {
+        let policy = policy(0, 0);
+        assert!(policy.check(&mining_tx(1)).is_ok());
+
+        let mut other_target = mining_tx(1);
+        other_target.set_to(Address::repeat_byte(0xee));
+        assert!(matches!(
+            policy.check(&other_target),
+            Err(PolicyViolation::Target(_))
+        ));
+
+        let mut with_value = mining_tx(1);
+        with_value.set_value(1);
+        assert!(matches!(
+            policy.check(&with_value),
+            Err(PolicyViolation::Value(_))
+        ));
+
+        let mut other_call = mining_tx(1);
+        other_call.set_data(vec![0xde, 0xad, 0xbe, 0xef].into());
+        assert!(matches!(
+            policy.check(&other_call),
+            Err(PolicyViolation::Selector(_))
+        ));
+    }
+
+    #[test]
+    fn hourly_count_slides_with_the_window() {
+        let policy = policy(2, 0);
+        let start = Instant::now();
+        policy.check_at(&mining_tx(1), start).unwrap();
+        policy.check_at(&mining_tx(1), start).unwrap();
+        assert!(matches!(
+            policy.check_at(&mining_tx(1), start + HOUR / 2),
+            Err(PolicyViolation::HourlyCount(2))
+        ));
+        assert!(policy.check_at(&mining_tx(1), start + HOUR).is_ok());
+    }
+
+    #[test]
+    fn daily_gas_counts_signed_fees_for_a_day() {
+        let policy = policy(0, 100);
+        let start = Instant::now();
+        policy.check_at(&mining_tx(60), start).unwrap();
+        assert!(matches!(
+            policy.check_at(&mining_tx(41), start + HOUR),
+            Err(PolicyViolation::DailyGas(_))
+        ));
+        // 被拒绝的交易不占用额度
+        policy.check_at(&mining_tx(40), start + HOUR).unwrap();
+        assert!(policy.check_at(&mining_tx(1), start + HOUR).is_err());
+        // 一天后最早的花费移出窗口
+        assert!(policy.check_at(&mining_tx(60), start + DAY).is_ok());
+    }
+
+    #[test]
+    fn released_reservation_frees_the_budget() {
+        let policy = policy(1, 100);
+        let reservation = policy.check(&mining_tx(100)).unwrap();
+        assert!(policy.check(&mining_tx(1)).is_err());
+        policy.release(reservation);
+        assert!(policy.check(&mining_tx(100)).is_ok());
+    }
+
+    #[tokio::test]
+    async fn failed_signing_is_not_counted() {
+        let service =
+            MockRpc::start(|_, _| Reply::Error(-32000, "signer locked".to_string())).await;
+        let wallet = LocalWallet::from_bytes(&[1; 32]).unwrap();
+        let remote = RemoteSigner::connect(&service.url, Some(wallet.address()), 1, false)
+            .await
+            .unwrap();
+        let policy = Arc::new(policy(1, 100));
+        let signer = PolicySigner::new(MinerSigner::Remote(remote), policy.clone());
+
+        assert!(matches!(
+            signer.sign_transaction(&mining_tx(100)).await,
+            Err(PolicySignerError::Signer(_))
+        ));
+        assert!(policy.check(&mining_tx(100)).is_ok());
+
+        // 本地私钥签名成功后计入统计
+        let signer = PolicySigner::new(MinerSigner::Local(wallet), Arc::new(self::policy(1, 0)));
+        signer.sign_transaction(&mining_tx(1)).await.unwrap();
+        assert!(matches!(
+            signer.sign_transaction(&mining_tx(1)).await,
+            Err(PolicySignerError::Denied(PolicyViolation::HourlyCount(1)))
+        ));
+    }
+}
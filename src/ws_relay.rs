@@ -0,0 +1,397 @@
+use anyhow::{anyhow, Result};
+use colored::*;
+use rand::Rng;
+use reqwest::Url;
+use std::{
+    io,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+use tokio_rustls::{
+    rustls::{self, OwnedTrustAnchor, RootCertStore, ServerName},
+    TlsConnector,
+};
+use tokio_socks::{tcp::Socks5Stream, TargetAddr};
+
+use crate::{
+    http_util::{accept_with_backoff, read_head},
+    rpc_config::EndpointConfig,
+};
+
+// ethers 的 WebSocket 客户端只支持 Authorization 头，不支持代理和自定义 CA。
+// 对这类节点在本地起一个中继：客户端连接 ws://127.0.0.1:<port>/<随机路径>，中继改写握手请求、
+// 加上配置的请求头，并经代理/TLS 连接真正的节点后双向转发。
+// 随机路径防止本机其他进程借用中继的认证信息和代理。
+// 返回供 ethers 连接的本地地址
+pub async fn spawn(config: EndpointConfig) -> Result<String> {
+    let target = Upstream::new(config)?;
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let local = format!("ws://{}{}", listener.local_addr()?, target.relay_path);
+
+    let target = Arc::new(target);
+    tokio::spawn(async move {
+        loop {
+            let (client, _) = accept_with_backoff(
+                "WebSocket 中继接受连接失败 / WebSocket relay accept failed",
+                || listener.accept(),
+            )
+            .await;
+            let target = target.clone();
+            tokio::spawn(async move {
+                if let Err(e) = target.relay(client).await {
+                    console_eprintln!(
+                        warn: "{}",
+                        format!(
+                            "WebSocket 中继失败 / WebSocket relay to {} failed: {}",
+                            target.config.url, e
+                        )
+                        .yellow()
+                    );
+                }
+            });
+        }
+    });
+
+    Ok(local)
+}
+
+struct Upstream {
+    config: EndpointConfig,
+    url: Url,
+    // 不带方括号的主机名或 IP 地址
+    host: String,
+    ip: Option<IpAddr>,
+    port: u16,
+    tls: Option<TlsConnector>,
+    extra_headers: String,
+    // 本地客户端必须请求的路径
+    relay_path: String,
+}
+
+impl Upstream {
+    fn new(config: EndpointConfig) -> Result<Self> {
+        let url = Url::parse(&config.url)?;
+        let host = url
+            .host_str()
+            .ok_or_else(|| anyhow!("节点地址缺少主机名 / Endpoint URL has no host"))?;
+        // IPv6 地址在 URL 中带方括号，解析与连接时需要去掉
+        let host = host
+            .strip_prefix('[')
+            .and_then(|host| host.strip_suffix(']'))
+            .unwrap_or(host)
+            .to_string();
+        let ip = host.parse().ok();
+        let port = url
+            .port_or_known_default()
+            .ok_or_else(|| anyhow!("节点地址缺少端口 / Endpoint URL has no port"))?;
+        let tls = match url.scheme() {
+            "wss" => Some(tls_connector(&config)?),
+            _ => None,
+        };
+
+        let mut extra_headers = String::new();
+        for (name, value) in config.header_map()?.iter() {
+            extra_headers.push_str(&format!("{}: {}\r\n", name, value.to_str()?));
+        }
+
+        Ok(Upstream {
+            config,
+            url,
+            host,
+            ip,
+            port,
+            tls,
+            extra_headers,
+            relay_path: format!("/{}", hex::encode(rand::thread_rng().gen::<[u8; 16]>())),
+        })
+    }
+
+    async fn relay(&self, mut client: TcpStream) -> Result<()> {
+        let head = read_head(&mut client).await?;
+        let head = self.rewrite_head(&head)?;
+
+        let stream = self.connect().await?;
+        match &self.tls {
+            Some(tls) => {
+                let upstream = tls.connect(self.server_name()?, stream).await?;
+                forward(client, upstream, head).await
+            }
+            None => forward(client, stream, head).await,
+        }
+    }
+
+    // 请求行改为真实路径，Host 改为真实主机，再附加配置的请求头；
+    // 请求路径不是中继的随机路径时拒绝
+    fn rewrite_head(&self, head: &str) -> Result<String> {
+        let mut lines = head.split("\r\n");
+        let path = lines
+            .next()
+            .and_then(|line| line.strip_prefix("GET "))
+            .and_then(|line| line.split_whitespace().next())
+            .ok_or_else(|| anyhow!("不是 WebSocket 握手请求 / Not a WebSocket handshake"))?;
+        if path != self.relay_path {
+            return Err(anyhow!(
+                "中继路径不正确，拒绝连接 / Wrong relay path, connection refused"
+            ));
+        }
+
+        let mut path = self.url.path().to_string();
+        if let Some(query) = self.url.query() {
+            path.push('?');
+            path.push_str(query);
+        }
+        let host = match self.url.port() {
+            Some(port) => format!("{}:{}", self.authority_host(), port),
+            None => self.authority_host(),
+        };
+
+        let mut rewritten = format!("GET {} HTTP/1.1\r\nHost: {}\r\n", path, host);
+        for line in lines.filter(|line| !line.is_empty()) {
+            let name = line.split(':').next().unwrap_or_default().trim();
+            if name.eq_ignore_ascii_case("host")
+                || self
+                    .config
+                    .headers
+                    .keys()
+                    .any(|h| h.eq_ignore_ascii_case(name))
+            {
+                continue;
+            }
+            rewritten.push_str(line);
+            rewritten.push_str("\r\n");
+        }
+        rewritten.push_str(&self.extra_headers);
+        rewritten.push_str("\r\n");
+        Ok(rewritten)
+    }
+
+    // 直连或经代理建立到节点的 TCP 连接
+    async fn connect(&self) -> Result<TcpStream> {
+        let Some(proxy) = &self.config.proxy else {
+            return Ok(TcpStream::connect((self.host.as_str(), self.port)).await?);
+        };
+
+        let proxy = Url::parse(proxy)?;
+        let proxy_addr = (
+            proxy
+                .host_str()
+                .ok_or_else(|| anyhow!("代理地址缺少主机名 / Proxy URL has no host"))?
+                .to_string(),
+            proxy.port_or_known_default().unwrap_or(1080),
+        );
+        let socket = TcpStream::connect((proxy_addr.0.as_str(), proxy_addr.1)).await?;
+
+        match proxy.scheme() {
+            scheme @ ("socks5" | "socks5h") => {
+                let target = self.socks_target(scheme).await?;
+                let stream = if proxy.username().is_empty() {
+                    Socks5Stream::connect_with_socket(socket, target).await?
+                } else {
+                    Socks5Stream::connect_with_password_and_socket(
+                        socket,
+                        target,
+                        proxy.username(),
+                        proxy.password().unwrap_or_default(),
+                    )
+                    .await?
+                };
+                Ok(stream.into_inner())
+            }
+            "http" => http_connect(socket, &self.authority_host(), self.port).await,
+            scheme => Err(anyhow!(
+                "不支持的代理类型 / Unsupported proxy scheme: {}",
+                scheme
+            )),
+        }
+    }
+
+    // 校验证书所用的名称，IP 地址的节点按 IP 校验
+    fn server_name(&self) -> Result<ServerName> {
+        Ok(match self.ip {
+            Some(ip) => ServerName::IpAddress(ip),
+            None => ServerName::try_from(self.host.as_str())?,
+        })
+    }
+
+    // Host 头与 CONNECT 请求中的主机，IPv6 地址加上方括号
+    fn authority_host(&self) -> String {
+        match self.ip {
+            Some(IpAddr::V6(ip)) => format!("[{}]", ip),
+            _ => self.host.clone(),
+        }
+    }
+
+    // socks5 在本地解析主机名后把 IP 交给代理，socks5h 把主机名交给代理解析
+    async fn socks_target(&self, scheme: &str) -> Result<TargetAddr<'_>> {
+        if let Some(ip) = self.ip {
+            return Ok(TargetAddr::Ip(SocketAddr::new(ip, self.port)));
+        }
+        if scheme == "socks5h" {
+            return Ok(TargetAddr::Domain(self.host.as_str().into(), self.port));
+        }
+        let addr = tokio::net::lookup_host((self.host.as_str(), self.port))
+            .await?
+            .next()
+            .ok_or_else(|| anyhow!("无法解析主机名 / Cannot resolve host {}", self.host))?;
+        Ok(TargetAddr::Ip(addr))
+    }
+}
+
+fn tls_connector(config: &EndpointConfig) -> Result<TlsConnector> {
+    let mut roots = RootCertStore::empty();
+    roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|ta| {
+        OwnedTrustAnchor::from_subject_spki_name_constraints(
+            ta.subject,
+            ta.spki,
+            ta.name_constraints,
+        )
+    }));
+    if let Some(pem) = config.ca_cert_pem()? {
+        for cert in rustls_pemfile::certs(&mut pem.as_slice())? {
+            roots.add(&rustls::Certificate(cert))?;
+        }
+    }
+
+    let tls_config = rustls::ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    Ok(TlsConnector::from(Arc::new(tls_config)))
+}
+
+// 通过 HTTP 代理的 CONNECT 方法建立隧道
+async fn http_connect(mut socket: TcpStream, host: &str, port: u16) -> Result<TcpStream> {
+    socket
+        .write_all(
+            format!(
+                "CONNECT {}:{} HTTP/1.1\r\nHost: {}:{}\r\n\r\n",
+                host, port, host, port
+            )
+            .as_bytes(),
+        )
+        .await?;
+
+    let response = read_head(&mut socket).await?;
+    let status = response.lines().next().unwrap_or_default();
+    if status.split_whitespace().nth(1) != Some("200") {
+        return Err(anyhow!("代理拒绝连接 / Proxy refused CONNECT: {}", status));
+    }
+    Ok(socket)
+}
+
+async fn forward<S>(mut client: TcpStream, mut upstream: S, head: String) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    upstream.write_all(head.as_bytes()).await?;
+    match tokio::io::copy_bidirectional(&mut client, &mut upstream).await {
+        Ok(_) => Ok(()),
+        // 任一方断开连接都是正常结束
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn upstream(url: &str) -> Upstream {
+        Upstream::new(EndpointConfig {
+            url: url.to_string(),
+            headers: BTreeMap::from([("x-api-key".to_string(), "secret".to_string())]),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    fn handshake(path: &str) -> String {
+        format!(
+            "GET {} HTTP/1.1\r\nHost: 127.0.0.1:1234\r\nUpgrade: websocket\r\nX-Api-Key: old\r\n\r\n",
+            path
+        )
+    }
+
+    #[test]
+    fn rewrite_head_targets_the_real_endpoint() {
+        let upstream = upstream("ws://node.example.com:8546/rpc?key=1");
+        let head = upstream
+            .rewrite_head(&handshake(&upstream.relay_path))
+            .unwrap();
+
+        assert!(head.starts_with("GET /rpc?key=1 HTTP/1.1\r\nHost: node.example.com:8546\r\n"));
+        assert!(head.contains("Upgrade: websocket\r\n"));
+        assert!(head.contains("x-api-key: secret\r\n"));
+        assert!(!head.contains("X-Api-Key: old"));
+        assert!(head.ends_with("\r\n\r\n"));
+    }
+
+    #[test]
+    fn rewrite_head_rejects_other_paths() {
+        let upstream = upstream("ws://node.example.com/rpc");
+        assert!(upstream.rewrite_head(&handshake("/")).is_err());
+        assert!(upstream.rewrite_head(&handshake("/rpc")).is_err());
+        assert!(upstream
+            .rewrite_head(&format!("POST {} HTTP/1.1\r\n\r\n", upstream.relay_path))
+            .is_err());
+    }
+
+    #[test]
+    fn relay_paths_are_random() {
+        let a = upstream("ws://node.example.com/rpc");
+        let b = upstream("ws://node.example.com/rpc");
+        assert_ne!(a.relay_path, b.relay_path);
+        assert_eq!(a.relay_path.len(), 33);
+    }
+
+    #[tokio::test]
+    async fn socks5_resolves_locally_and_socks5h_remotely() {
+        let upstream = upstream("ws://localhost:8546/");
+        assert!(matches!(
+            upstream.socks_target("socks5").await.unwrap(),
+            TargetAddr::Ip(addr) if addr.ip().is_loopback() && addr.port() == 8546
+        ));
+        assert!(matches!(
+            upstream.socks_target("socks5h").await.unwrap(),
+            TargetAddr::Domain(host, 8546) if host == "localhost"
+        ));
+
+        let upstream = self::upstream("ws://[::1]:8546/");
+        for scheme in ["socks5", "socks5h"] {
+            assert!(matches!(
+                upstream.socks_target(scheme).await.unwrap(),
+                TargetAddr::Ip(addr) if addr == "[::1]:8546".parse().unwrap()
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn ipv6_endpoints_are_reachable() {
+        let upstream = upstream("ws://[::1]:8546/rpc");
+        assert_eq!(upstream.host, "::1");
+        let head = upstream
+            .rewrite_head(&handshake(&upstream.relay_path))
+            .unwrap();
+        assert!(head.starts_with("GET /rpc HTTP/1.1\r\nHost: [::1]:8546\r\n"));
+
+        let tls = self::upstream("wss://[::1]:8546/rpc");
+        assert_eq!(
+            tls.server_name().unwrap(),
+            ServerName::IpAddress("::1".parse().unwrap())
+        );
+        assert!(matches!(
+            self::upstream("wss://node.example.com/rpc").server_name(),
+            Ok(ServerName::DnsName(_))
+        ));
+
+        let listener = TcpListener::bind("[::1]:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let upstream = self::upstream(&format!("ws://[::1]:{}/", port));
+        assert!(upstream.connect().await.is_ok());
+    }
+}